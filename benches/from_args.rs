@@ -0,0 +1,40 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_shon::{from_args, to_raw_params};
+
+#[derive(Deserialize, serde::Serialize)]
+struct Entry {
+    name: String,
+    id: u64,
+    tags: Vec<String>,
+}
+
+const ITERATIONS: u32 = 20;
+
+// Usage: `cargo bench --bench from_args`
+fn main() {
+    let entries: Vec<Entry> = (0..20_000)
+        .map(|id| Entry {
+            name: format!("entry number {}", id),
+            id,
+            tags: vec!["a".to_string(), "b".to_string()],
+        })
+        .collect();
+    let mut args = to_raw_params(&entries).unwrap();
+    args.insert(0, "./binary".to_string());
+    println!("{} args", args.len());
+
+    // the copies of the input are made up front to only time the parsing
+    let inputs: Vec<Vec<String>> = (0..ITERATIONS).map(|_| args.clone()).collect();
+    let start = Instant::now();
+    for input in inputs {
+        let parsed: Vec<Entry> = from_args(black_box(input).into_iter()).unwrap();
+        black_box(parsed);
+    }
+    println!(
+        "from_args: {:?} per iteration",
+        start.elapsed() / ITERATIONS
+    );
+}
@@ -0,0 +1,40 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_shon::to_raw_params;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    id: u64,
+    enabled: bool,
+    owner: Option<String>,
+    tags: Vec<String>,
+}
+
+const ITERATIONS: u32 = 20;
+
+// Usage: `cargo bench --bench to_params`
+fn main() {
+    let entries: Vec<Entry> = (0..20_000)
+        .map(|id| Entry {
+            name: format!("entry number {}", id),
+            id,
+            enabled: id % 2 == 0,
+            owner: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+        })
+        .collect();
+    println!("{} args", to_raw_params(&entries).unwrap().len());
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let params = to_raw_params(black_box(&entries)).unwrap();
+        black_box(params);
+    }
+    println!(
+        "to_raw_params: {:?} per iteration",
+        start.elapsed() / ITERATIONS
+    );
+}
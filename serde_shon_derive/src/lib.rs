@@ -0,0 +1,212 @@
+//! The `#[shon]` attribute of `serde_shon`, to be enabled through its
+//! `derive` feature rather than used directly.
+
+use proc_macro::TokenStream;
+use quote::ToTokens;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, LitStr, Type};
+
+/// Configures how `serde_shon` handles the fields of a struct. Has to be
+/// placed above the serde derives, which then see the matching serde
+/// attributes:
+///
+/// - `#[shon(flag)]`: a `bool` field that is `true` when its key is given
+///   without a value, and `false` when it is missing.
+/// - `#[shon(positional)]`: the field is taken from a bare value at the start
+///   of the object, in declaration order, instead of from a key.
+/// - `#[shon(alias = "-o")]`: an additional key for the field, which may also
+///   be a short `-o` style key.
+/// - `#[shon(rest)]`: the field takes the params after a `--` that ends the
+///   options, see `DoubleDash::EndOfOptions`, or after the document, see
+///   `DeserializerOptions::trailing_rest`. Otherwise it is keyed as usual.
+///   Missing params leave it to its default.
+/// - `#[shon(nested)]`: an array or object is written as a single param
+///   holding its document, to be read back with nested documents enabled.
+/// - `#[shon(secret)]`: the value is replaced by `***` in shell strings meant
+///   for logging, but kept in params meant for execution.
+///
+/// Structs flattened with `#[serde(flatten)]` are read like the fields of
+/// the struct they are flattened into, flags and key styles included,
+/// rather than from what serde buffered of their keys. Without `#[shon]` on
+/// the struct they are flattened into, only the keys given for their fields
+/// as they are spelled reach them: flags that aren't given are missing, and
+/// neither key styles nor `--no-<name>` apply to them.
+#[proc_macro_attribute]
+pub fn shon(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if !args.is_empty() {
+        return syn::Error::new(
+            proc_macro2::TokenStream::from(args)
+                .into_iter()
+                .next()
+                .unwrap()
+                .span(),
+            "#[shon] takes no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    match expand(&mut input) {
+        Ok(()) => input.into_token_stream().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    flag: bool,
+    positional: bool,
+    rest: bool,
+    secret: bool,
+    nested: bool,
+    aliases: Vec<LitStr>,
+}
+
+fn expand(input: &mut DeriveInput) -> syn::Result<()> {
+    let fields = match &mut input.data {
+        Data::Struct(data) => match &mut data.fields {
+            Fields::Named(fields) => &mut fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, UNSUPPORTED)),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, UNSUPPORTED)),
+    };
+
+    let mut keyed = false;
+    for field in fields.iter_mut() {
+        let mut options = Options::default();
+        let mut attrs = Vec::new();
+        for attr in field.attrs.drain(..) {
+            if attr.path().is_ident("shon") {
+                parse_options(&attr, &mut options)?;
+            } else {
+                attrs.push(attr);
+            }
+        }
+        field.attrs = attrs;
+        let ident = field.ident.as_ref().unwrap();
+
+        if options.positional {
+            if keyed {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "positional fields have to come before all other fields",
+                ));
+            }
+            if !options.aliases.is_empty() || field.attrs.iter().any(renames) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "positional fields can't be renamed or aliased",
+                ));
+            }
+            let name = format!("$shon:positional:{}", ident);
+            field.attrs.push(parse_quote!(#[serde(rename = #name)]));
+        } else {
+            keyed = true;
+        }
+
+        if options.rest {
+            if options.positional || options.flag || !options.aliases.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "rest fields can't be positional, flags or aliased",
+                ));
+            }
+            if field.attrs.iter().any(renames) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "rest fields can't be renamed",
+                ));
+            }
+            let name = format!("$shon:rest:{}", ident);
+            field
+                .attrs
+                .push(parse_quote!(#[serde(rename = #name, default)]));
+        }
+
+        if options.flag {
+            if !is_bool(&field.ty) {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "flag fields have to be bool",
+                ));
+            }
+            field.attrs.push(parse_quote!(#[serde(
+                default,
+                deserialize_with = "::serde_shon::private::deserialize_flag"
+            )]));
+        }
+        if options.nested {
+            if options.secret {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "nested fields can't be secret",
+                ));
+            }
+            field.attrs.push(parse_quote!(#[serde(
+                serialize_with = "::serde_shon::private::serialize_nested"
+            )]));
+        }
+        if options.secret {
+            field.attrs.push(parse_quote!(#[serde(
+                serialize_with = "::serde_shon::private::serialize_secret"
+            )]));
+        }
+        if field.attrs.iter().any(flattens) && !field.attrs.iter().any(deserializes_with) {
+            // read from the params rather than from what serde buffered
+            field.attrs.push(parse_quote!(#[serde(
+                deserialize_with = "::serde_shon::private::deserialize_flattened"
+            )]));
+        }
+        for alias in options.aliases {
+            let value = alias.value();
+            if matches!(value.as_str(), "" | "-" | "--" | "-t" | "-f" | "-n") {
+                return Err(syn::Error::new_spanned(alias, "reserved token as alias"));
+            }
+            // long keys lose their dashes while parsing, short ones keep them
+            let key = value.strip_prefix("--").unwrap_or(&value);
+            field.attrs.push(parse_quote!(#[serde(alias = #key)]));
+        }
+    }
+    Ok(())
+}
+
+const UNSUPPORTED: &str = "#[shon] only supports structs with named fields";
+
+fn parse_options(attr: &Attribute, options: &mut Options) -> syn::Result<()> {
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("flag") {
+            options.flag = true;
+        } else if meta.path.is_ident("positional") {
+            options.positional = true;
+        } else if meta.path.is_ident("nested") {
+            options.nested = true;
+        } else if meta.path.is_ident("rest") {
+            options.rest = true;
+        } else if meta.path.is_ident("secret") {
+            options.secret = true;
+        } else if meta.path.is_ident("alias") {
+            options.aliases.push(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("unknown shon attribute"));
+        }
+        Ok(())
+    })
+}
+
+fn renames(attr: &Attribute) -> bool {
+    attr.path().is_ident("serde") && {
+        let tokens = attr.meta.to_token_stream().to_string();
+        tokens.contains("rename") || tokens.contains("alias")
+    }
+}
+
+fn flattens(attr: &Attribute) -> bool {
+    attr.path().is_ident("serde") && attr.meta.to_token_stream().to_string().contains("flatten")
+}
+
+fn deserializes_with(attr: &Attribute) -> bool {
+    attr.path().is_ident("serde") && attr.meta.to_token_stream().to_string().contains("with")
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("bool"))
+}
@@ -0,0 +1,179 @@
+// Checks of serialized params for anything a shell would act on, for
+// reviewing scripts that paste SHON output into larger commands without
+// going through `to_string`.
+
+use crate::Shell;
+
+/// Why a character of a param would be interpreted by the shell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Hazard {
+    /// `$name` and friends, which are replaced by a variable.
+    Expansion,
+    /// `$(...)` or a backtick, running another command.
+    CommandSubstitution,
+    /// `;`, `&` or `|`, ending the command or starting another one.
+    Separator,
+    /// `<` or `>`, reading or writing a file.
+    Redirection,
+    /// Parens and braces, grouping commands or building values.
+    Grouping,
+    /// `*`, `?` and bracket expressions, matching file names.
+    Glob,
+    /// `~` at the start of a param, replaced by a home directory.
+    Tilde,
+    /// `#` at the start of a param, dropping the rest of the line.
+    Comment,
+    /// Quotes and backslashes, changing how the following text is read.
+    Quote,
+    /// Whitespace, splitting the param into several.
+    WordSplit,
+    /// A line break, which ends the command.
+    LineBreak,
+}
+
+/// A single place in the params that needs quoting for the target shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Index of the param in the audited list.
+    pub param: usize,
+    /// Byte offset of the character within the param.
+    pub offset: usize,
+    pub hazard: Hazard,
+}
+
+/// Lists everything in the params that the given shell would interpret if
+/// they were pasted into a command without quoting. An empty list means the
+/// params are safe to use as they are.
+///
+/// ```
+/// use serde_shon::{audit_shell_safety, Finding, Hazard, Shell};
+///
+/// let params = serde_shon::to_raw_params(&["ok", "$(id)"]).unwrap();
+/// assert_eq!(
+///     audit_shell_safety(&params, Shell::Posix),
+///     [
+///         Finding { param: 2, offset: 0, hazard: Hazard::CommandSubstitution },
+///         Finding { param: 2, offset: 1, hazard: Hazard::Grouping },
+///         Finding { param: 2, offset: 4, hazard: Hazard::Grouping },
+///     ]
+/// );
+/// ```
+pub fn audit_shell_safety<T>(args: &[T], shell: Shell) -> Vec<Finding>
+where
+    T: AsRef<str>,
+{
+    let mut findings = Vec::new();
+    for (param, arg) in args.iter().enumerate() {
+        let arg = arg.as_ref();
+        for (offset, c) in arg.char_indices() {
+            let rest = &arg[offset + c.len_utf8()..];
+            let hazard = match shell {
+                Shell::Posix | Shell::Make | Shell::Dockerfile => posix(c, offset, rest),
+                Shell::Nushell => nushell(c, offset),
+            };
+            if let Some(hazard) = hazard {
+                findings.push(Finding {
+                    param,
+                    offset,
+                    hazard,
+                });
+            }
+        }
+    }
+    findings
+}
+
+fn posix(c: char, offset: usize, rest: &str) -> Option<Hazard> {
+    Some(match c {
+        '$' if rest.starts_with('(') => Hazard::CommandSubstitution,
+        '$' => Hazard::Expansion,
+        '`' => Hazard::CommandSubstitution,
+        ';' | '&' | '|' => Hazard::Separator,
+        '<' | '>' => Hazard::Redirection,
+        '(' | ')' | '{' | '}' => Hazard::Grouping,
+        '*' | '?' => Hazard::Glob,
+        '[' if is_bracket_expression(rest) => Hazard::Glob,
+        '~' if offset == 0 => Hazard::Tilde,
+        '#' if offset == 0 => Hazard::Comment,
+        '\'' | '"' | '\\' => Hazard::Quote,
+        '\n' | '\r' => Hazard::LineBreak,
+        c if c.is_whitespace() => Hazard::WordSplit,
+        _ => return None,
+    })
+}
+
+// Only a complete bracket expression matches files, which rules out the lone
+// brackets of arrays and `[]`, as a `]` right after the opening bracket is
+// part of the expression rather than its end.
+fn is_bracket_expression(rest: &str) -> bool {
+    let rest = rest.strip_prefix(['!', '^']).unwrap_or(rest);
+    let mut chars = rest.chars();
+    chars.next().is_some() && chars.as_str().contains(']')
+}
+
+fn nushell(c: char, offset: usize) -> Option<Hazard> {
+    Some(match c {
+        '$' => Hazard::Expansion,
+        '`' | '\'' | '"' => Hazard::Quote,
+        ';' | '|' => Hazard::Separator,
+        '(' | ')' | '{' | '}' | '[' | ']' => Hazard::Grouping,
+        '*' | '?' => Hazard::Glob,
+        '~' if offset == 0 => Hazard::Tilde,
+        '#' if offset == 0 => Hazard::Comment,
+        '\n' | '\r' => Hazard::LineBreak,
+        c if c.is_whitespace() => Hazard::WordSplit,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hazards(args: &[&str], shell: Shell) -> Vec<Hazard> {
+        audit_shell_safety(args, shell)
+            .into_iter()
+            .map(|f| f.hazard)
+            .collect()
+    }
+
+    #[test]
+    fn audit() {
+        let safe = ["[", "--name", "shon", "-1.5", "]", "[]", "a~b#c"];
+        assert!(audit_shell_safety(&safe, Shell::Posix).is_empty());
+        assert_eq!(hazards(&["[--]"], Shell::Posix), [Hazard::Glob]);
+        assert_eq!(
+            hazards(&["a b;c", "~/x", "#x", "`id`", "$HOME>f"], Shell::Posix),
+            [
+                Hazard::WordSplit,
+                Hazard::Separator,
+                Hazard::Tilde,
+                Hazard::Comment,
+                Hazard::CommandSubstitution,
+                Hazard::CommandSubstitution,
+                Hazard::Expansion,
+                Hazard::Redirection,
+            ]
+        );
+        assert_eq!(
+            audit_shell_safety(&["x", "é'\n"], Shell::Dockerfile),
+            [
+                Finding {
+                    param: 1,
+                    offset: 2,
+                    hazard: Hazard::Quote
+                },
+                Finding {
+                    param: 1,
+                    offset: 3,
+                    hazard: Hazard::LineBreak
+                },
+            ]
+        );
+        assert_eq!(
+            hazards(&["[", "]", "a&b", "$env"], Shell::Nushell),
+            [Hazard::Grouping, Hazard::Grouping, Hazard::Expansion]
+        );
+    }
+}
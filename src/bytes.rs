@@ -0,0 +1,132 @@
+// Byte strings written as a single `hex:` or `base64:` param, instead of an
+// array of numbers.
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How `Serializer` writes byte strings, see `Serializer::bytes_encoding`.
+/// Whatever the encoding, byte strings are read from all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum BytesEncoding {
+    /// An array of numbers, like `[ 1 255 ]`.
+    #[default]
+    Array,
+    /// A single param of lowercase hex digits, like `hex:01ff`.
+    Hex,
+    /// A single param of standard, padded base64, like `base64:Af8=`.
+    Base64,
+}
+
+pub(crate) fn encode(bytes: &[u8], encoding: BytesEncoding) -> Option<String> {
+    match encoding {
+        BytesEncoding::Array => None,
+        BytesEncoding::Hex => {
+            let mut out = String::with_capacity(4 + bytes.len() * 2);
+            out.push_str("hex:");
+            for byte in bytes {
+                out.push(HEX[usize::from(byte >> 4)].into());
+                out.push(HEX[usize::from(byte & 0xf)].into());
+            }
+            Some(out)
+        }
+        BytesEncoding::Base64 => {
+            let mut out = String::with_capacity(7 + bytes.len().div_ceil(3) * 4);
+            out.push_str("base64:");
+            for chunk in bytes.chunks(3) {
+                let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+                    group | u32::from(byte) << (16 - 8 * i)
+                });
+                for i in 0..4 {
+                    let digit = (group >> (18 - 6 * i) & 0x3f) as usize;
+                    out.push(if i <= chunk.len() {
+                        BASE64[digit].into()
+                    } else {
+                        '='
+                    });
+                }
+            }
+            Some(out)
+        }
+    }
+}
+
+// The bytes of a `hex:` or `base64:` param, or `None` for any other param.
+// Hex digits may be in any case, and the base64 padding is optional.
+pub(crate) fn decode(param: &str) -> Option<Result<Vec<u8>, String>> {
+    if let Some(digits) = param.strip_prefix("hex:") {
+        let digits = digits.as_bytes();
+        if digits.len() % 2 != 0 {
+            return Some(Err("odd number of hex digits".to_string()));
+        }
+        let bytes = digits.chunks(2).map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16)
+                .ok()
+                .filter(|_| !pair.starts_with('+'))
+        });
+        return Some(
+            bytes
+                .collect::<Option<_>>()
+                .ok_or("invalid hex digit".to_string()),
+        );
+    }
+    let digits = param.strip_prefix("base64:")?.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    let (mut group, mut bits) = (0u32, 0);
+    for digit in digits.bytes() {
+        let Some(value) = BASE64.iter().position(|&d| d == digit) else {
+            return Some(Err("invalid base64 digit".to_string()));
+        };
+        group = group << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+    // a single digit left over doesn't make a byte
+    if bits >= 6 {
+        return Some(Err("invalid base64 length".to_string()));
+    }
+    Some(Ok(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        for bytes in [
+            &b""[..],
+            b"\x01",
+            b"\x01\xff",
+            b"abc",
+            b"\x00\x10\x83\x10\x51",
+        ] {
+            for encoding in [BytesEncoding::Hex, BytesEncoding::Base64] {
+                let param = encode(bytes, encoding).unwrap();
+                assert_eq!(decode(&param), Some(Ok(bytes.to_vec())), "{}", param);
+            }
+        }
+        assert_eq!(encode(b"\x01\xff", BytesEncoding::Hex).unwrap(), "hex:01ff");
+        assert_eq!(
+            encode(b"\x01\xff", BytesEncoding::Base64).unwrap(),
+            "base64:Af8="
+        );
+        assert_eq!(encode(b"abc", BytesEncoding::Array), None);
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(decode("hex:0A0b"), Some(Ok(vec![10, 11])));
+        assert_eq!(decode("base64:Af8"), Some(Ok(vec![1, 255])));
+        assert_eq!(decode("0a0b"), None);
+        assert!(matches!(decode("hex:0"), Some(Err(_))));
+        assert!(matches!(decode("hex:+1"), Some(Err(_))));
+        assert!(matches!(decode("hex:zz"), Some(Err(_))));
+        assert!(matches!(decode("base64:A"), Some(Err(_))));
+        assert!(matches!(decode("base64:A!"), Some(Err(_))));
+    }
+}
@@ -0,0 +1,121 @@
+// Conversion of parsed clap arguments into a SHON value, enabled by the
+// `clap` feature.
+
+use ::clap::parser::ValuesRef;
+use ::clap::{ArgMatches, Id};
+
+use crate::value::{Map, Number, Value};
+
+/// Walks the parsed arguments of a clap command into an object, so a clap CLI
+/// can pass on its options to other tools as SHON.
+///
+/// Every argument becomes a key named after its id. Arguments with a single
+/// value turn into that value, while arguments with several values, like
+/// repeated or multi-valued options, turn into an array. Flags become booleans
+/// and counted flags become numbers. Values of other types keep the string
+/// they were parsed from. An invoked subcommand becomes a nested object under
+/// its name.
+///
+/// ```ignore
+/// let matches = cli().get_matches();
+/// println!("{}", serde_shon::from_arg_matches(&matches).to_shell_string()?);
+/// ```
+pub fn from_arg_matches(matches: &ArgMatches) -> Value {
+    let mut map = Map::new();
+    for id in matches.ids() {
+        // groups hold the ids of their arguments, which are present already
+        if matches.try_get_many::<Id>(id.as_str()).is_ok() {
+            continue;
+        }
+        if let Some(value) = arg_value(matches, id.as_str()) {
+            map.insert(id.to_string(), value);
+        }
+    }
+    if let Some((name, sub)) = matches.subcommand() {
+        map.insert(name.to_string(), from_arg_matches(sub));
+    }
+    Value::Object(map)
+}
+
+fn arg_value(matches: &ArgMatches, id: &str) -> Option<Value> {
+    if let Ok(values) = matches.try_get_many::<bool>(id) {
+        return values.map(|v| collect(v, |b| Value::Bool(*b)));
+    }
+    if let Ok(values) = matches.try_get_many::<u8>(id) {
+        return values.map(|v| collect(v, |n| Value::Number(Number::from(*n as u64))));
+    }
+    if let Ok(values) = matches.try_get_many::<String>(id) {
+        return values.map(|v| collect(v, |s| Value::String(s.clone())));
+    }
+    let values = matches.try_get_raw(id).ok()??;
+    let mut values: Vec<Value> = values
+        .map(|raw| Value::String(raw.to_string_lossy().into_owned()))
+        .collect();
+    Some(match values.len() {
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    })
+}
+
+fn collect<T, F>(values: ValuesRef<'_, T>, f: F) -> Value
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(&T) -> Value,
+{
+    let mut values: Vec<Value> = values.map(f).collect();
+    match values.len() {
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::clap::{value_parser, Arg, ArgAction, ArgGroup, Command};
+
+    use super::*;
+
+    fn cli() -> Command {
+        Command::new("tool")
+            .arg(Arg::new("verbose").short('v').action(ArgAction::Count))
+            .arg(
+                Arg::new("dry_run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(Arg::new("name").long("name"))
+            .arg(Arg::new("tag").long("tag").action(ArgAction::Append))
+            .arg(
+                Arg::new("port")
+                    .long("port")
+                    .value_parser(value_parser!(u16)),
+            )
+            .group(ArgGroup::new("naming").args(["name", "tag"]).multiple(true))
+            .subcommand(Command::new("run").arg(Arg::new("target").required(true)))
+    }
+
+    #[test]
+    fn arg_matches() {
+        let matches = cli()
+            .try_get_matches_from([
+                "tool",
+                "-vv",
+                "--name=-x",
+                "--tag",
+                "a",
+                "--tag",
+                "b",
+                "--port",
+                "80",
+                "run",
+                "all",
+            ])
+            .unwrap();
+        let value = from_arg_matches(&matches);
+        assert_eq!(
+            value.to_raw_params().unwrap().join(" "),
+            "[ --verbose 2 --name -- -x --tag [ a b ] --port -- 80 --dry_run -f \
+             --run [ --target all ] ]"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+// A config-rs source for command line SHON, enabled by the `config` feature.
+
+use ::config::{ConfigError, Map, Source, ValueKind};
+
+use crate::Value;
+
+const ORIGIN: &str = "SHON arguments";
+
+/// A `config::Source` feeding a SHON object into the config merge pipeline.
+///
+/// ```ignore
+/// let config: Config = config::Config::builder()
+///     .add_source(config::File::with_name("config"))
+///     .add_source(ShonSource::args()?)
+///     .build()?
+///     .try_deserialize()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShonSource {
+    value: Value,
+}
+
+impl ShonSource {
+    /// Parses the params from `env::args()`, skipping the binary.
+    pub fn args() -> crate::Result<Self> {
+        Self::from_args(std::env::args())
+    }
+
+    /// Parses the params from an `env::args()` style iterator, skipping the
+    /// binary. No params at all make for an empty source.
+    pub fn from_args<I>(iter: I) -> crate::Result<Self>
+    where
+        I: Iterator<Item = String>,
+    {
+        Ok(Self::new(crate::from_args(iter)?))
+    }
+
+    /// Uses an already parsed document, which has to be an object or null.
+    pub fn new(value: Value) -> Self {
+        ShonSource { value }
+    }
+}
+
+impl Source for ShonSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, ::config::Value>, ConfigError> {
+        match &self.value {
+            Value::Null => Ok(Map::new()),
+            Value::Object(o) => Ok(o.iter().map(|(k, v)| (k.to_string(), convert(v))).collect()),
+            _ => Err(ConfigError::Message(format!(
+                "{} must be an object",
+                ORIGIN
+            ))),
+        }
+    }
+}
+
+fn convert(value: &Value) -> ::config::Value {
+    let origin = ORIGIN.to_string();
+    let kind = match value {
+        Value::Null => ValueKind::Nil,
+        Value::Bool(b) => ValueKind::Boolean(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => ValueKind::U64(u),
+            (_, Some(i)) => ValueKind::I64(i),
+            _ => ValueKind::Float(n.as_f64()),
+        },
+        Value::String(s) => ValueKind::String(s.clone()),
+        Value::Array(a) => ValueKind::Array(a.iter().map(convert).collect()),
+        Value::Object(o) => {
+            ValueKind::Table(o.iter().map(|(k, v)| (k.to_string(), convert(v))).collect())
+        }
+    };
+    ::config::Value::new(Some(&origin), kind)
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        host: String,
+        port: u16,
+        tls: Tls,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Tls {
+        enabled: bool,
+        ratio: f64,
+    }
+
+    #[test]
+    fn merges_over_defaults() {
+        let args = [
+            "./binary",
+            "[",
+            "--port",
+            "8080",
+            "--tls",
+            "[",
+            "--enabled",
+            "-t",
+            "]",
+            "]",
+        ];
+        let config: Config = ::config::Config::builder()
+            .set_default("host", "localhost")
+            .unwrap()
+            .set_default("port", 80)
+            .unwrap()
+            .set_default("tls.ratio", 0.5)
+            .unwrap()
+            .add_source(ShonSource::from_args(args.into_iter().map(String::from)).unwrap())
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                host: "localhost".to_string(),
+                port: 8080,
+                tls: Tls {
+                    enabled: true,
+                    ratio: 0.5
+                },
+            }
+        );
+
+        assert!(ShonSource::new(Value::Null).collect().unwrap().is_empty());
+        assert!(ShonSource::new(Value::Bool(true)).collect().is_err());
+    }
+}
@@ -1,16 +1,102 @@
 // https://serde.rs/impl-deserializer.html
 
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::io::{self, BufRead, Read};
+use std::iter;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::rc::Rc;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
 use serde::de::{
-    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
-    Visitor,
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
 };
 use serde::{forward_to_deserialize_any, Deserialize};
 
-use crate::{Error, Result};
+use crate::error::{MISSING_SHOWN, TRAILING_SHOWN};
+use crate::files::ResponseFiles;
+use crate::options::DEFAULT_MAX_DEPTH;
+use crate::parse::{
+    self, classify, is_short_key, InlineValues, LaterKeys, Scalar, Stream, Token, Tokens, Wide,
+};
+use crate::source_map::{Recorder, SourceMap};
+use crate::type_config::{self, KeyStyle, TypeConfig};
+use crate::{bytes, private, words, DeserializerOptions, DuplicateKeys, Error, Result};
 
-pub struct Deserializer {
-    args: Vec<String>,
-    empty: bool,
+pub struct Deserializer<'de> {
+    tokens: Tokens<'de>,
+    options: DeserializerOptions,
+    // required struct fields found missing by earlier attempts at the same
+    // params, by the fields of their struct, which are filled with
+    // placeholders in every struct with those fields to get at the next one
+    missing: Vec<(&'static [&'static str], &'static str)>,
+    // where they were filled in, by the pointer of their struct
+    found: Vec<(String, &'static str)>,
+    // the innermost required field that this attempt found missing, and the
+    // fields of its struct
+    failed: Option<MissingField>,
+    failed_in: &'static [&'static str],
+    // bool fields found missing by earlier attempts, which are `false` in
+    // flag mode, see `DeserializerOptions::flags`
+    absent: Vec<MissingField>,
+    // in flag mode, whether the fields a struct ends without are tried as
+    // absent flags right away, see `Probe`
+    probing: bool,
+    // the bool fields found absent that way
+    probed: Vec<MissingField>,
+    // the names of fields found to be those of the same field, and the
+    // field tried last, until its value is read
+    aliases: Vec<Alias>,
+    probe: Option<(&'static [&'static str], &'static str)>,
+    // whether a field was tried that way, and whether something other than
+    // a bool stood in for one, which leaves the params to be read again
+    tried: bool,
+    stood_in: bool,
+    // the id of the outermost struct, which tells it apart from the others,
+    // or `usize::MAX` when this isn't the outermost deserializer
+    start: usize,
+    // the params after the end of the options, until a field takes them
+    rest: Option<Vec<String>>,
+    // where the values came from, when asked for a source map
+    recorder: Option<Recorder>,
+    // the positions of the params after the end of the options
+    rest_range: Range<usize>,
+    // set when reading a struct from the object it is flattened into, with
+    // its fields once it started, see `deserialize_flattened`
+    flattened: Option<&'static [&'static str]>,
+    // the number of arrays and objects the next value is nested in
+    depth: usize,
+    // the value in front is the only one of a repeated key, which is an
+    // array of just itself where one is expected, see
+    // `DeserializerOptions::repeated_keys`
+    single: bool,
+}
+
+// A struct field, with the struct identified by the number of params taken
+// before it started, which is the same on every attempt.
+type MissingField = (usize, &'static str);
+
+// Another name of a field and the field, for structs with the given fields,
+// or the field twice when it was given by a name that isn't known.
+type Alias = (&'static [&'static str], &'static str, &'static str);
+
+impl Deserializer<'_> {
+    /// The options to deserialize with, strict by default, which are handed
+    /// to `from_args_with` and the other `_with` functions.
+    ///
+    /// ```
+    /// use serde_shon::Deserializer;
+    ///
+    /// let options = Deserializer::builder().numbers_as_strings(true);
+    /// let zip: String = serde_shon::from_iter_with(["01234"].into_iter(), &options).unwrap();
+    /// assert_eq!(zip, "01234");
+    /// ```
+    pub fn builder() -> DeserializerOptions {
+        DeserializerOptions::new()
+    }
 }
 
 /// to be used with `env::args()` to get command line parameters parsed.
@@ -21,13 +107,7 @@ where
     I: Iterator<Item = String>,
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_args(iter.skip(1));
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.args.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::Message("premature cancel of parse".to_string()))
-    }
+    from_args_with(iter, &DeserializerOptions::default())
 }
 
 // to be used with any other string array
@@ -36,418 +116,5216 @@ where
     I: Iterator<Item = &'static str>,
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_iter(iter);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.args.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::Message("premature cancel of parse".to_string()))
+    from_iter_with(iter, &DeserializerOptions::default())
+}
+
+/// Like `from_args`, with the given options.
+pub fn from_args_with<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    deserialize_params(
+        PhantomData,
+        iter.skip(1).map(Cow::Owned),
+        options,
+        1,
+        false,
+        false,
+    )
+    .map(|(t, ..)| t)
+}
+
+/// Like `from_args`, but hands back the args after the document instead of
+/// failing on them, e.g. to pass them on to another program.
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Options {
+///     dir: String,
+/// }
+///
+/// let args = ["./binary", "[", "--dir", "/tmp", "]", "ls", "-l"];
+/// let (options, rest): (Options, _) =
+///     serde_shon::from_args_partial(args.map(String::from).into_iter()).unwrap();
+/// assert_eq!(options.dir, "/tmp");
+/// assert_eq!(rest, ["ls", "-l"]);
+/// ```
+pub fn from_args_partial<'a, T, I>(iter: I) -> Result<(T, Vec<String>)>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    from_args_partial_with(iter, &DeserializerOptions::default())
+}
+
+/// Like `from_args_partial`, with the given options.
+pub fn from_args_partial_with<'a, T, I>(
+    iter: I,
+    options: &DeserializerOptions,
+) -> Result<(T, Vec<String>)>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    let params = iter.skip(1).map(Cow::Owned);
+    deserialize_params(PhantomData, params, options, 1, false, true).map(|(t, _, rest)| (t, rest))
+}
+
+/// Like `from_args`, but drives the seed, for values that need state of
+/// their own to be deserialized.
+///
+/// The seed runs once on params that parse, unless flag mode is on. It is
+/// run again on a clone of it when a struct ends without fields other than
+/// bools in flag mode, to find out which of them are flags, and when fields are
+/// missing, to name all of them in the error. Only the last run counts, but
+/// whatever else the seed does happens on every run, so a seed with state
+/// has to be fine with seeing the same params more than once.
+///
+/// ```
+/// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+///
+/// #[derive(Clone, Copy)]
+/// struct Scaled(u32);
+///
+/// impl<'de> DeserializeSeed<'de> for Scaled {
+///     type Value = Vec<u32>;
+///
+///     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<u32>, D::Error> {
+///         let numbers = Vec::<u32>::deserialize(deserializer)?;
+///         Ok(numbers.into_iter().map(|n| n * self.0).collect())
+///     }
+/// }
+///
+/// let args = ["./binary", "[", "1", "2", "]"].map(String::from);
+/// let numbers = serde_shon::from_args_seed(args.into_iter(), Scaled(10)).unwrap();
+/// assert_eq!(numbers, [10, 20]);
+/// ```
+pub fn from_args_seed<'a, S, I>(iter: I, seed: S) -> Result<S::Value>
+where
+    I: Iterator<Item = String>,
+    S: DeserializeSeed<'a> + Clone,
+{
+    from_args_seed_with(iter, seed, &DeserializerOptions::default())
+}
+
+/// Like `from_args_seed`, with the given options.
+pub fn from_args_seed_with<'a, S, I>(
+    iter: I,
+    seed: S,
+    options: &DeserializerOptions,
+) -> Result<S::Value>
+where
+    I: Iterator<Item = String>,
+    S: DeserializeSeed<'a> + Clone,
+{
+    let params = iter.skip(1).map(Cow::Owned);
+    deserialize_params(seed, params, options, 1, false, false).map(|(value, ..)| value)
+}
+
+/// Parses params without copying them, so `&str` and `Cow<str>` fields can
+/// borrow from them. The binary isn't expected in front, like with
+/// `from_iter`.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Greeting<'a> {
+///     name: &'a str,
+///     #[serde(borrow)]
+///     text: Cow<'a, str>,
+/// }
+///
+/// let params = vec!["[", "--name", "shon", "--text", "hi", "]"];
+/// let greeting: Greeting = serde_shon::from_slice(&params).unwrap();
+/// assert_eq!(greeting.name, "shon");
+/// assert!(matches!(greeting.text, Cow::Borrowed("hi")));
+/// ```
+pub fn from_slice<'de, T>(params: &'de [&'de str]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_slice_with(params, &DeserializerOptions::default())
+}
+
+/// Like `from_slice`, with the given options.
+pub fn from_slice_with<'de, T>(params: &'de [&'de str], options: &DeserializerOptions) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let params = params.iter().map(|param| Cow::Borrowed(*param));
+    deserialize_params(PhantomData, params, options, 0, false, false).map(|(t, ..)| t)
+}
+
+/// Like `from_args`, for `env::args_os()`, which also hands over args that
+/// aren't valid UTF-8, like odd file names. Those are an error, unless
+/// `DeserializerOptions::lossy_utf8` is enabled.
+pub fn from_os_args<'a, T, I>(iter: I) -> Result<T>
+where
+    I: Iterator<Item = OsString>,
+    T: Deserialize<'a>,
+{
+    from_os_args_with(iter, &DeserializerOptions::default())
+}
+
+/// Like `from_os_args`, with the given options.
+pub fn from_os_args_with<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = OsString>,
+    T: Deserialize<'a>,
+{
+    let params = iter
+        .enumerate()
+        .skip(1)
+        .map(|(index, arg)| match arg.into_string() {
+            Ok(param) => Ok(param),
+            Err(arg) if options.lossy => Ok(arg.to_string_lossy().into_owned()),
+            Err(arg) => Err(Error::At {
+                index,
+                token: Some(arg.to_string_lossy().into_owned()),
+                error: Box::new(Error::Message("invalid UTF-8".to_string())),
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    deserialize_params(
+        PhantomData,
+        params.into_iter().map(Cow::Owned),
+        options,
+        1,
+        false,
+        false,
+    )
+    .map(|(t, ..)| t)
+}
+
+/// Like `from_iter`, with the given options.
+pub fn from_iter_with<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = &'static str>,
+    T: Deserialize<'a>,
+{
+    let params = iter.map(|param| -> Cow<'a, str> { param.into() });
+    deserialize_params(PhantomData, params, options, 0, false, false).map(|(t, ..)| t)
+}
+
+/// Parses params read from the iterator only as they are needed, so a huge
+/// document, like one coming in on stdin, is never held in memory as a
+/// whole. The binary isn't expected in front, like with `from_iter`.
+///
+/// ```
+/// use std::io::BufRead;
+///
+/// let input = "[\n1\n2\n3\n]\n";
+/// let params = input.as_bytes().lines().map_while(Result::ok);
+/// let numbers: Vec<u32> = serde_shon::from_stream(params).unwrap();
+/// assert_eq!(numbers, [1, 2, 3]);
+/// ```
+pub fn from_stream<'a, T, I>(iter: I) -> Result<T>
+where
+    I: Iterator<Item = String> + 'a,
+    T: Deserialize<'a>,
+{
+    from_stream_with(iter, &DeserializerOptions::default())
+}
+
+/// Like `from_stream`, with the given options. As params that were read
+/// are gone, only the first missing field is reported, errors only tell
+/// the index of the param they happened at, and `DoubleDash::EndOfOptions`
+/// isn't supported.
+pub fn from_stream_with<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = String> + 'a,
+    T: Deserialize<'a>,
+{
+    let stream = stream(iter.map(Cow::Owned), options);
+    let mut deserializer = Deserializer::from_stream(stream, options.clone());
+    let result = T::deserialize(&mut deserializer).and_then(|t| match deserializer.at_end() {
+        true => Ok(t),
+        false => Err(deserializer.tokens.trailing()),
+    });
+    // the params ending early is what made the deserializer fail then
+    if let Some(err) = deserializer.tokens.take_error() {
+        return Err(err);
     }
+    result.map_err(|err| match deserializer.failed {
+        // missing fields aren't at any arg in particular
+        Some(_) => err,
+        None => locate(err, &deserializer.tokens, &[], 0),
+    })
+}
+
+/// Parses one document after the other from the params of the iterator,
+/// which are read as they are needed like with `from_stream`. Every call to
+/// `next` parses one complete value, and the iterator ends with the params
+/// or after an error.
+///
+/// ```
+/// use serde_shon::StreamDeserializer;
+///
+/// let params = ["[", "1", "2", "]", "[", "]", "[", "3", "]"].map(String::from);
+/// let mut documents = StreamDeserializer::<Vec<u32>>::new(params.into_iter());
+/// assert_eq!(documents.next().unwrap().unwrap(), [1, 2]);
+/// assert_eq!(documents.position(), 4);
+/// assert_eq!(documents.next().unwrap().unwrap(), []);
+/// assert_eq!(documents.next().unwrap().unwrap(), [3]);
+/// assert!(documents.next().is_none());
+/// ```
+pub struct StreamDeserializer<'a, T> {
+    de: Deserializer<'a>,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
 }
 
-impl Deserializer {
-    fn from_args<I>(iter: I) -> Self
+impl<'a, T> StreamDeserializer<'a, T>
+where
+    T: Deserialize<'a>,
+{
+    pub fn new<I>(iter: I) -> Self
     where
-        I: Iterator<Item = String>,
+        I: Iterator<Item = String> + 'a,
     {
-        let mut d = Deserializer {
-            args: iter
-                .map(|s| s.trim().to_owned()) // trim whitespace
-                .filter(|p| !p.is_empty()) // remove elements that are zero sized
-                .collect(),
-            empty: false,
-        };
-        d.args.reverse();
-        d
+        StreamDeserializer::with_options(iter, &DeserializerOptions::default())
     }
-    fn from_iter<I>(iter: I) -> Self
+
+    /// Like `new`, with the given options, which apply to every document
+    /// like they do for `from_stream_with`.
+    pub fn with_options<I>(iter: I, options: &DeserializerOptions) -> Self
     where
-        I: Iterator<Item = &'static str>,
+        I: Iterator<Item = String> + 'a,
     {
-        Self::from_args(iter.map(|s| s.to_owned()))
+        let stream = stream(iter.map(Cow::Owned), options);
+        StreamDeserializer {
+            de: Deserializer::from_stream(stream, options.clone()),
+            done: false,
+            marker: PhantomData,
+        }
     }
-}
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer {
-    type Error = Error;
+    /// The index of the param the next document starts at, or of the end
+    /// after the last one.
+    pub fn position(&self) -> usize {
+        self.de.tokens.position()
+    }
 
-    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        let mut s = self;
-        if s.args.is_empty() {
-            return visitor.visit_none();
+    fn next_document(&mut self) -> Result<Option<T>> {
+        if self.de.tokens.peek().is_none() {
+            return self.de.tokens.take_error().map_or(Ok(None), Err);
         }
-        match s.args.pop().unwrap().as_str() {
-            "-t" => visitor.visit_bool(true),
-            "-f" => visitor.visit_bool(false),
-            "-n" => visitor.visit_none(),
-            "--" => {
-                let arg = s.args.pop().unwrap();
-                visitor.visit_str(&arg)
+        // every document has an outermost struct of its own
+        self.de.start = self.de.tokens.count();
+        // the params ending early is what made the document fail then, but
+        // an error past its end is for the next one
+        T::deserialize(&mut self.de).map(Some).map_err(|err| {
+            match (self.de.tokens.take_error(), self.de.failed) {
+                (Some(err), _) => err,
+                (None, Some(_)) => err,
+                (None, None) => locate(err, &self.de.tokens, &[], 0),
             }
-            "[" => {
-                // Object or array about to start, depends if key next
-                let next = s.args.last().unwrap();
-                if next.starts_with("--") && next.len() > 2 {
-                    let result = visitor.visit_map(&mut s);
-                    s.args.pop().unwrap(); // TODO errors on these if they are bad
-                    result
-                } else {
-                    let result = visitor.visit_seq(&mut s);
-                    s.args.pop().unwrap(); // TODO errors on these if they are bad
-                    result
+        })
+    }
+}
+
+impl<'a, T> Iterator for StreamDeserializer<'a, T>
+where
+    T: Deserialize<'a>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_document().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+// Turns the params into standard ones one at a time, like
+// `deserialize_params` does for all of them at once.
+fn stream<'a, I>(iter: I, options: &DeserializerOptions) -> Stream<'a>
+where
+    I: Iterator<Item = Cow<'a, str>> + 'a,
+{
+    let ends_options = options.dialect.ends_options();
+    let mut reader = options.dialect.reader();
+    let mut inline = options.inline_values.then(InlineValues::default);
+    let mut expand = options.extensions.expander();
+    let mut files = options.response_files.then(ResponseFiles::default);
+    let mut limits = Limits::new(options);
+    let params: Stream<'a> = match options.stdin_marker {
+        true => Box::new(read_marker(iter.enumerate(), io::stdin().lock())),
+        false => Box::new(iter.enumerate().map(Ok)),
+    };
+    let params = params.flat_map(move |param| {
+        let (position, param) = match param {
+            Ok(param) => param,
+            Err(err) => return vec![Err(err)],
+        };
+        if ends_options && param.trim() == parse::STRING_ESCAPE {
+            let err = "`--` can't end the options of streamed params".to_string();
+            return vec![Err(Error::Message(err))];
+        }
+        let mut read = Vec::new();
+        match &mut files {
+            Some(files) => {
+                if let Err(err) = files.push(&mut read, position, param) {
+                    return vec![Err(err)];
                 }
             }
-            "[]" => {
-                s.empty = true;
-                visitor.visit_seq(s)
+            None => read.push((position, param)),
+        }
+        for (_, param) in &read {
+            if let Err(err) = limits.count(param) {
+                return vec![Err(err)];
             }
-            "[--]" => {
-                s.empty = true;
-                visitor.visit_map(s)
+        }
+        if let Some(reader) = &mut reader {
+            let mut dialect = Vec::new();
+            for (position, param) in read {
+                reader.push(&mut dialect, position, param);
             }
-            v => {
-                // We're dealing with a key
-                if v.starts_with("--") && v.len() > 2 {
-                    visitor.visit_str(v.strip_prefix("--").unwrap())
-                } else {
-                    // We're dealing with a number or a string next
-                    if let Ok(uint) = v.parse::<u64>() {
-                        visitor.visit_u64(uint)
-                    } else if let Ok(int) = v.parse::<i64>() {
-                        visitor.visit_i64(int)
-                    } else if let Ok(float) = v.parse::<f64>() {
-                        visitor.visit_f64(float)
-                    } else {
-                        visitor.visit_str(v)
-                    }
-                }
+            read = dialect;
+        }
+        if let Some(inline) = &mut inline {
+            let mut split = Vec::new();
+            for (position, param) in read {
+                inline.push(&mut split, position, param);
             }
+            read = split;
         }
-    }
-
-    fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        if self.args.last().unwrap() == "-n" {
-            self.args.pop();
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
+        let mut expanded = Vec::new();
+        for (position, param) in read {
+            if let Err(err) = expand.push(&mut expanded, position, param) {
+                return vec![Err(err)];
+            }
         }
+        expanded.into_iter().map(Ok).collect()
+    });
+    if !options.unbracketed {
+        return Box::new(params);
     }
-
-    fn deserialize_enum<V>(
-        self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        let item = self.args.last().unwrap();
-        if item != "[" {
-            // Visit a unit variant.
-            visitor.visit_enum(self.args.pop().unwrap().into_deserializer())
-        } else {
-            self.args.pop().unwrap();
-            let value = visitor.visit_enum(Enum::new(self))?;
-            // TODO: check that next char is ]
-            self.args.pop().unwrap();
-            Ok(value)
+    // the brackets belong to the first and last param
+    let last = Rc::new(Cell::new(0));
+    let seen = Rc::clone(&last);
+    let params = params.inspect(move |param| {
+        if let Ok((position, _)) = param {
+            seen.set(*position);
         }
-    }
-
-    forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct identifier ignored_any
-    }
+    });
+    Box::new(
+        iter::once(Ok((0, Cow::Borrowed(parse::OPEN))))
+            .chain(params)
+            .chain(iter::once_with(move || {
+                Ok((last.get(), Cow::Borrowed(parse::CLOSE)))
+            })),
+    )
 }
 
-impl<'de> SeqAccess<'de> for Deserializer {
-    type Error = crate::Error;
+// Counts params against `DeserializerOptions::max_params` and `max_bytes`.
+struct Limits {
+    max_params: Option<usize>,
+    max_bytes: Option<usize>,
+    params: usize,
+    bytes: usize,
+}
 
-    fn next_element_seed<T>(
-        &mut self,
-        seed: T,
-    ) -> std::result::Result<Option<T::Value>, Self::Error>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        if self.empty || self.args.last().unwrap() == "]" {
-            self.empty = false;
-            return Ok(None);
+impl Limits {
+    fn new(options: &DeserializerOptions) -> Self {
+        Limits {
+            max_params: options.max_params,
+            max_bytes: options.max_bytes,
+            params: 0,
+            bytes: 0,
         }
-        seed.deserialize(self).map(Some)
     }
-}
-
-impl<'de> MapAccess<'de> for Deserializer {
-    type Error = crate::Error;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Self::Error>
-    where
-        K: DeserializeSeed<'de>,
-    {
-        if self.empty || self.args.last().unwrap() == "]" {
-            self.empty = false;
-            return Ok(None);
+    fn count(&mut self, param: &str) -> Result<()> {
+        self.params += 1;
+        self.bytes += param.len();
+        if let Some(max) = self.max_params.filter(|max| self.params > *max) {
+            return Err(Error::Message(format!("more than {} params", max)));
         }
-        seed.deserialize(self).map(Some)
+        if let Some(max) = self.max_bytes.filter(|max| self.bytes > *max) {
+            let err = format!("params longer than {} bytes in total", max);
+            return Err(Error::Message(err));
+        }
+        Ok(())
     }
+}
 
-    fn next_value_seed<V>(&mut self, seed: V) -> std::result::Result<V::Value, Self::Error>
-    where
-        V: DeserializeSeed<'de>,
-    {
-        seed.deserialize(self)
-    }
+// Replaces a lone `-` param with the params of the document read from the
+// reader, see `DeserializerOptions::stdin_marker`. They are read as they are
+// needed, and take the position of the `-`.
+fn read_marker<'a, I, R>(iter: I, reader: R) -> impl Iterator<Item = Result<(usize, Cow<'a, str>)>>
+where
+    I: Iterator<Item = (usize, Cow<'a, str>)>,
+    R: BufRead + 'a,
+{
+    let mut reader = Some(reader);
+    let mut escaped = false;
+    iter.flat_map(move |(position, param)| -> Box<dyn Iterator<Item = _>> {
+        let marker = !escaped && param == "-";
+        escaped = !escaped && param == parse::STRING_ESCAPE;
+        if !marker {
+            return Box::new(iter::once(Ok((position, param))));
+        }
+        match reader.take() {
+            Some(reader) => Box::new(
+                words::split_lines(reader).map(move |word| Ok((position, Cow::Owned(word?)))),
+            ),
+            None => Box::new(iter::once(Err(Error::Message(
+                "stdin can only be read once, for the first `-`".to_string(),
+            )))),
+        }
+    })
 }
 
-struct Enum<'a> {
-    de: &'a mut Deserializer,
+/// Like `from_args_with`, also returning which of the args every value was
+/// read from.
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_shon::DeserializerOptions;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+///     ports: Vec<u16>,
+/// }
+///
+/// let args = ["./binary", "[", "--host", "shon", "--ports", "[", "80", "443", "]", "]"];
+/// let args = args.map(String::from).into_iter();
+/// let (_, map) =
+///     serde_shon::from_args_mapped::<Server, _>(args, &DeserializerOptions::new()).unwrap();
+/// assert_eq!(map.get(""), Some(1..10));
+/// assert_eq!(map.get("/host"), Some(2..4));
+/// assert_eq!(map.get("/ports/1"), Some(7..8));
+/// ```
+pub fn from_args_mapped<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<(T, SourceMap)>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    let (t, recorder, _) = deserialize_params(
+        PhantomData,
+        iter.skip(1).map(Cow::Owned),
+        options,
+        1,
+        true,
+        false,
+    )?;
+    Ok((t, recorder.map(Recorder::finish).unwrap_or_default()))
 }
 
-impl<'a> Enum<'a> {
-    fn new(de: &'a mut Deserializer) -> Self {
-        Enum { de }
-    }
+/// Reads a document from a file or any other reader. The document is split
+/// into params like a shell would, and lines starting with `#` are comments.
+///
+/// ```
+/// let config = "# the defaults\n[\n  --name shon\n  --port 80\n]\n";
+/// let value: serde_shon::Value = serde_shon::from_reader(config.as_bytes()).unwrap();
+/// assert_eq!(value.get("port").and_then(|p| p.as_u64()), Some(80));
+/// ```
+pub fn from_reader<'a, R, T>(mut reader: R) -> Result<T>
+where
+    R: Read,
+    T: Deserialize<'a>,
+{
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    from_params(words::split_document(&input)?.into_iter())
 }
 
-impl<'de, 'a> EnumAccess<'de> for Enum<'a> {
-    type Error = Error;
-    type Variant = Self;
+/// Parses a whole command line as Windows passes it to a program, split
+/// into params the way `CommandLineToArgvW` does. The program name in front
+/// is skipped, as in `from_args`.
+///
+/// ```
+/// let line = r#"C:\tools\app.exe [ --dir "C:\Program Files" --quote \" ]"#;
+/// let value: serde_shon::Value = serde_shon::from_command_line(line).unwrap();
+/// assert_eq!(value.get("dir").unwrap().as_str(), Some(r"C:\Program Files"));
+/// assert_eq!(value.get("quote").unwrap().as_str(), Some("\""));
+/// ```
+pub fn from_command_line<'a, T>(command_line: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_args(words::split_windows(command_line).into_iter())
+}
 
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
-    where
-        V: DeserializeSeed<'de>,
-    {
-        let val = seed.deserialize(&mut *self.de)?;
-        Ok((val, self))
-    }
+/// Like `from_command_line`, for a command line split into params the way
+/// a POSIX shell would, without any expansions.
+///
+/// ```
+/// let value: serde_shon::Value =
+///     serde_shon::from_shell_command_line("app [ 'a b' c\\ d ]").unwrap();
+/// assert_eq!(value.to_raw_params().unwrap(), ["[", "a b", "c d", "]"]);
+/// ```
+pub fn from_shell_command_line<'a, T>(command_line: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_args(words::split(command_line)?.into_iter())
 }
 
-impl<'de, 'a> VariantAccess<'de> for Enum<'a> {
-    type Error = Error;
+/// Parses a document given as a single string, split into params like a
+/// POSIX shell would, without any expansions. This reads the output of
+/// `to_string` back.
+///
+/// ```
+/// let value: serde_shon::Value = serde_shon::from_str("[ --name 'a b' --n 3 ]").unwrap();
+/// assert_eq!(value.get("name").unwrap().as_str(), Some("a b"));
+/// assert_eq!(value.get("n").unwrap().as_u64(), Some(3));
+/// ```
+pub fn from_str<'a, T>(s: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_params(words::split(s)?.into_iter())
+}
 
-    fn unit_variant(self) -> Result<()> {
-        // Was handled earlier
-        panic!();
+/// Parses a top-level array from the command line one element at a time, so
+/// huge arrays don't have to be held in memory as a whole. Like `from_args`,
+/// the binary in front is skipped. The args are read as they are needed, so
+/// like with `from_stream`, errors only tell the index of the param they
+/// happened at. After an error, the iterator ends.
+///
+/// ```
+/// let args = ["./binary", "[", "1", "2", "x", "]"].map(String::from);
+/// let mut numbers = serde_shon::seq_iter::<u32, _>(args.into_iter());
+/// assert_eq!(numbers.next().unwrap().unwrap(), 1);
+/// assert_eq!(numbers.next().unwrap().unwrap(), 2);
+/// assert!(numbers.next().unwrap().is_err());
+/// assert!(numbers.next().is_none());
+/// ```
+pub fn seq_iter<'a, T, I>(args: I) -> impl Iterator<Item = Result<T>> + 'a
+where
+    I: Iterator<Item = String> + 'a,
+    T: DeserializeOwned + 'a,
+{
+    let options = DeserializerOptions::default();
+    let stream = stream(args.skip(1).map(Cow::Owned), &options);
+    SeqIter {
+        de: Deserializer::from_stream(stream, options),
+        started: false,
+        done: false,
+        marker: PhantomData,
     }
+}
 
-    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        seed.deserialize(self.de)
-    }
+struct SeqIter<'a, T> {
+    de: Deserializer<'a>,
+    // the opening bracket was consumed
+    started: bool,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
+}
 
-    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        de::Deserializer::deserialize_seq(self.de, visitor)
+impl<T> SeqIter<'_, T>
+where
+    T: DeserializeOwned,
+{
+    fn next_element(&mut self) -> Result<Option<T>> {
+        let tokens = &mut self.de.tokens;
+        if !self.started {
+            self.started = true;
+            match tokens.peek() {
+                // nothing at all is an empty array, like it is for `Vec`
+                None => return Ok(None),
+                Some(Token::EmptyArray) => {
+                    tokens.next();
+                    return self.end().map(|()| None);
+                }
+                Some(Token::Open) => {
+                    tokens.next();
+                    if let Some(Token::Key(_)) = tokens.peek() {
+                        return Err(Error::Message(
+                            "expected an array, found an object".to_string(),
+                        ));
+                    }
+                }
+                Some(_) => return Err(Error::Message("expected an array".to_string())),
+            }
+        }
+        if tokens.at_close()? {
+            tokens.next();
+            return self.end().map(|()| None);
+        }
+        T::deserialize(&mut self.de).map(Some)
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        de::Deserializer::deserialize_map(self.de, visitor)
+    fn end(&mut self) -> Result<()> {
+        match self.de.tokens.is_empty() {
+            true => Ok(()),
+            false => Err(self.de.tokens.trailing()),
+        }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use std::collections::HashMap;
+impl<T> Iterator for SeqIter<'_, T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
 
-    use serde::Serialize;
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_element().map_err(|err| {
+            // the args ending early is what made it fail then
+            match self.de.tokens.take_error() {
+                Some(err) => err,
+                None => locate(err, &self.de.tokens, &[], 1),
+            }
+        });
+        let next = next.transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
 
-    use crate::ser;
+// params without the binary in front, e.g. the output of `to_raw_params`
+pub(crate) fn from_params<'a, T, I>(iter: I) -> Result<T>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    from_params_with(iter, &DeserializerOptions::default())
+}
 
-    use super::*;
+pub(crate) fn from_params_with<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a>,
+{
+    deserialize_params(PhantomData, iter.map(Cow::Owned), options, 0, false, false).map(|(t, ..)| t)
+}
 
-    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
-    struct Test {
-        str: String,
-        int: u32,
-        int1: Option<u32>,
-        int2: Option<u32>,
-        int3: Option<u32>,
-        data: bool,
-        seq: Vec<String>,
-        map: HashMap<String, i32>,
-        e: E,
-    }
+// Deserializes the params, which start at the given offset in the args,
+// optionally recording where every value came from. A partial parse hands
+// back the args the document didn't take instead of failing on them.
+fn deserialize_params<'a, S, I>(
+    seed: S,
+    iter: I,
+    options: &DeserializerOptions,
+    offset: usize,
+    record: bool,
+    partial: bool,
+) -> Result<(S::Value, Option<Recorder>, Vec<String>)>
+where
+    I: Iterator<Item = Cow<'a, str>>,
+    S: DeserializeSeed<'a> + Clone,
+{
+    let mut limits = Limits::new(options);
+    let args: Vec<Cow<'a, str>> = iter
+        .map(|arg| limits.count(&arg).map(|()| arg))
+        .collect::<Result<_>>()?;
+    let mut params = args.clone();
+    // the params after the document, or after the `--` that ends the options
+    let trailing = match options.trailing_rest {
+        true => options.dialect.split_trailing(&mut params),
+        false => None,
+    };
+    let (rest, skipped) = match trailing {
+        Some(rest) => (Some(rest), 0),
+        None => (options.dialect.split_rest(&mut params)?, 1),
+    };
+    let rest: Option<Vec<String>> =
+        rest.map(|rest| rest.into_iter().map(Cow::into_owned).collect());
+    // where the document ends, and the params split off start
+    let split = params.len();
+    let rest_range = match &rest {
+        Some(rest) => split + skipped..split + skipped + rest.len(),
+        None => 0..0,
+    };
+    let mut params: Vec<_> = params.into_iter().enumerate().collect();
+    // what stdin and the response files hold counts towards the limits too,
+    // checked for stdin as it is read
+    if options.stdin_marker {
+        let mut limits = Limits::new(options);
+        params = read_marker(params.into_iter(), io::stdin().lock())
+            .map(|param| {
+                let (position, param) = param?;
+                limits.count(&param).map(|()| (position, param))
+            })
+            .collect::<Result<_>>()?;
+    }
+    if options.response_files {
+        let mut files = ResponseFiles::default();
+        let mut read = Vec::with_capacity(params.len());
+        for (position, param) in params {
+            files.push(&mut read, position, param)?;
+        }
+        let mut limits = Limits::new(options);
+        for (_, param) in &read {
+            limits.count(param)?;
+        }
+        params = read;
+    }
+    let mut params = options.dialect.read_positioned(params);
+    if options.inline_values {
+        params = InlineValues::default().split(params);
+    }
+    let mut params = options.extensions.expand(params)?;
+    if options.unbracketed {
+        // the brackets belong to the first and last param
+        let first = params.first().map_or(0, |(position, _)| *position);
+        let last = params.last().map_or(0, |(position, _)| *position);
+        params.insert(0, (first, Cow::Borrowed(parse::OPEN)));
+        params.push((last, Cow::Borrowed(parse::CLOSE)));
+    }
+    // in flag mode, the fields structs end without are tried as absent flags
+    // as they end, see `Probe`
+    let mut probing = options.flags;
+    let mut absent = Vec::new();
+    let mut aliases = Vec::new();
+    let (t, mut deserializer) = loop {
+        let mut deserializer = Deserializer {
+            rest: rest.clone(),
+            recorder: record.then(|| Recorder::new(offset)),
+            rest_range: rest_range.clone(),
+            absent: absent.clone(),
+            probing,
+            aliases: aliases.clone(),
+            ..Deserializer::from_positioned(params.clone().into_iter(), options.clone())
+        };
+        let start = deserializer.tokens.position();
+        deserializer.enter(None, start);
+        let result = seed.clone().deserialize(&mut deserializer);
+        if let Some(again) = deserializer.retry_probed(result.is_ok(), &mut absent, &mut aliases) {
+            probing = again;
+            continue;
+        }
+        match result {
+            Ok(t) => break (t, deserializer),
+            Err(_) if deserializer.retry_missing(&mut absent) => {}
+            // missing fields aren't at any arg in particular
+            Err(err) if deserializer.failed.is_some() => {
+                // the field wasn't a flag after all
+                let failed = deserializer.failed;
+                deserializer.absent.retain(|field| Some(*field) != failed);
+                return Err(missing_fields(seed, err, deserializer, params, rest, start));
+            }
+            Err(err) => return Err(locate(err, &deserializer.tokens, &args, offset)),
+        }
+    };
+    deserializer.leave();
+    if partial {
+        // the args as they were given, from the first one left over, without
+        // the params after `--` if a field took them
+        let (start, end) = match (rest.is_some(), deserializer.rest.is_some()) {
+            (true, false) => (deserializer.tokens.position(), split),
+            (true, true) if deserializer.tokens.is_empty() => (split, args.len()),
+            _ => (deserializer.tokens.position(), args.len()),
+        };
+        let left = args[start.min(end)..end].iter().map(|arg| arg.to_string());
+        return Ok((t, deserializer.recorder, left.collect()));
+    }
+    if !deserializer.at_end() {
+        let err = deserializer.tokens.trailing();
+        return Err(locate(err, &deserializer.tokens, &args, offset));
+    }
+    match (skipped, &deserializer.rest) {
+        // params after the document no field takes are simply left over
+        (0, Some(rest)) => {
+            if let Some(first) = rest.first() {
+                let err = Error::TrailingTokens {
+                    remaining: Some(rest.len()),
+                    first: rest.iter().take(TRAILING_SHOWN).cloned().collect(),
+                };
+                return Err(Error::At {
+                    index: split + offset,
+                    token: Some(first.clone()),
+                    error: Box::new(err),
+                });
+            }
+        }
+        (_, Some(_)) => {
+            return Err(Error::Message(
+                "nothing takes the params after `--`, which ends the options".to_string(),
+            ))
+        }
+        (_, None) => {}
+    }
+    Ok((t, deserializer.recorder, Vec::new()))
+}
+
+// Adds the arg the error happened at, which is the param taken last, or the
+// end when the params ran out.
+fn locate(err: Error, tokens: &Tokens, args: &[Cow<str>], offset: usize) -> Error {
+    let (index, token) = match (&err, tokens.taken()) {
+        (Error::Io(_) | Error::At { .. }, _) => return err,
+        (Error::Eof, _) => (tokens.position(), None),
+        (_, taken) => {
+            let index = taken.unwrap_or_else(|| tokens.position());
+            (index, args.get(index).map(|arg| arg.to_string()))
+        }
+    };
+    Error::At {
+        index: index + offset,
+        token,
+        error: Box::new(err),
+    }
+}
+
+// Serde stops at the first required field that is missing, which it tells
+// through `de::Error::missing_field`. Which of the other fields a struct ended
+// without have defaults only serde knows, so to report the missing ones as
+// well, the params are read again with placeholders for the fields found so
+// far, which every struct with the same fields that doesn't have them gets,
+// until no new one turns up or there are more than are shown.
+fn missing_fields<'a, S>(
+    seed: S,
+    err: Error,
+    mut deserializer: Deserializer<'a>,
+    params: Vec<(usize, Cow<'a, str>)>,
+    rest: Option<Vec<String>>,
+    start: usize,
+) -> Error
+where
+    S: DeserializeSeed<'a> + Clone,
+{
+    let mut missing = Vec::new();
+    let mut found = Vec::new();
+    // there may be more than the ones shown
+    let mut more = false;
+    while let Some((_, field)) = deserializer.failed.take() {
+        let field = (deserializer.failed_in, field);
+        more = found.len() >= MISSING_SHOWN;
+        if missing.contains(&field) || more {
+            break;
+        }
+        missing.push(field);
+        deserializer = Deserializer {
+            missing: missing.clone(),
+            absent: deserializer.absent,
+            rest: rest.clone(),
+            // for the pointers of the structs
+            recorder: Some(Recorder::new(0)),
+            ..Deserializer::from_positioned(params.clone().into_iter(), deserializer.options)
+        };
+        deserializer.enter(None, start);
+        let done = seed.clone().deserialize(&mut deserializer).is_ok();
+        found = std::mem::take(&mut deserializer.found);
+        if done {
+            break;
+        }
+    }
+    if found.len() < 2 {
+        return err;
+    }
+    let names: Vec<String> = found
+        .iter()
+        .take(MISSING_SHOWN)
+        .map(|(pointer, field)| {
+            let name = match field.strip_prefix(private::POSITIONAL) {
+                Some(name) => format!("<{}>", name),
+                None => format!("--{}", field),
+            };
+            match pointer.is_empty() {
+                true => name,
+                false => format!("{} in {}", name, pointer),
+            }
+        })
+        .collect();
+    let more = match more || found.len() > MISSING_SHOWN {
+        true => ", ...",
+        false => "",
+    };
+    Error::Message(format!("missing: {}{}", names.join(", "), more))
+}
+
+thread_local! {
+    // the objects being read as maps, innermost last, which structs may be
+    // flattened into. Serde reads flattened fields with a deserializer of its
+    // own, so this is the only way to reach them, see `FlatteningScope`.
+    static FLATTENING: RefCell<Vec<Flattening>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Flattening {
+    params: Vec<(usize, String)>,
+    options: DeserializerOptions,
+    // keys given without a value, with their position, which need a
+    // flattened struct to take them as flags
+    bare: Vec<(usize, String)>,
+    // the keys the flattened structs took
+    claimed: Vec<String>,
+    // what went wrong in a flattened struct, before serde made a message of
+    // it, and where
+    error: Option<(Option<usize>, Error)>,
+    // whether all keys of the object were read, which is when serde reads
+    // the structs flattened into it, and whether one of them is being read.
+    // Anything else asking for the object, like another parse while a value
+    // of it is read, gets what serde buffered.
+    ended: bool,
+    reading: bool,
+}
+
+// Keeps an object on `FLATTENING` while it is read, and takes it off again
+// however reading it ends, along with anything left above it.
+struct FlatteningScope {
+    // the length of the stack with the object on it
+    len: usize,
+}
+
+impl FlatteningScope {
+    fn enter(object: Flattening) -> Self {
+        FLATTENING.with(|flattening| {
+            let mut flattening = flattening.borrow_mut();
+            flattening.push(object);
+            FlatteningScope {
+                len: flattening.len(),
+            }
+        })
+    }
+
+    fn exit(self) -> Result<Flattening> {
+        let object = FLATTENING.with(|flattening| {
+            let mut flattening = flattening.borrow_mut();
+            flattening.truncate(self.len);
+            match flattening.len() == self.len {
+                true => flattening.pop(),
+                false => None,
+            }
+        });
+        object.ok_or_else(|| Error::Message("flattened object went missing".to_string()))
+    }
+}
+
+impl Drop for FlatteningScope {
+    fn drop(&mut self) {
+        // not panicking while the thread ends or already panics
+        let _ = FLATTENING.try_with(|flattening| {
+            if let Ok(mut flattening) = flattening.try_borrow_mut() {
+                flattening.truncate(self.len - 1);
+            }
+        });
+    }
+}
+
+// Serde hands the keys a struct doesn't know to the structs flattened into
+// it as values it buffered, which loses what the params looked like. So
+// those structs are read from the params of the object again instead, where
+// they are all at hand. Maps and other types are left to serde, as are
+// objects whose params are streamed in.
+pub(crate) fn deserialize_flattened<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let object = FLATTENING.with(|flattening| {
+        let mut flattening = flattening.borrow_mut();
+        let object = flattening
+            .last_mut()
+            .filter(|object| object.ended && !object.reading)?;
+        object.reading = true;
+        Some((object.params.clone(), object.options.clone()))
+    });
+    let Some((params, options)) = object else {
+        return T::deserialize(deserializer);
+    };
+    let result = deserialize_flattened_params(deserializer, params, options);
+    FLATTENING.with(|flattening| {
+        if let Some(object) = flattening.borrow_mut().last_mut() {
+            object.reading = false;
+        }
+    });
+    result
+}
+
+fn deserialize_flattened_params<'de, D, T>(
+    deserializer: D,
+    params: Vec<(usize, String)>,
+    mut options: DeserializerOptions,
+) -> std::result::Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    // the other keys are those of the object and its other flattened structs
+    options.deny_unknown_keys = false;
+    // in flag mode, the fields it ends without are tried as absent flags
+    let mut probing = options.flags;
+    let mut absent = Vec::new();
+    let mut aliases = Vec::new();
+    loop {
+        let params = params
+            .iter()
+            .map(|(p, param)| (*p, Cow::Owned(param.clone())));
+        let mut flattened = Deserializer {
+            start: usize::MAX,
+            absent: absent.clone(),
+            probing,
+            aliases: aliases.clone(),
+            flattened: Some(&[]),
+            ..Deserializer::from_positioned(params, options.clone())
+        };
+        let result = T::deserialize(&mut flattened);
+        if let Some(again) = flattened.retry_probed(result.is_ok(), &mut absent, &mut aliases) {
+            probing = again;
+            continue;
+        }
+        let err = match result {
+            Ok(t) => return take_fields(deserializer, flattened.flattened).map(|()| t),
+            Err(err) => err,
+        };
+        if flattened.tokens.count() == 0 {
+            // not a struct
+            return T::deserialize(deserializer);
+        }
+        if !flattened.retry_missing(&mut absent) {
+            let msg = err.to_string();
+            let taken = flattened.tokens.taken();
+            FLATTENING.with(|flattening| {
+                if let Some(object) = flattening.borrow_mut().last_mut() {
+                    object.error = Some((taken, err));
+                }
+            });
+            return Err(de::Error::custom(msg));
+        }
+    }
+}
+
+// Takes the fields of a flattened struct out of what serde buffered, so they
+// don't show up in the maps flattened next to it.
+fn take_fields<'de, D>(
+    deserializer: D,
+    fields: Option<&'static [&'static str]>,
+) -> std::result::Result<(), D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct Fields;
+
+    impl<'de> Visitor<'de> for Fields {
+        type Value = ();
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a struct")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> std::result::Result<(), A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            while map
+                .next_entry::<de::IgnoredAny, de::IgnoredAny>()?
+                .is_some()
+            {}
+            Ok(())
+        }
+    }
+
+    deserializer.deserialize_struct("", fields.unwrap_or_default(), Fields)
+}
+
+impl<'de> Deserializer<'de> {
+    fn from_args<I>(iter: I, options: DeserializerOptions) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        Deserializer::from_positioned(iter.map(Cow::Owned).enumerate(), options)
+    }
+
+    fn from_stream(stream: Stream<'de>, options: DeserializerOptions) -> Self {
+        Deserializer {
+            tokens: Tokens::streamed(stream, !options.keep_whitespace),
+            ..Deserializer::from_positioned(iter::empty(), options)
+        }
+    }
+
+    fn from_positioned<I>(iter: I, options: DeserializerOptions) -> Self
+    where
+        I: Iterator<Item = (usize, Cow<'de, str>)>,
+    {
+        let tokens = Tokens::positioned(iter, !options.keep_whitespace);
+        Deserializer {
+            start: 0,
+            tokens,
+            options,
+            missing: Vec::new(),
+            found: Vec::new(),
+            failed: None,
+            failed_in: &[],
+            absent: Vec::new(),
+            probing: false,
+            probed: Vec::new(),
+            aliases: Vec::new(),
+            probe: None,
+            tried: false,
+            stood_in: false,
+            rest: None,
+            recorder: None,
+            rest_range: 0..0,
+            flattened: None,
+            depth: 0,
+            single: false,
+        }
+    }
+
+    // enters an array or object, whose `[` was just taken
+    fn descend(&mut self) -> Result<()> {
+        // a `}` where the `]` of brackets that are never closed belongs was
+        // most likely meant to close them, which reads better than whatever
+        // it fails as otherwise
+        let open = self.tokens.taken();
+        if let Some(found) = open.and_then(|open| self.tokens.mismatched(open)) {
+            let found = Some(found.to_string());
+            return Err(Error::UnbalancedBracket { found });
+        }
+        if self.depth >= self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH) {
+            return Err(Error::DepthLimitExceeded);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // leaves an array or object, taking its `]`
+    fn close(&mut self) -> Result<()> {
+        self.depth -= 1;
+        self.tokens.close()
+    }
+
+    // starts recording a value at the given position, see `SourceMap`
+    fn enter(&mut self, segment: Option<&str>, start: usize) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.enter(segment, start);
+        }
+    }
+
+    // ends recording the innermost value after the params taken so far
+    fn leave(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.leave(self.tokens.consumed());
+        }
+    }
+
+    // records the params after the end of the options for the field
+    // taking them
+    fn record_rest(&mut self, segment: &str) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.enter(Some(segment), self.rest_range.start);
+            recorder.leave(self.rest_range.end);
+        }
+    }
+
+    // Whether the params have to be read again after trying the fields that
+    // structs ended without as absent flags, as something else stood in for
+    // one of them, or a field tried made the parse fail, and whether they
+    // are tried again. They are when a field turned out to be tried by
+    // another of its names, and read with the bools found absent otherwise.
+    fn retry_probed(
+        &mut self,
+        ok: bool,
+        absent: &mut Vec<MissingField>,
+        aliases: &mut Vec<Alias>,
+    ) -> Option<bool> {
+        if !self.probing || !(self.stood_in || (!ok && self.tried)) {
+            return None;
+        }
+        let again = self.aliases.len() > aliases.len();
+        *aliases = std::mem::take(&mut self.aliases);
+        *absent = match again {
+            true => Vec::new(),
+            false => std::mem::take(&mut self.probed),
+        };
+        Some(again)
+    }
+
+    // Whether the params have to be read again after the parse failed for
+    // a missing field in flag mode, which is read as an absent flag then,
+    // unless it was already. Serde doesn't tell the aliases of a field from
+    // other fields, so a bool given none of its names may be missing still.
+    fn retry_missing(&mut self, absent: &mut Vec<MissingField>) -> bool {
+        match self.failed {
+            Some(field) if self.options.flags && !absent.contains(&field) => {
+                absent.push(field);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // notes the field when a struct failed because of a missing field, unless
+    // a struct nested in it already did
+    fn note_missing(&mut self, err: Error, id: usize, fields: &'static [&'static str]) -> Error {
+        // a field tried as an absent flag by a name it was given by already
+        if let (Some((names, name)), Error::DuplicateField(dup)) = (self.probe.take(), &err) {
+            if let Some(field) = names.iter().find(|field| *field == dup) {
+                self.aliases.push((names, name, field));
+            }
+        }
+        if let (None, Error::MissingField(missing)) = (self.failed, &err) {
+            self.failed = fields.contains(missing).then_some((id, *missing));
+            if self.failed.is_some() {
+                self.failed_in = fields;
+            }
+        }
+        err
+    }
+
+    // A whole document in the next param, which is split into params of its
+    // own, see `DeserializerOptions::nested_documents`.
+    fn nested_document(&mut self) -> Result<Option<Deserializer<'de>>> {
+        if !self.options.nested {
+            return Ok(None);
+        }
+        match self.tokens.peek() {
+            Some(Token::Word(word)) if self.options.dialect.opens(word) => {
+                let params = self.options.dialect.read(words::split(word)?);
+                self.tokens.next();
+                Ok(Some(Deserializer {
+                    // not the outermost struct, whatever it looks like
+                    start: usize::MAX,
+                    depth: self.depth,
+                    ..Deserializer::from_args(params.into_iter(), self.options.clone())
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // whether the whole document was read, leaving the trailing params
+    // alone if they are allowed
+    fn at_end(&self) -> bool {
+        self.options.trailing_params || self.tokens.is_empty()
+    }
+
+    fn end_nested(&mut self) -> Result<()> {
+        match self.tokens.is_empty() {
+            true => Ok(()),
+            false => Err(self.tokens.trailing()),
+        }
+    }
+
+    // fails when reading what was flattened into an object as a struct, but
+    // it isn't one, see `deserialize_flattened`
+    fn check_flattened(&self) -> Result<()> {
+        match self.flattened.is_some() && self.tokens.count() == 0 {
+            true => Err(Error::Message("expected a struct".to_string())),
+            false => Ok(()),
+        }
+    }
+
+    // Reads an object as a map, keeping a copy of its params for the structs
+    // flattened into it.
+    fn deserialize_flattening<V>(
+        &mut self,
+        params: Vec<(usize, String)>,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let scope = FlatteningScope::enter(Flattening {
+            params,
+            options: self.options.clone(),
+            bare: Vec::new(),
+            claimed: Vec::new(),
+            error: None,
+            ended: false,
+            reading: false,
+        });
+        self.tokens.next();
+        self.descend()?;
+        let result = visitor.visit_map(Access {
+            flattening: true,
+            ..Access::new(self)
+        });
+        let object = scope.exit()?;
+        let result = match (result, object.error) {
+            (Err(_), Some((taken, err))) => {
+                if let Some(taken) = taken {
+                    self.tokens.point_at(taken);
+                }
+                return Err(err);
+            }
+            (result, _) => result?,
+        };
+        let claimed = |key: &String| object.claimed.contains(key);
+        if let Some((position, _)) = object.bare.iter().find(|(_, key)| !claimed(key)) {
+            self.tokens.point_at(*position);
+            return Err(Error::Message("missing value".to_string()));
+        }
+        self.close()?;
+        Ok(result)
+    }
+}
+
+// Hands a param to the visitor, borrowed from the input if it is.
+fn visit_param<'de, V>(visitor: V, param: Cow<'de, str>) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match param {
+        Cow::Borrowed(param) => visitor.visit_borrowed_str(param),
+        Cow::Owned(param) => visitor.visit_string(param),
+    }
+}
+
+// Hands the end of a param to the visitor, like the name of a key.
+fn visit_suffix<'de, V>(visitor: V, param: &Cow<'de, str>, len: usize) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match param {
+        Cow::Borrowed(param) => visitor.visit_borrowed_str(&param[param.len() - len..]),
+        Cow::Owned(param) => visitor.visit_str(&param[param.len() - len..]),
+    }
+}
+
+// Forwards the number methods of `de::Deserializer` to `deserialize_number`.
+macro_rules! deserialize_numbers {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.deserialize_number(visitor)
+            }
+        )*
+    };
+}
+
+// Forwards the methods of the integers narrower than 64 bits to
+// `deserialize_integer`, with the type they read.
+macro_rules! deserialize_integers {
+    ($($method:ident: $ty:ty)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.deserialize_integer::<$ty, V>(stringify!($ty), visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        !self.options.compact
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_flattened()?;
+        let Some(arg) = self.tokens.next() else {
+            return visitor.visit_none();
+        };
+        match classify(&arg) {
+            Token::True => visitor.visit_bool(true),
+            Token::False => visitor.visit_bool(false),
+            Token::Null => visitor.visit_none(),
+            Token::Escape => match self.tokens.next() {
+                Some(word) => visit_param(visitor, word),
+                None => Err(Error::Message(
+                    "`--` makes the next param a string, but there is none".to_string(),
+                )),
+            },
+            Token::Open => {
+                self.descend()?;
+                // Object or array about to start, depends if key next
+                let result = if let Some(Token::Key(_)) = self.tokens.peek() {
+                    visitor.visit_map(Access::new(self))?
+                } else {
+                    visitor.visit_seq(Access::new(self))?
+                };
+                self.close()?;
+                Ok(result)
+            }
+            Token::Close => Err(Error::UnbalancedBracket { found: None }),
+            Token::EmptyArray => visitor.visit_seq(Access::empty(self)),
+            Token::EmptyObject => visitor.visit_map(Access::empty(self)),
+            Token::Key(key) => visit_suffix(visitor, &arg, key.len()),
+            Token::Word(word) => match self.word(word) {
+                Scalar::U64(uint) => visitor.visit_u64(uint),
+                Scalar::I64(int) => visitor.visit_i64(int),
+                Scalar::F64(float) => visitor.visit_f64(float),
+                Scalar::Str(word) => {
+                    self.check_dashes(word)?;
+                    visit_param(visitor, arg)
+                }
+            },
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.tokens.peek() {
+            Some(Token::Null) => {
+                self.tokens.next();
+                visitor.visit_none()
+            }
+            None => visitor.visit_none(),
+            Some(_) => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == private::FLAG {
+            // a flag directly followed by the next key or the end of the
+            // object is set without an explicit value
+            let bare = match self.tokens.peek() {
+                None | Some(Token::Close) | Some(Token::Key(_)) => true,
+                Some(Token::Word(word)) => is_short_key(word),
+                Some(_) => false,
+            };
+            if bare {
+                return visitor.visit_bool(true);
+            }
+        }
+        if name == private::DECIMAL {
+            // handed over as written, without going through a float
+            if self.tokens.peek() == Some(Token::Escape) {
+                self.tokens.next();
+                return visit_param(visitor, self.tokens.expect_next()?);
+            }
+            if let Some(Token::Word(_)) = self.tokens.peek() {
+                return visit_param(visitor, self.tokens.expect_next()?);
+            }
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(mut nested) = self.nested_document()? {
+            let result = nested.deserialize_struct(name, fields, visitor)?;
+            nested.end_nested()?;
+            return Ok(result);
+        }
+        let id = self.tokens.count();
+        if self.flattened.is_some() && id == 0 {
+            self.flattened = Some(fields);
+        }
+        let mut config = type_config::take();
+        if config.key_style == KeyStyle::Field {
+            config.key_style = self.options.key_style;
+        }
+        match self.tokens.peek() {
+            Some(Token::Open) if self.options.positional => {
+                self.tokens.next();
+                self.descend()?;
+                // all fields in order, unless the first one comes with a key
+                let keyed = match self.tokens.peek() {
+                    Some(Token::Key(_)) => true,
+                    Some(Token::Word(word)) => is_short_key(word),
+                    _ => false,
+                };
+                let result = match keyed {
+                    true => visitor.visit_map(Access {
+                        fields,
+                        config,
+                        id: Some(id),
+                        ..Access::new(self)
+                    }),
+                    false => visitor.visit_seq(Access {
+                        fields,
+                        ..Access::new(self)
+                    }),
+                }
+                .map_err(|err| self.note_missing(err, id, fields))?;
+                self.close()?;
+                Ok(result)
+            }
+            Some(Token::Open) => {
+                self.tokens.next();
+                self.descend()?;
+                let outermost = id == self.start;
+                let positional = fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, f)| {
+                        f.starts_with(private::POSITIONAL)
+                            || *i < config.positional
+                            || (outermost && *i < self.options.positional_fields)
+                    })
+                    .map(|(_, f)| *f)
+                    .collect();
+                let result = visitor
+                    .visit_map(Access {
+                        positional,
+                        fields,
+                        config,
+                        id: Some(id),
+                        ..Access::new(self)
+                    })
+                    .map_err(|err| self.note_missing(err, id, fields))?;
+                self.close()?;
+                Ok(result)
+            }
+            // nothing at all is an object without keys, leaving every field
+            // to its default
+            None | Some(Token::EmptyObject) => {
+                self.tokens.next();
+                visitor
+                    .visit_map(Access {
+                        fields,
+                        config,
+                        id: Some(id),
+                        ..Access::empty(self)
+                    })
+                    .map_err(|err| self.note_missing(err, id, fields))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // field names are matched against a slice of the key in place, so
+        // identifying a field doesn't allocate
+        let result = match self.tokens.peek() {
+            Some(Token::Key(key)) => visitor.visit_str(key),
+            Some(Token::Word(word)) if is_short_key(word) => visitor.visit_str(word),
+            _ => return self.deserialize_any(visitor),
+        };
+        self.tokens.next();
+        result
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_flattened()?;
+        match self.tokens.peek() {
+            Some(Token::Open) => {
+                self.tokens.next();
+                self.descend()?;
+                let value = visitor.visit_enum(Enum::new(self))?;
+                self.close()?;
+                Ok(value)
+            }
+            // Visit a unit variant.
+            _ => visitor.visit_enum(self.tokens.expect_next()?.into_deserializer()),
+        }
+    }
+
+    // With positional structs, the target type decides what a word is.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.positional {
+            if let Some(Token::Word(word @ ("true" | "false"))) = self.tokens.peek() {
+                let value = word == "true";
+                self.tokens.next();
+                return visitor.visit_bool(value);
+            }
+        }
+        if self.options.bool_words {
+            if let Some(Token::Word(word)) = self.tokens.peek() {
+                if let Some(value) = parse::bool_word(word) {
+                    self.tokens.next();
+                    return visitor.visit_bool(value);
+                }
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // digits are chars as well, rather than numbers
+        if let Some(Token::Word(word)) = self.tokens.peek() {
+            let mut chars = word.chars();
+            let result = match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(Error::Message("expected a single character".to_string())),
+            };
+            self.tokens.next();
+            return visitor.visit_char(result?);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(Token::Word(word)) = self.tokens.peek() {
+            if let Some(bytes) = bytes::decode(word) {
+                self.tokens.next();
+                return visitor.visit_byte_buf(bytes.map_err(Error::Message)?);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.positional || self.options.numbers_as_strings {
+            if let Some(Token::Word(_)) = self.tokens.peek() {
+                let arg = self.tokens.expect_next()?;
+                if let Scalar::Str(word) = self.word(&arg) {
+                    self.check_dashes(word)?;
+                }
+                return visit_param(visitor, arg);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_flattened()?;
+        if let Some(mut nested) = self.nested_document()? {
+            let result = nested.deserialize_seq(visitor)?;
+            nested.end_nested()?;
+            return Ok(result);
+        }
+        if std::mem::take(&mut self.single) {
+            return visitor.visit_seq(Single(Some(self)));
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_flattened()?;
+        if let Some(mut nested) = self.nested_document()? {
+            let result = nested.deserialize_map(visitor)?;
+            nested.end_nested()?;
+            return Ok(result);
+        }
+        // structs with flattened fields are read as maps
+        match self.tokens.peek_brackets() {
+            Some(params) if params.get(1).is_some_and(|(_, p)| parse::is_key(p)) => {
+                self.deserialize_flattening(params, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_numbers! {
+        deserialize_i64 deserialize_u64
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_float(visitor)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_float(visitor)
+    }
+
+    deserialize_integers! {
+        deserialize_i8: i8 deserialize_i16: i16 deserialize_i32: i32
+        deserialize_u8: u8 deserialize_u16: u16 deserialize_u32: u32
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        unit unit_struct tuple
+        tuple_struct ignored_any
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    // what a word reads as, with the prefixed integers if enabled
+    fn word<'w>(&self, word: &'w str) -> Scalar<'w> {
+        match parse::word(word) {
+            Scalar::Str(word) if self.options.radix_prefixes => {
+                parse::prefixed(word).unwrap_or(Scalar::Str(word))
+            }
+            scalar => scalar,
+        }
+    }
+
+    // With `strict_dashes`, words that start with a dash and aren't numbers
+    // are errors, as they are more likely misplaced flags than strings. A
+    // lone `-` can't be a flag, and is left alone.
+    fn check_dashes(&self, word: &str) -> Result<()> {
+        if self.options.strict_dashes && word.starts_with('-') && word != "-" {
+            let err = "strings starting with a dash need `--` in front".to_string();
+            return Err(Error::Message(err));
+        }
+        Ok(())
+    }
+
+    // words that aren't numbers are reported as such, any other mismatch by
+    // the visitor
+    fn deserialize_number<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(Token::Word(word)) = self.tokens.peek() {
+            if let Scalar::Str(word) = self.word(word) {
+                let err = Error::InvalidNumber(word.to_string());
+                self.tokens.next();
+                return Err(err);
+            }
+        }
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    // integers that don't fit the type are reported as such, rather than as
+    // whatever serde makes of the mismatch
+    fn deserialize_integer<T, V>(&mut self, ty: &str, visitor: V) -> Result<V::Value>
+    where
+        T: TryFrom<u64> + TryFrom<i64>,
+        V: Visitor<'de>,
+    {
+        if let Some(Token::Word(word)) = self.tokens.peek() {
+            let fits = match self.word(word) {
+                Scalar::U64(uint) => T::try_from(uint).is_ok(),
+                Scalar::I64(int) => T::try_from(int).is_ok(),
+                // too large for 64 bits
+                Scalar::F64(_) => word.parse::<i128>().is_err(),
+                Scalar::Str(_) => true,
+            };
+            if !fits {
+                self.tokens.next();
+                return Err(Error::Message(format!("value out of range for {}", ty)));
+            }
+        }
+        self.deserialize_number(visitor)
+    }
+
+    // infinities and NaN, when enabled, are only floats to float types
+    fn deserialize_float<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.special_floats {
+            if let Some(Token::Word(word)) = self.tokens.peek() {
+                if let Some(float) = parse::special_float(word) {
+                    self.tokens.next();
+                    return visitor.visit_f64(float);
+                }
+            }
+        }
+        self.deserialize_number(visitor)
+    }
+
+    // Integers beyond 64 bits are floats to `deserialize_any`, like in other
+    // self-describing formats, as serde can't buffer them otherwise. Asked
+    // for by type, they are read exactly.
+    fn deserialize_wide<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if let Some(Token::Word(word)) = self.tokens.peek() {
+            if let Ok(uint) = word.parse::<u128>() {
+                self.tokens.next();
+                return visitor.visit_u128(uint);
+            }
+            if let Ok(int) = word.parse::<i128>() {
+                self.tokens.next();
+                return visitor.visit_i128(int);
+            }
+            let prefixed = match self.options.radix_prefixes {
+                true => parse::prefixed_wide(word),
+                false => None,
+            };
+            match prefixed {
+                Some(Wide::U128(uint)) => {
+                    self.tokens.next();
+                    return visitor.visit_u128(uint);
+                }
+                Some(Wide::I128(int)) => {
+                    self.tokens.next();
+                    return visitor.visit_i128(int);
+                }
+                None => {}
+            }
+        }
+        self.deserialize_number(visitor)
+    }
+}
+
+struct Access<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    // `[]` and `[--]` have no closing bracket to look out for
+    empty: bool,
+    // positional struct fields which are still waiting for their value
+    positional: Vec<&'static str>,
+    // the fields of the struct being deserialized, empty for maps
+    fields: &'static [&'static str],
+    // the settings the struct brought along, see `ShonConfig`
+    config: TypeConfig,
+    // the value of the last key, when the key itself already implied it
+    implied: Option<Implied>,
+    // identifies structs for filling in missing fields, see `Deserializer`
+    id: Option<usize>,
+    // how many missing fields were filled in so far
+    filled: usize,
+    // the last key was a missing field, which gets a placeholder value
+    placeholder: bool,
+    // the last key was a missing field that is read as an absent flag
+    absent: Option<&'static str>,
+    // the fields given, and how far the fields were tried as absent flags
+    // once the struct ended, when probing, see `Deserializer::probing`
+    seen: Vec<&'static str>,
+    tried: usize,
+    // the last key is a field tried as an absent flag
+    probe: Option<&'static str>,
+    // the last key was a repeated field, whose values are gathered
+    gather: Option<String>,
+    // the number of times the last key was given, for counters
+    count: Option<u64>,
+    // where the keys of the object are, for finding repeated ones
+    later: LaterKeys,
+    // the last key was the field taking the params after the options
+    rest: bool,
+    // the object is read as a map, which structs may be flattened into
+    flattening: bool,
+    // the last key and its position, when flattening
+    last_key: Option<(usize, String)>,
+    // the last key isn't one of the flattened struct being read, but of the
+    // object or another struct flattened into it
+    passed: bool,
+    // the segment of the last key and where it started, when recording
+    key: Option<(String, usize)>,
+    // the index of the next element
+    index: usize,
+}
+
+impl<'a, 'de> Access<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Access {
+            de,
+            empty: false,
+            positional: Vec::new(),
+            fields: &[],
+            config: TypeConfig::default(),
+            implied: None,
+            id: None,
+            filled: 0,
+            placeholder: false,
+            absent: None,
+            seen: Vec::new(),
+            tried: 0,
+            probe: None,
+            gather: None,
+            count: None,
+            later: LaterKeys::default(),
+            rest: false,
+            flattening: false,
+            last_key: None,
+            passed: false,
+            key: None,
+            index: 0,
+        }
+    }
+
+    fn empty(de: &'a mut Deserializer<'de>) -> Self {
+        Access {
+            empty: true,
+            ..Access::new(de)
+        }
+    }
+
+    fn at_end(&self) -> Result<bool> {
+        Ok(self.empty || self.de.tokens.at_close()?)
+    }
+
+    // the next field that an earlier attempt found missing in this struct,
+    // and whether it is an absent flag
+    fn next_missing(&mut self) -> Option<(&'static str, bool)> {
+        let id = self.id?;
+        let absent = self
+            .de
+            .absent
+            .iter()
+            .filter(|(struct_id, _)| *struct_id == id);
+        if let Some((_, field)) = absent.clone().nth(self.filled) {
+            self.filled += 1;
+            return Some((field, true));
+        }
+        // a field missing from one struct is missing from all the others
+        // with the same fields that don't have it
+        let fields = self.fields;
+        let (_, field) = self
+            .de
+            .missing
+            .iter()
+            .find(|(missing, field)| *missing == fields && !self.known(field))?;
+        self.seen.push(field);
+        if self.de.found.len() <= MISSING_SHOWN {
+            let pointer = self.de.recorder.as_ref().map_or("", Recorder::pointer);
+            let found = (pointer.to_string(), *field);
+            if !self.de.found.contains(&found) {
+                self.de.found.push(found);
+            }
+        }
+        Some((field, false))
+    }
+
+    // the next field the struct ended without, to be tried as an absent flag
+    fn next_probe(&mut self) -> Option<&'static str> {
+        if !self.de.probing || self.id.is_none() {
+            return None;
+        }
+        while let Some(field) = self.fields.get(self.tried).copied() {
+            self.tried += 1;
+            // short aliases are tried as the field they belong to
+            if !self.known(field) && !is_short_key(field) {
+                self.seen.push(field);
+                self.de.tried = true;
+                self.de.probe = Some((self.fields, field));
+                return Some(field);
+            }
+        }
+        None
+    }
+
+    // whether the field was given or tried already, by any of its names
+    // known to be one
+    fn known(&self, field: &str) -> bool {
+        let seen = |name: &str| self.seen.contains(&name);
+        seen(field)
+            || self
+                .de
+                .aliases
+                .iter()
+                .filter(|(fields, ..)| *fields == self.fields)
+                .any(|(_, alias, name)| match (*alias == field, *name == field) {
+                    (true, true) => true,
+                    (true, false) => seen(name),
+                    (false, true) => seen(alias),
+                    (false, false) => false,
+                })
+    }
+
+    // notes the field the key is one of the names of, which isn't tried as
+    // an absent flag then. The names of a field are sorted, so its short
+    // aliases come right before the name of the field.
+    fn note_field(&mut self, key: &str) {
+        if (!self.de.probing && self.de.missing.is_empty()) || self.id.is_none() {
+            return;
+        }
+        let fields = self.fields;
+        let Some(i) = fields.iter().position(|field| *field == key) else {
+            return;
+        };
+        if let Some(field) = fields[i..].iter().find(|field| !is_short_key(field)) {
+            self.seen.push(field);
+        }
+    }
+
+    // `--no-<name>` for a field `<name>`, if negation or flag mode is enabled
+    fn negated_field(&self) -> Option<&'static str> {
+        if !self.de.options.negation && !self.de.options.flags {
+            return None;
+        }
+        let Some(Token::Key(key)) = self.de.tokens.peek() else {
+            return None;
+        };
+        let name = key.strip_prefix("no-")?;
+        if self.fields.contains(&key) {
+            return None;
+        }
+        self.fields
+            .iter()
+            .copied()
+            .find(|f| *f == name || self.config.key_style.key(f) == name)
+    }
+
+    // the field of the outermost struct that takes the params after the end
+    // of the options, once all keys are through
+    fn rest_field(&self) -> Option<&'static str> {
+        if self.id != Some(self.de.start) || self.de.rest.is_none() {
+            return None;
+        }
+        self.fields
+            .iter()
+            .copied()
+            .find(|f| f.starts_with(private::REST))
+    }
+
+    // a rest field given with its key like any other field
+    fn keyed_rest_field(&self) -> Option<&'static str> {
+        let Some(Token::Key(key)) = self.de.tokens.peek() else {
+            return None;
+        };
+        self.fields
+            .iter()
+            .copied()
+            .find(|f| f.strip_prefix(private::REST) == Some(key))
+    }
+
+    // notes the key that starts here, for recording where its value is from
+    fn note_key(&mut self, segment: &str) {
+        if self.de.recorder.is_some() {
+            self.key = Some((segment.to_string(), self.de.tokens.position()));
+        }
+    }
+
+    // whether the key isn't one of a struct, which has all the names it
+    // takes in its fields, aliases included
+    fn unknown(&self, key: &str) -> bool {
+        self.id.is_some() && !self.fields.iter().any(|f| field_name(f) == key)
+    }
+
+    // the field counting how often the key in front is given, with all the
+    // keys it counts, see `DeserializerOptions::counter`
+    fn counted_field(&self) -> Option<(&'static str, Vec<String>)> {
+        let param = self.de.tokens.peek_param()?;
+        self.de.options.counters.iter().find_map(|(token, name)| {
+            let field = *self.fields.iter().find(|f| field_name(f) == name)?;
+            let keys = vec![
+                token.clone(),
+                format!("{}{}", parse::STRING_ESCAPE, name),
+                format!(
+                    "{}{}",
+                    parse::STRING_ESCAPE,
+                    self.config.key_style.key(name)
+                ),
+            ];
+            keys.iter().any(|key| key == param).then_some((field, keys))
+        })
+    }
+
+    // tells the field an unknown key was probably meant to be, if a field
+    // is spelled closely enough
+    fn suggest_field(&self, mut err: Error, key: &str) -> Error {
+        if let Error::UnknownField {
+            field, suggestion, ..
+        } = &mut err
+        {
+            if field == key {
+                *suggestion = self
+                    .fields
+                    .iter()
+                    .map(|field| self.config.key_style.key(field_name(field)))
+                    .map(|field| (edit_distance(key, &field), field))
+                    .filter(|(distance, _)| *distance <= (key.chars().count() / 3).max(2))
+                    .min_by_key(|(distance, _)| *distance)
+                    .map(|(_, field)| field);
+            }
+        }
+        err
+    }
+
+    // keeps only one of the values of the key in front, if it is repeated
+    // and there is a policy for that, see `DeserializerOptions::duplicate_keys`
+    fn resolve_duplicates(&mut self) -> Result<()> {
+        let policy = self.de.options.duplicate_keys;
+        let gathered = self.de.options.repeated_keys && self.id.is_some();
+        let keyed = match self.de.tokens.peek() {
+            Some(Token::Key(_)) => true,
+            Some(Token::Word(word)) => self.id.is_some() && is_short_key(word),
+            _ => false,
+        };
+        match policy != DuplicateKeys::Default && keyed && !gathered {
+            true => self.de.tokens.resolve_duplicates(policy, &mut self.later),
+            false => Ok(()),
+        }
+    }
+
+    // notes the key in front of a struct field, whose values are gathered
+    // into an array when it is repeated, see `DeserializerOptions::repeated_keys`
+    fn note_repeated(&mut self) {
+        if self.de.options.repeated_keys && self.id.is_some() {
+            self.gather = self.de.tokens.peek_param().map(str::to_string);
+        }
+    }
+
+    // lets the structs flattened into the object being read as a map read
+    // its params, once serde reads them, see `Flattening::ended`
+    fn end_flattening(&self) {
+        if !self.flattening {
+            return;
+        }
+        FLATTENING.with(|flattening| {
+            if let Some(object) = flattening.borrow_mut().last_mut() {
+                object.ended = true;
+            }
+        });
+    }
+
+    // notes the key in front as taken by a struct flattened into the object
+    // being read, see `deserialize_flattened`
+    fn claim(&self) {
+        if self.de.flattened.is_none() || self.id != Some(0) {
+            return;
+        }
+        let Some(key) = self.de.tokens.peek_param() else {
+            return;
+        };
+        FLATTENING.with(|flattening| {
+            if let Some(object) = flattening.borrow_mut().last_mut() {
+                object.claimed.push(key.to_string());
+            }
+        });
+    }
+
+    // the field whose key is spelled in the key style of the struct
+    fn styled_field(&self) -> Option<&'static str> {
+        if self.config.key_style == KeyStyle::Field {
+            return None;
+        }
+        let Some(Token::Key(key)) = self.de.tokens.peek() else {
+            return None;
+        };
+        if self.fields.contains(&key) {
+            return None;
+        }
+        self.fields
+            .iter()
+            .copied()
+            .find(|f| self.config.key_style.key(f) == key)
+    }
+}
+
+// The Levenshtein distance between the strings, in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// the name of a field without the prefix telling how it is read
+fn field_name(field: &str) -> &str {
+    field
+        .strip_prefix(private::POSITIONAL)
+        .or_else(|| field.strip_prefix(private::REST))
+        .unwrap_or(field)
+}
+
+impl<'de> SeqAccess<'de> for Access<'_, 'de> {
+    type Error = crate::Error;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> std::result::Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.at_end()? {
+            return Ok(None);
+        }
+        if self.de.recorder.is_none() {
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
+        // the elements of structs read without keys are still their fields
+        let segment = match self.fields.get(self.index) {
+            Some(field) => field_name(field).to_string(),
+            None => self.index.to_string(),
+        };
+        self.index += 1;
+        let start = self.de.tokens.position();
+        self.de.enter(Some(&segment), start);
+        let element = seed.deserialize(&mut *self.de)?;
+        self.de.leave();
+        Ok(Some(element))
+    }
+}
+
+impl<'de> MapAccess<'de> for Access<'_, 'de> {
+    type Error = crate::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.at_end()? {
+            if let Some(field) = self.rest_field() {
+                self.rest = true;
+                self.note_field(field);
+                self.de.record_rest(field_name(field));
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+            if let Some((field, absent)) = self.next_missing() {
+                self.placeholder = !absent;
+                self.absent = absent.then_some(field);
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+            return match self.next_probe() {
+                Some(field) => {
+                    self.probe = Some(field);
+                    seed.deserialize(field.into_deserializer()).map(Some)
+                }
+                None => {
+                    self.end_flattening();
+                    Ok(None)
+                }
+            };
+        }
+        if !self.positional.is_empty() {
+            // positional values come before any of the keys
+            let keyed = match self.de.tokens.peek() {
+                Some(Token::Key(_)) => true,
+                Some(Token::Word(word)) => is_short_key(word),
+                _ => false,
+            };
+            if keyed {
+                self.positional.clear();
+            } else {
+                let field = self.positional.remove(0);
+                self.note_field(field);
+                self.note_key(field_name(field));
+                return seed.deserialize(field.into_deserializer()).map(Some);
+            }
+        }
+        if let Some(field) = self.negated_field() {
+            self.note_field(field);
+            self.note_key(field);
+            self.claim();
+            self.de.tokens.next();
+            self.implied = Some(Implied {
+                value: false,
+                bare: false,
+                flag: false,
+                deferred: None,
+            });
+            return seed.deserialize(field.into_deserializer()).map(Some);
+        }
+        if let Some((field, keys)) = self.counted_field() {
+            self.note_field(field);
+            self.note_key(field_name(field));
+            self.claim();
+            self.de.tokens.next();
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let found = self.de.tokens.count_later(&keys, &mut self.later)?;
+            self.count = Some(1 + found as u64);
+            return seed.deserialize(field.into_deserializer()).map(Some);
+        }
+        self.resolve_duplicates()?;
+        if let Some(field) = self.styled_field().or_else(|| self.keyed_rest_field()) {
+            self.note_repeated();
+            self.note_field(field);
+            self.note_key(field_name(field));
+            self.claim();
+            self.de.tokens.next();
+            return seed.deserialize(field.into_deserializer()).map(Some);
+        }
+        let key = match self.de.tokens.peek() {
+            Some(Token::Key(key)) => key.to_string(),
+            Some(Token::Word(word)) if is_short_key(word) => word.to_string(),
+            _ => {
+                let param = self.de.tokens.next().unwrap_or_default().into_owned();
+                return Err(Error::ExpectedKey(param));
+            }
+        };
+        if self.de.options.deny_unknown_keys && self.unknown(&key) {
+            self.de.tokens.next();
+            let err = de::Error::unknown_field(&key, self.fields);
+            return Err(self.suggest_field(err, &key));
+        }
+        self.note_repeated();
+        self.note_field(&key);
+        self.note_key(&key);
+        self.passed = self.de.flattened.is_some() && self.id == Some(0) && self.unknown(&key);
+        if !self.passed {
+            self.claim();
+        }
+        if self.flattening {
+            let param = self.de.tokens.peek_param().unwrap_or_default();
+            self.last_key = Some((self.de.tokens.position(), param.to_string()));
+        }
+        // structs with `#[serde(deny_unknown_fields)]` fail on the key
+        seed.deserialize(&mut *self.de)
+            .map(Some)
+            .map_err(|err| self.suggest_field(err, &key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let Some((segment, start)) = self.key.take() else {
+            return self.value_seed(seed);
+        };
+        self.de.enter(Some(&segment), start);
+        let value = self.value_seed(seed)?;
+        self.de.leave();
+        Ok(value)
+    }
+}
+
+impl<'de> Access<'_, 'de> {
+    fn value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (passed, last_key) = (std::mem::take(&mut self.passed), self.last_key.take());
+        if let Some(implied) = self.implied.take() {
+            return seed.deserialize(implied);
+        }
+        if self.placeholder {
+            self.placeholder = false;
+            return seed.deserialize(Placeholder);
+        }
+        if let Some(field) = self.absent.take() {
+            return seed.deserialize(Absent(field));
+        }
+        if let Some((field, id)) = self.probe.take().zip(self.id) {
+            self.de.probe = None;
+            let mut flag = false;
+            let value = seed.deserialize(Probe { flag: &mut flag })?;
+            match flag {
+                true => self.de.probed.push((id, field)),
+                false => self.de.stood_in = true,
+            }
+            return Ok(value);
+        }
+        if let Some(key) = self.gather.take() {
+            self.de.tokens.gather(&key, &mut self.later)?;
+            self.de.single = !matches!(
+                self.de.tokens.peek(),
+                None | Some(
+                    Token::Open
+                        | Token::EmptyArray
+                        | Token::EmptyObject
+                        | Token::Key(_)
+                        | Token::Close
+                )
+            );
+        }
+        if let Some(count) = self.count.take() {
+            return seed.deserialize(count.into_deserializer());
+        }
+        if self.rest {
+            self.rest = false;
+            let rest = self.de.rest.take().unwrap_or_default();
+            return seed.deserialize(SeqDeserializer::new(rest.into_iter()));
+        }
+        if self.de.tokens.is_empty() {
+            return Err(parse::end_of_input());
+        }
+        if let Some(Token::Close | Token::Key(_)) = self.de.tokens.peek() {
+            // a bare key, which is only enough for flags and optional bools
+            let flag = self.config.flags || self.de.options.flags;
+            return seed.deserialize(Implied {
+                value: true,
+                bare: true,
+                flag: flag || passed,
+                deferred: last_key.filter(|_| !flag),
+            });
+        }
+        let value = seed.deserialize(&mut *self.de);
+        self.de.single = false;
+        value
+    }
+}
+
+// A value read as an array of just itself, see `Deserializer::single`.
+struct Single<'a, 'de>(Option<&'a mut Deserializer<'de>>);
+
+impl<'de> SeqAccess<'de> for Single<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0.take() {
+            Some(de) => seed.deserialize(de).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(usize::from(self.0.is_some()))
+    }
+}
+
+// The value of a key that doesn't come with one, like a negated flag.
+struct Implied {
+    value: bool,
+    // given as just the key, which doesn't say anything about plain bools
+    bare: bool,
+    // unless the struct takes bare keys as plain bools
+    flag: bool,
+    // the key and its position, when a struct flattened into the object may
+    // still take it as a flag, see `deserialize_flattened`
+    deferred: Option<(usize, String)>,
+}
+
+impl<'de> de::Deserializer<'de> for Implied {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // like for the keys caught by a flattened map, when it takes flags
+        if self.bare && !self.flag {
+            let Some(key) = self.deferred else {
+                return Err(Error::Message("missing value".to_string()));
+            };
+            FLATTENING.with(|flattening| {
+                if let Some(object) = flattening.borrow_mut().last_mut() {
+                    object.bare.push(key);
+                }
+            });
+        }
+        visitor.visit_bool(self.value)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.bare && !self.flag {
+            return Err(Error::Message("missing value".to_string()));
+        }
+        visitor.visit_bool(self.value)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(Implied {
+            bare: false,
+            ..self
+        })
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == private::FLAG {
+            return visitor.visit_bool(self.value);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+// A bool field that isn't given at all, which is `false` in flag mode. Other
+// types are still missing.
+struct Absent(&'static str);
+
+impl<'de> de::Deserializer<'de> for Absent {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::missing_field(self.0))
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == private::FLAG {
+            return visitor.visit_bool(false);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct map
+        struct enum identifier ignored_any
+    }
+}
+
+// A field a struct ended without, tried as an absent flag, which is `false`
+// if it is a bool. Anything else gets a placeholder, and the params are read
+// again with serde filling in the field, as it knows about the defaults.
+struct Probe<'a> {
+    flag: &'a mut bool,
+}
+
+macro_rules! deserialize_placeholder {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                de::Deserializer::$method(Placeholder, $($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Probe<'_> {
+    type Error = Error;
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.flag = true;
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == private::FLAG {
+            return self.deserialize_bool(visitor);
+        }
+        Placeholder.deserialize_newtype_struct(name, visitor)
+    }
+
+    deserialize_placeholder! {
+        deserialize_any(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+}
+
+// Stands in for a missing field while looking for further missing fields,
+// giving the emptiest value of whatever type is asked for.
+struct Placeholder;
+
+macro_rules! deserialize_empty {
+    ($($method:ident => $visit:ident($($value:expr)?),)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Placeholder {
+    type Error = Error;
+
+    deserialize_empty! {
+        deserialize_any => visit_unit(),
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_option => visit_none(),
+        deserialize_unit => visit_unit(),
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<Placeholder>()))
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(std::iter::empty::<(
+            Placeholder,
+            Placeholder,
+        )>()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        // every field gets a placeholder too, so the struct can't fail on
+        // its own missing fields
+        let fields = fields.iter().map(|field| (*field, Placeholder));
+        visitor.visit_map(MapDeserializer::new(fields))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = variants.first().copied().unwrap_or_default();
+        visitor.visit_enum(variant.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 unit_struct tuple tuple_struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Placeholder {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'a, 'de> Enum<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Enum { de }
+    }
+}
+
+impl<'de, 'a> EnumAccess<'de> for Enum<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let val = seed.deserialize(&mut *self.de)?;
+        Ok((val, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
+    type Error = Error;
+
+    // a unit variant is usually given as just its name, but `[ Unit ]` is
+    // the same
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use serde::Serialize;
+
+    use crate::{ser, BytesEncoding, Dialect, DoubleDash};
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+    struct Test {
+        str: String,
+        int: u32,
+        int1: Option<u32>,
+        int2: Option<u32>,
+        int3: Option<u32>,
+        data: bool,
+        seq: Vec<String>,
+        map: HashMap<String, i32>,
+        e: E,
+    }
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+    enum E {
+        Unit,
+        Newtype(u32),
+        Tuple(u32, u32),
+        Struct { a: u32 },
+    }
+
+    #[test]
+    fn deserialize() {
+        let v: Vec<&str> = vec![
+            "./binary",
+            "[",
+            "--str",
+            "data",
+            "--int",
+            "123",
+            "--int1",
+            "456",
+            "--int2",
+            "-n",
+            "--seq",
+            "[",
+            "--",
+            "'hello there'",
+            "general",
+            "kenobi",
+            "]",
+            "--data",
+            "-t",
+            "--map",
+            "[",
+            "--one",
+            "2",
+            "--three",
+            "4",
+            "]",
+            "--e",
+            "Unit",
+            "]",
+        ];
+        let v = v.into_iter().map(String::from);
+        let t: Test = from_args(v).unwrap();
+        assert_eq!(
+            t,
+            Test {
+                str: "data".to_string(),
+                int: 123,
+                int1: Some(456),
+                int2: None,
+                int3: None,
+                data: true,
+                seq: vec![
+                    "'hello there'".to_string(),
+                    "general".to_string(),
+                    "kenobi".to_string()
+                ],
+                map: HashMap::from([("one".to_string(), 2), ("three".to_string(), 4)]),
+                e: E::Unit,
+            }
+        );
+    }
+
+    #[test]
+    fn unit_struct() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Test {}
+        let v: Vec<&str> = vec!["[--]"];
+        let t: Test = from_iter(v.into_iter()).unwrap();
+        assert_eq!(t, Test {});
+    }
+
+    #[test]
+    fn empty_seq() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Test {
+            seq: Vec<String>,
+            extra: i32,
+        }
+        let v: Vec<&str> = vec!["[", "--seq", "[]", "--extra", "-1", "]"];
+        let t: Test = from_iter(v.into_iter()).unwrap();
+        assert_eq!(
+            t,
+            Test {
+                seq: vec![],
+                extra: -1
+            }
+        );
+    }
+
+    #[test]
+    fn empty_map() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Test {
+            map: HashMap<String, String>,
+            extra: i32,
+        }
+        let v: Vec<&str> = vec!["[", "--map", "[--]", "--extra", "-1", "]"];
+        let t: Test = from_iter(v.into_iter()).unwrap();
+        assert_eq!(
+            t,
+            Test {
+                map: HashMap::new(),
+                extra: -1,
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_enum_variants() {
+        let expected = E::Unit;
+        assert_eq!(
+            from_iter::<E, _>(vec!["Unit"].into_iter()).unwrap(),
+            expected
+        );
+
+        let expected = E::Newtype(1);
+        assert_eq!(
+            from_iter::<E, _>(vec!["[", "--Newtype", "1", "]"].into_iter()).unwrap(),
+            expected
+        );
+
+        let expected = E::Tuple(1, 2);
+        assert_eq!(
+            from_iter::<E, _>(vec!["[", "--Tuple", "[", "1", "2", "]", "]"].into_iter()).unwrap(),
+            expected
+        );
+
+        let expected = E::Struct { a: 1 };
+        assert_eq!(
+            from_iter::<E, _>(vec!["[", "--Struct", "[", "--a", "1", "]", "]"].into_iter())
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn tagged_enums() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        #[serde(tag = "type", rename_all = "lowercase")]
+        enum Shape {
+            Circle { radius: u32, filled: bool },
+            Label(Label),
+            Point,
+        }
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Label {
+            text: String,
+        }
+
+        let shapes = [
+            Shape::Circle {
+                radius: 2,
+                filled: true,
+            },
+            Shape::Label(Label {
+                text: "a".to_string(),
+            }),
+            Shape::Point,
+        ];
+        for shape in shapes {
+            let params = ser::to_raw_params(&shape).unwrap();
+            assert_eq!(params[..3], ["[", "--type", params[2].as_str()]);
+            assert_eq!(from_params::<Shape, _>(params.into_iter()).unwrap(), shape);
+        }
+        // the tag may come after the fields
+        let args = [
+            "[", "--radius", "2", "--filled", "-f", "--type", "circle", "]",
+        ];
+        let shape: Shape = from_iter(args.into_iter()).unwrap();
+        assert_eq!(
+            shape,
+            Shape::Circle {
+                radius: 2,
+                filled: false
+            }
+        );
+        let err = from_iter::<Shape, _>(["[", "--radius", "2", "]"].into_iter()).unwrap_err();
+        assert!(err.to_string().ends_with("missing field `type`"), "{}", err);
+
+        // serde hands the variant what it buffered of the values, which only
+        // tells what the params looked like
+        let args = ["[", "--type", "label", "--text", "1", "]"];
+        let err = from_iter::<Shape, _>(args.into_iter()).unwrap_err();
+        assert!(err.to_string().ends_with("expected a string"), "{}", err);
+        let args = ["[", "--type", "label", "--text", "--", "1", "]"];
+        let shape: Shape = from_iter(args.into_iter()).unwrap();
+        assert_eq!(
+            shape,
+            Shape::Label(Label {
+                text: "1".to_string()
+            })
+        );
+        let args = ["[", "--type", "circle", "--radius", "2", "--filled", "]"];
+        assert!(from_iter::<Shape, _>(args.into_iter()).is_err());
+        let options = DeserializerOptions::new().flags(true);
+        let shape: Shape = from_iter_with(args.into_iter(), &options).unwrap();
+        assert!(matches!(shape, Shape::Circle { filled: true, .. }));
+    }
+
+    #[test]
+    fn adjacently_tagged_enums() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        #[serde(tag = "t", content = "c")]
+        enum Message {
+            Quit,
+            Write(String),
+            Move { x: i32, y: i32 },
+            Color(u8, u8, u8),
+        }
+
+        let messages = [
+            Message::Quit,
+            Message::Write("1".to_string()),
+            Message::Move { x: 1, y: -1 },
+            Message::Color(0, 127, 255),
+        ];
+        for message in messages {
+            let params = ser::to_raw_params(&message).unwrap();
+            assert_eq!(params[..2], ["[", "--t"]);
+            assert_eq!(
+                from_params::<Message, _>(params.into_iter()).unwrap(),
+                message
+            );
+        }
+        let params = ser::to_raw_params(&Message::Move { x: 1, y: -1 }).unwrap();
+        assert_eq!(
+            params,
+            ["[", "--t", "Move", "--c", "[", "--x", "1", "--y", "-1", "]", "]"]
+        );
+
+        // content in front of the tag is buffered by serde, and only read
+        // by what it looks like
+        let args = [
+            "[", "--c", "[", "--x", "1", "--y", "2", "]", "--t", "Move", "]",
+        ];
+        let message: Message = from_iter(args.into_iter()).unwrap();
+        assert_eq!(message, Message::Move { x: 1, y: 2 });
+        let args = ["[", "--c", "1", "--t", "Write", "]"];
+        assert!(from_iter::<Message, _>(args.into_iter()).is_err());
+        let options = DeserializerOptions::new().numbers_as_strings(true);
+        let args = ["[", "--t", "Write", "--c", "1", "]"];
+        let message: Message = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(message, Message::Write("1".to_string()));
+    }
+
+    #[test]
+    fn integer_ranges() {
+        #[derive(Debug, Deserialize)]
+        struct Listen {
+            port: u16,
+            backlog: i8,
+        }
+
+        let parse = |port, backlog| {
+            let args = ["[", "--port", port, "--backlog", backlog, "]"];
+            from_iter::<Listen, _>(args.into_iter())
+        };
+        let listen = parse("65535", "-128").unwrap();
+        assert_eq!((listen.port, listen.backlog), (65535, -128));
+        let err = parse("99999", "1").unwrap_err();
+        assert_eq!(err.to_string(), "arg 2 `99999`: value out of range for u16");
+        let err = parse("-1", "1").unwrap_err();
+        assert_eq!(err.to_string(), "arg 2 `-1`: value out of range for u16");
+        let err = parse("1", "128").unwrap_err();
+        assert_eq!(err.to_string(), "arg 4 `128`: value out of range for i8");
+        let err = parse("1", "99999999999999999999").unwrap_err();
+        assert!(
+            err.to_string().ends_with("value out of range for i8"),
+            "{}",
+            err
+        );
+        let err = parse("1.5", "1").unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{}", err);
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        let options = DeserializerOptions::new().radix_prefixes(true);
+        let parse =
+            |args: &[&'static str]| from_iter_with::<Vec<i32>, _>(args.iter().copied(), &options);
+        assert_eq!(
+            parse(&["[", "0xff", "0o755", "-0b1010", "0XA", "]"]).unwrap(),
+            [255, 493, -10, 10]
+        );
+        let err = parse(&["[", "0x1ffffffff", "]"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "arg 1 `0x1ffffffff`: value out of range for i32"
+        );
+        let err = parse(&["[", "0xfg", "]"]).unwrap_err();
+        assert_eq!(err.to_string(), "arg 1 `0xfg`: invalid number `0xfg`");
+        let value: crate::Value = from_iter_with(["0b11"].into_iter(), &options).unwrap();
+        assert_eq!(value.as_u64(), Some(3));
+
+        // 128-bit integers take them too, however wide they are
+        let wide = |arg: &'static str| from_iter_with::<i128, _>([arg].into_iter(), &options);
+        assert_eq!(wide("0xff").unwrap(), 255);
+        assert_eq!(wide("-0x10000000000000000").unwrap(), -(1 << 64));
+        assert_eq!(
+            wide("-0x80000000000000000000000000000000").unwrap(),
+            i128::MIN
+        );
+        let err = wide("0x80000000000000000000000000000000").unwrap_err();
+        assert!(err.to_string().contains("expected i128"), "{}", err);
+        let uint: u128 =
+            from_iter_with(["0x10000000000000000000000000"].into_iter(), &options).unwrap();
+        assert_eq!(uint, 1 << 100);
+        assert!(from_iter::<u128, _>(["0x10000000000000000"].into_iter()).is_err());
+
+        // like other numbers, strings that look like them are escaped
+        assert_eq!(ser::to_raw_params(&"0xff").unwrap(), ["--", "0xff"]);
+        let value: String = from_iter_with(["--", "0xff"].into_iter(), &options).unwrap();
+        assert_eq!(value, "0xff");
+
+        // without the option, they are strings
+        let value: String = from_iter(["0xff"].into_iter()).unwrap();
+        assert_eq!(value, "0xff");
+        let err = from_iter::<u8, _>(["0xff"].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("invalid number"), "{}", err);
+    }
+
+    #[test]
+    fn size_limits() {
+        let options = DeserializerOptions::new().max_params(3).max_bytes(6);
+        let parse = |args: &[&'static str]| {
+            from_iter_with::<Vec<String>, _>(args.iter().copied(), &options)
+        };
+        assert_eq!(parse(&["[", "abcd", "]"]).unwrap(), ["abcd"]);
+        let err = parse(&["[", "a", "b", "]"]).unwrap_err();
+        assert_eq!(err.to_string(), "more than 3 params");
+        let err = parse(&["[", "abcde", "]"]).unwrap_err();
+        assert_eq!(err.to_string(), "params longer than 6 bytes in total");
+
+        // the binary doesn't count, and reading stops at the limit
+        let args = ["./binary", "[", "abcd", "]"].map(String::from);
+        assert!(from_args_with::<Vec<String>, _>(args.into_iter(), &options).is_ok());
+        let endless = iter::repeat("1");
+        let err = from_iter_with::<Vec<u8>, _>(endless, &options).unwrap_err();
+        assert_eq!(err.to_string(), "more than 3 params");
+        let endless = iter::once("[".to_string()).chain(iter::repeat("1".to_string()));
+        let err = from_stream_with::<Vec<u8>, _>(endless, &options).unwrap_err();
+        assert_eq!(err.to_string(), "more than 3 params");
+    }
+
+    #[test]
+    fn partial() {
+        let args = |args: &[&str]| {
+            let args = iter::once("./binary").chain(args.iter().copied());
+            args.map(String::from).collect::<Vec<_>>().into_iter()
+        };
+        let (value, rest): (Vec<u8>, _) =
+            from_args_partial(args(&["[", "1", "]", "--", "x", "[", "]"])).unwrap();
+        assert_eq!(value, [1]);
+        assert_eq!(rest, ["--", "x", "[", "]"]);
+        let (value, rest): (u8, _) = from_args_partial(args(&["1"])).unwrap();
+        assert_eq!((value, rest.len()), (1, 0));
+        assert!(from_args_partial::<u8, _>(args(&["x", "1"])).is_err());
+
+        // the params after `--` are left over when no field takes them
+        let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+        let options = DeserializerOptions::new().dialect(dialect);
+        let parse = |list: &[&str]| {
+            from_args_partial_with::<Vec<u8>, _>(args(list), &options).map(|(_, rest)| rest)
+        };
+        assert_eq!(parse(&["[", "]", "--", "a"]).unwrap(), ["--", "a"]);
+        assert_eq!(
+            parse(&["[", "]", "b", "--", "a"]).unwrap(),
+            ["b", "--", "a"]
+        );
+    }
+
+    #[test]
+    fn seeded() {
+        // a seed with state, which sees every attempt at the params
+        #[derive(Clone, Copy)]
+        struct Interner<'s>(&'s RefCell<Vec<String>>);
+
+        impl<'de> DeserializeSeed<'de> for Interner<'_> {
+            type Value = Vec<usize>;
+
+            fn deserialize<D>(self, deserializer: D) -> std::result::Result<Vec<usize>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                let mut strings = self.0.borrow_mut();
+                let ids = Vec::<String>::deserialize(deserializer)?
+                    .into_iter()
+                    .map(|s| match strings.iter().position(|known| *known == s) {
+                        Some(id) => id,
+                        None => {
+                            strings.push(s);
+                            strings.len() - 1
+                        }
+                    });
+                Ok(ids.collect())
+            }
+        }
+
+        let strings = RefCell::new(Vec::new());
+        let args = ["./binary", "[", "a", "b", "a", "]"].map(String::from);
+        let ids = from_args_seed(args.into_iter(), Interner(&strings)).unwrap();
+        assert_eq!(ids, [0, 1, 0]);
+        assert_eq!(*strings.borrow(), ["a", "b"]);
+
+        let args = ["./binary", "[", "a", "]", "x"].map(String::from);
+        let err = from_args_seed(args.into_iter(), Interner(&strings)).unwrap_err();
+        assert_eq!(err.index(), Some(4));
+    }
+
+    #[test]
+    fn seed_runs() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Opts {
+            verbose: bool,
+            name: String,
+            port: u16,
+            #[serde(default)]
+            level: u8,
+        }
+
+        #[derive(Clone, Copy)]
+        struct Counted<'c>(&'c Cell<usize>);
+
+        impl<'de> DeserializeSeed<'de> for Counted<'_> {
+            type Value = Opts;
+
+            fn deserialize<D>(self, deserializer: D) -> std::result::Result<Opts, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                self.0.set(self.0.get() + 1);
+                Opts::deserialize(deserializer)
+            }
+        }
+
+        let runs = |params: &[&str], options: &DeserializerOptions| {
+            let runs = Cell::new(0);
+            let args = ["./binary", "["].iter().chain(params).chain(["]"].iter());
+            let args = args.map(|arg| arg.to_string());
+            let opts = from_args_seed_with(args, Counted(&runs), options);
+            (opts.is_ok(), runs.get())
+        };
+        let options = DeserializerOptions::default();
+        let all = [
+            "--verbose",
+            "-t",
+            "--name",
+            "a",
+            "--port",
+            "1",
+            "--level",
+            "2",
+        ];
+        assert_eq!(runs(&all, &options), (true, 1));
+        assert_eq!(runs(&all[..6], &options), (true, 1));
+        // once more for every field missing, to name them all
+        assert_eq!(runs(&all[..4], &options), (false, 2));
+        assert_eq!(runs(&all[..2], &options), (false, 3));
+
+        let flags = DeserializerOptions::new().flags(true);
+        let all = ["--verbose", "--name", "a", "--port", "1", "--level", "2"];
+        assert_eq!(runs(&all, &flags), (true, 1));
+        assert_eq!(runs(&all[1..], &flags), (true, 1));
+        // once more when a field the struct ended without isn't a bool
+        assert_eq!(runs(&all[..5], &flags), (true, 2));
+    }
+
+    #[test]
+    fn depth_limit() {
+        #[derive(Debug, Deserialize)]
+        struct Tree {
+            #[serde(default)]
+            children: Vec<Tree>,
+        }
+
+        let nested = |depth| {
+            let mut args = vec!["["; depth];
+            args.extend(vec!["]"; depth]);
+            args
+        };
+        let value: crate::Value = from_slice(&nested(128)).unwrap();
+        assert!(value.as_array().is_some());
+        for args in [nested(129), nested(100_000)] {
+            let err = from_slice::<crate::Value>(&args).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "arg 128 `[`: arrays and objects nested too deeply"
+            );
+        }
+
+        // structs, enums and nested documents count as well
+        let mut args = Vec::new();
+        for _ in 0..5 {
+            args.extend(["[", "--children", "["]);
+        }
+        args.extend(vec!["]"; 10]);
+        let options = DeserializerOptions::new().max_depth(10);
+        let tree = from_slice_with::<Tree>(&args, &options).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        let options = DeserializerOptions::new().max_depth(9);
+        let err = from_slice_with::<Tree>(&args, &options).unwrap_err();
+        assert!(matches!(err.inner(), Error::DepthLimitExceeded));
+        let nested = args[2..args.len() - 1].join(" ");
+        let args = ["[", "--children", &nested, "]"];
+        let options = options.nested_documents(true);
+        let err = from_slice_with::<Tree>(&args, &options).unwrap_err();
+        assert!(matches!(err.inner(), Error::DepthLimitExceeded));
+    }
+
+    #[test]
+    fn stdin_marker() {
+        let read = |args: &[&'static str], stdin: &'static str| {
+            let params = args.iter().map(|arg| Cow::Borrowed(*arg)).enumerate();
+            read_marker(params, stdin.as_bytes()).collect::<Result<Vec<_>>>()
+        };
+        let params = read(&["[", "--data", "-", "--", "-", "]"], "[ 'a b'\n# c\n2 ]\n").unwrap();
+        assert_eq!(
+            params,
+            [
+                (0, Cow::Borrowed("[")),
+                (1, Cow::Borrowed("--data")),
+                (2, Cow::Borrowed("[")),
+                (2, Cow::Borrowed("a b")),
+                (2, Cow::Borrowed("2")),
+                (2, Cow::Borrowed("]")),
+                (3, Cow::Borrowed("--")),
+                (4, Cow::Borrowed("-")),
+                (5, Cow::Borrowed("]")),
+            ]
+        );
+        let err = read(&["[", "-", "-", "]"], "1").unwrap_err();
+        assert!(err.to_string().contains("only be read once"), "{}", err);
+        let err = read(&["-"], "'a").unwrap_err();
+        assert_eq!(err.to_string(), "unterminated single quote");
+
+        // without the option, `-` is a string
+        let value: String = from_iter(["-"].into_iter()).unwrap();
+        assert_eq!(value, "-");
+    }
+
+    #[test]
+    fn dashes() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            offset: i32,
+            name: Option<String>,
+            #[serde(default)]
+            mode: String,
+        }
+
+        let lenient = DeserializerOptions::new();
+        let strict = DeserializerOptions::new().strict_dashes(true);
+        let parse = |args: &[&'static str], options| {
+            from_iter_with::<Args, _>(args.iter().copied(), options)
+        };
+        for options in [&lenient, &strict] {
+            // numbers win, and `-n` is null unless escaped
+            let args = parse(&["[", "--offset", "-1", "--name", "-n", "]"], options).unwrap();
+            assert_eq!((args.offset, args.name), (-1, None));
+            let args = [
+                "[", "--offset", "-0", "--name", "--", "-n", "--mode", "-", "]",
+            ];
+            let args = parse(&args, options).unwrap();
+            assert_eq!(args.name.as_deref(), Some("-n"));
+            assert_eq!(args.mode, "-");
+            let err = parse(&["[", "--offset", "-t", "]"], options).unwrap_err();
+            assert!(err.to_string().contains("invalid type: boolean"), "{}", err);
+        }
+
+        let args = ["[", "--offset", "1", "--name", "-t3", "--mode", "-x", "]"];
+        let parsed = parse(&args, &lenient).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("-t3"));
+        assert_eq!(parsed.mode, "-x");
+        let err = parse(&args, &strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "arg 4 `-t3`: strings starting with a dash need `--` in front"
+        );
+        let err = parse(&["[", "--offset", "-1x", "]"], &strict).unwrap_err();
+        assert_eq!(err.to_string(), "arg 2 `-1x`: invalid number `-1x`");
+
+        // strings that look like numbers are still strings, when asked to
+        let options = strict.clone().numbers_as_strings(true);
+        let args = ["[", "--offset", "1", "--name", "-1", "--mode", "-x", "]"];
+        let err = parse(&args, &options).unwrap_err();
+        assert!(err.to_string().starts_with("arg 6 `-x`"), "{}", err);
+    }
+
+    #[test]
+    fn byte_strings() {
+        #[derive(Debug, PartialEq)]
+        struct Bytes(Vec<u8>);
+
+        impl Serialize for Bytes {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                s: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                s.serialize_bytes(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Bytes {
+            fn deserialize<D: de::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+                struct BytesVisitor;
+
+                impl<'de> Visitor<'de> for BytesVisitor {
+                    type Value = Bytes;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("bytes")
+                    }
+
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Bytes, E> {
+                        Ok(Bytes(v))
+                    }
+
+                    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Bytes, A::Error>
+                    where
+                        A: de::SeqAccess<'de>,
+                    {
+                        let mut bytes = Vec::new();
+                        while let Some(byte) = seq.next_element()? {
+                            bytes.push(byte);
+                        }
+                        Ok(Bytes(bytes))
+                    }
+                }
+
+                d.deserialize_byte_buf(BytesVisitor)
+            }
+        }
+
+        let bytes = Bytes(vec![0, 1, 254, 255]);
+        for (encoding, param) in [
+            (BytesEncoding::Hex, "hex:0001feff"),
+            (BytesEncoding::Base64, "base64:AAH+/w=="),
+        ] {
+            let mut ser = ser::Serializer::new().bytes_encoding(encoding);
+            ser.serialize_document(&bytes).unwrap();
+            assert_eq!(ser.params(), [param]);
+            let args = ser.params().iter().map(String::as_str).collect::<Vec<_>>();
+            assert_eq!(from_slice::<Bytes>(&args).unwrap(), bytes);
+        }
+        let params = ser::to_raw_params(&bytes).unwrap();
+        assert_eq!(params, ["[", "0", "1", "254", "255", "]"]);
+        let args = params.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(from_slice::<Bytes>(&args).unwrap(), bytes);
+
+        let err = from_iter::<Bytes, _>(["hex:abc"].into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 0 `hex:abc`: odd number of hex digits");
+    }
+
+    #[test]
+    fn chars() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Split {
+            separator: char,
+            quote: Option<char>,
+            digit: char,
+        }
+
+        let split = Split {
+            separator: 'é',
+            quote: Some('['),
+            digit: '5',
+        };
+        let params = ser::to_raw_params(&split).unwrap();
+        let args = params.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(from_slice::<Split>(&args).unwrap(), split);
+        let args = ["[", "--separator", ",", "--digit", "0", "]"];
+        let split: Split = from_iter(args.into_iter()).unwrap();
+        assert_eq!((split.separator, split.digit), (',', '0'));
+
+        let err = from_iter::<char, _>(["ab"].into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 0 `ab`: expected a single character");
+        let err = from_iter::<char, _>(["--", "ab"].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("expected a character"), "{}", err);
+    }
+
+    #[test]
+    fn bool_words() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            verbose: bool,
+            color: Option<bool>,
+            name: String,
+        }
+
+        let options = DeserializerOptions::new().bool_words(true);
+        let parse =
+            |args: &[&'static str]| from_iter_with::<Args, _>(args.iter().copied(), &options);
+        let args = parse(&[
+            "[",
+            "--verbose",
+            "YES",
+            "--color",
+            "false",
+            "--name",
+            "no",
+            "]",
+        ]);
+        let args = args.unwrap();
+        assert!(args.verbose);
+        assert_eq!(args.color, Some(false));
+        assert_eq!(args.name, "no");
+        let args = parse(&["[", "--verbose", "-f", "--name", "true", "]"]).unwrap();
+        assert!(!args.verbose);
+        assert_eq!(args.name, "true");
+        assert!(parse(&["[", "--verbose", "y", "--name", "a", "]"]).is_err());
+
+        let err = from_iter::<bool, _>(["true"].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("expected a boolean"), "{}", err);
+    }
+
+    #[test]
+    fn special_floats() {
+        #[derive(Debug, Deserialize, Serialize)]
+        struct Bounds {
+            low: f32,
+            high: f64,
+            name: String,
+        }
+
+        let bounds = Bounds {
+            low: f32::NEG_INFINITY,
+            high: f64::NAN,
+            name: "inf".to_string(),
+        };
+        let params = ser::to_raw_params(&bounds).unwrap();
+        assert_eq!(
+            params,
+            ["[", "--low", "-inf", "--high", "nan", "--name", "--", "inf", "]"]
+        );
+        let options = DeserializerOptions::new().special_floats(true);
+        let args = params.iter().map(String::as_str);
+        let back: Bounds = from_slice_with(&args.collect::<Vec<_>>(), &options).unwrap();
+        assert_eq!(back.low, f32::NEG_INFINITY);
+        assert!(back.high.is_nan());
+        assert_eq!(back.name, "inf");
+
+        // without the option, or for other types, they are strings
+        let err = from_iter::<f64, _>(["inf"].into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 0 `inf`: invalid number `inf`");
+        let name: String = from_iter_with(["NaN"].into_iter(), &options).unwrap();
+        assert_eq!(name, "NaN");
+        let value: crate::Value = from_iter_with(["inf"].into_iter(), &options).unwrap();
+        assert_eq!(value.as_str(), Some("inf"));
+    }
+
+    #[test]
+    fn wide_integers() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Wide {
+            id: u128,
+            offset: i128,
+            small: Option<i128>,
+        }
+
+        let wide = Wide {
+            id: u128::MAX,
+            offset: i128::MIN,
+            small: Some(-1),
+        };
+        let params = ser::to_raw_params(&wide).unwrap();
+        assert_eq!(params[2], u128::MAX.to_string());
+        assert_eq!(params[4], i128::MIN.to_string());
+        assert_eq!(from_params::<Wide, _>(params.into_iter()).unwrap(), wide);
+        let err = from_iter::<u128, _>(["-1"].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("invalid value"), "{}", err);
+        let err = from_iter::<u128, _>(["1e3"].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{}", err);
+
+        // untyped, they are floats like in other formats
+        let value: crate::Value = from_iter(["18446744073709551616"].into_iter()).unwrap();
+        assert_eq!(value.as_f64(), Some(18446744073709551616.0));
+        let string = ser::to_raw_params(&"18446744073709551616").unwrap();
+        assert_eq!(string, ["--", "18446744073709551616"]);
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn ser_then_de() {
+        let initial = Test {
+            str: "data".to_string(),
+            int: 123,
+            int1: Some(456),
+            int2: None,
+            int3: None,
+            data: true,
+            seq: vec![
+                "'hello there'".to_string(),
+                "general".to_string(),
+                "kenobi".to_string(),
+            ],
+            map: HashMap::from([("one".to_string(), 2), ("three".to_string(), 4)]),
+            e: E::Newtype(3),
+        };
+        let mut out = ser::to_params(&initial).unwrap();
+        // shell escapes with regular quotes are weird, so we have to emplace that single quote
+        // back. TODO: output easily reingestible data
+        let pos = out
+            .iter()
+            .position(|i| i == "''\\''hello there'\\'''")
+            .unwrap();
+        out[pos] = "'hello there'".to_string();
+        out.insert(0, "./binary".to_string());
+        let output = from_args(out.into_iter()).unwrap();
+        assert_eq!(initial, output);
+    }
+
+    // only accepts keys handed over as a slice, not as an owned string
+    struct Key(String);
+
+    impl<'de> Deserialize<'de> for Key {
+        fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            struct KeyVisitor;
+
+            impl Visitor<'_> for KeyVisitor {
+                type Value = Key;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("a key")
+                }
+
+                fn visit_str<E>(self, v: &str) -> std::result::Result<Key, E> {
+                    Ok(Key(v.to_string()))
+                }
+
+                fn visit_string<E: de::Error>(self, _: String) -> std::result::Result<Key, E> {
+                    Err(E::custom("key was allocated"))
+                }
+            }
+
+            deserializer.deserialize_identifier(KeyVisitor)
+        }
+    }
+
+    #[test]
+    fn negation() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Args {
+            verbose: bool,
+            color: Option<bool>,
+            #[serde(rename = "no-cache")]
+            no_cache: Option<bool>,
+            cache: Option<u32>,
+        }
+        let options = DeserializerOptions::new().negation(true);
+        let args = ["[", "--no-verbose", "--no-color", "--no-cache", "-t", "]"];
+        assert_eq!(
+            from_iter_with::<Args, _>(args.into_iter(), &options).unwrap(),
+            Args {
+                verbose: false,
+                color: Some(false),
+                no_cache: Some(true),
+                cache: None,
+            }
+        );
+        assert!(from_iter::<Args, _>(args.into_iter()).is_err());
+
+        #[derive(Debug, Deserialize)]
+        struct Count {
+            #[allow(dead_code)]
+            count: u32,
+        }
+        assert!(
+            from_iter_with::<Count, _>(["[", "--no-count", "]"].into_iter(), &options).is_err()
+        );
+    }
+
+    #[test]
+    fn optional_bools() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Args {
+            a: Option<bool>,
+            b: Option<bool>,
+            c: Option<bool>,
+            d: Option<bool>,
+            e: Option<bool>,
+        }
+        let options = DeserializerOptions::new().negation(true);
+        let args = ["[", "--a", "--b", "-n", "--no-c", "--d", "-f", "]"];
+        assert_eq!(
+            from_iter_with::<Args, _>(args.into_iter(), &options).unwrap(),
+            Args {
+                a: Some(true),
+                b: None,
+                c: Some(false),
+                d: Some(false),
+                e: None,
+            }
+        );
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Plain {
+            name: Option<String>,
+            verbose: bool,
+        }
+        assert!(from_iter::<Plain, _>(["[", "--name", "]"].into_iter()).is_err());
+        assert!(from_iter::<Plain, _>(["[", "--verbose", "]"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn missing_fields() {
+        #[derive(Debug, Deserialize, Default, PartialEq)]
+        #[serde(default)]
+        struct Defaults {
+            port: u16,
+            tags: Vec<String>,
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Args {
+            host: String,
+            user: Option<String>,
+            #[serde(default)]
+            retries: u8,
+            #[serde(default)]
+            defaults: Defaults,
+        }
+        let args = |args: &[&'static str]| from_iter::<Args, _>(args.iter().copied());
+        let expected = Args {
+            host: "h".to_string(),
+            user: None,
+            retries: 0,
+            defaults: Defaults::default(),
+        };
+        assert_eq!(args(&["[", "--host", "h", "]"]).unwrap(), expected);
+        for defaults in [&["[--]"][..], &["[]"], &["[", "]"]] {
+            let mut params = vec!["[", "--defaults"];
+            params.extend(defaults);
+            params.extend(["--host", "h", "]"]);
+            assert_eq!(args(&params).unwrap(), expected);
+        }
+        assert_eq!(
+            args(&["[", "--defaults", "[", "--port", "80", "]", "]"])
+                .unwrap_err()
+                .to_string(),
+            "missing field `host`"
+        );
+        let err = args(&[]).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `host`");
+        assert!(matches!(err, Error::MissingField("host")), "{:?}", err);
+        assert_eq!(err.error_report().kind, "missing_field");
+        assert_eq!(
+            from_iter::<Defaults, _>([].into_iter()).unwrap(),
+            Defaults::default()
+        );
+    }
+
+    #[test]
+    fn all_missing_fields() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Db {
+            host: String,
+            port: u16,
+            name: Option<String>,
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Args {
+            user: String,
+            level: E,
+            #[serde(default)]
+            retries: u8,
+            db: Db,
+            tags: Vec<String>,
+        }
+        let err = |args: &[&'static str]| {
+            from_iter::<Args, _>(args.iter().copied())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(&["[", "--db", "[", "--port", "1", "]", "]"]),
+            "missing: --host in /db, --user, --level, --tags"
+        );
+        assert_eq!(err(&[]), "missing: --user, --level, --db, --tags");
+        assert_eq!(err(&["[--]"]), "missing: --user, --level, --db, --tags");
+        let args = [
+            "[", "--user", "u", "--level", "Unit", "--tags", "[]", "--db", "[--]", "]",
+        ];
+        assert_eq!(err(&args), "missing: --host in /db, --port in /db");
+        assert_eq!(
+            err(&[
+                "[", "--user", "u", "--level", "Unit", "--db", "[", "--host", "h", "--port", "1",
+                "]", "]"
+            ]),
+            "missing field `tags`"
+        );
+        assert_eq!(
+            err(&["[", "--user", "u", "--level", "x", "]"]),
+            "arg 4 `x`: unknown variant `x`, expected one of `Unit`, `Newtype`, `Tuple`, `Struct`"
+        );
+
+        // every struct missing a field is found at once, and only a few shown
+        let mut args = vec!["["];
+        args.extend(["[", "--port", "1", "]"].repeat(1000));
+        args.extend(["[", "--host", "h", "]", "]"]);
+        let err = from_iter::<Vec<Db>, _>(args.into_iter()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing: --host in /0, --host in /1, --host in /2, --host in /3, --host in /4, ..."
+        );
+        let args = ["[", "[", "--port", "1", "]", "[--]", "]"];
+        let err = from_iter::<Vec<Db>, _>(args.into_iter()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing: --host in /0, --host in /1, --port in /1"
+        );
+    }
+
+    #[test]
+    fn positional_structs() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Target {
+            host: String,
+            port: u16,
+            #[serde(default)]
+            tls: bool,
+        }
+        let options = DeserializerOptions::new().positional_structs(true);
+        let parse = |args: &[&'static str]| {
+            from_iter_with::<Vec<Target>, _>(args.iter().copied(), &options)
+        };
+        assert_eq!(
+            parse(&["[", "[", "h", "1", "-t", "]", "[", "2", "2", "]", "]"]).unwrap(),
+            [
+                Target {
+                    host: "h".to_string(),
+                    port: 1,
+                    tls: true,
+                },
+                Target {
+                    host: "2".to_string(),
+                    port: 2,
+                    tls: false,
+                },
+            ]
+        );
+        let keyed = ["[", "[", "--port", "3", "--host", "h", "]", "]"];
+        assert_eq!(parse(&keyed).unwrap()[0].port, 3);
+        assert!(parse(&["[", "[", "h", "x", "]", "]"]).is_err());
+        assert!(parse(&["[", "[", "h", "1", "true", "x", "]", "]"]).is_err());
+
+        // without the option, a word that looks like a number isn't a string
+        let args = ["[", "--host", "2", "--port", "2", "]"];
+        assert!(from_iter::<Target, _>(args.into_iter()).is_err());
+    }
+
+    #[test]
+    fn mixed_positional() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Copy {
+            src: String,
+            dst: String,
+            #[serde(default)]
+            force: bool,
+            mode: Option<Mode>,
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Mode {
+            owner: String,
+            group: String,
+        }
+        let options = DeserializerOptions::new()
+            .unbracketed(true)
+            .positional_fields(2);
+        let parse =
+            |args: &[&'static str]| from_iter_with::<Copy, _>(args.iter().copied(), &options);
+        assert_eq!(
+            parse(&["a", "b", "--force", "-t"]).unwrap(),
+            Copy {
+                src: "a".to_string(),
+                dst: "b".to_string(),
+                force: true,
+                mode: None,
+            }
+        );
+        // nested structs are keyed as usual
+        let args = ["a", "b", "--mode", "[", "--owner", "o", "--group", "g", "]"];
+        assert_eq!(parse(&args).unwrap().mode.unwrap().group, "g");
+        assert!(parse(&["a", "b", "--mode", "[", "o", "g", "]"]).is_err());
+        let keyed = parse(&["--dst", "b", "--src", "a"]).unwrap();
+        assert_eq!((keyed.src.as_str(), keyed.dst.as_str()), ("a", "b"));
+        assert_eq!(parse(&[]).unwrap_err().to_string(), "missing: --src, --dst");
+
+        // in brackets as well, without `unbracketed`
+        let options = DeserializerOptions::new().positional_fields(2);
+        let args = [
+            "[", "a", "b", "--mode", "[", "--owner", "o", "--group", "g", "]", "]",
+        ];
+        let copy: Copy = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(
+            (copy.src.as_str(), copy.mode.unwrap().owner.as_str()),
+            ("a", "o")
+        );
+    }
+
+    #[test]
+    fn type_config() {
+        use serde::{Deserializer, Serialize, Serializer};
+
+        #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+        #[serde(remote = "Self")]
+        struct Sync {
+            dst: String,
+            #[serde(default)]
+            dry_run: bool,
+            #[serde(default)]
+            inner: Plain,
+        }
+
+        #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+        struct Plain {
+            #[serde(default)]
+            dry_run: bool,
+        }
+
+        impl crate::ShonConfig for Sync {
+            fn config() -> TypeConfig {
+                TypeConfig::new()
+                    .key_style(KeyStyle::Kebab)
+                    .flags(true)
+                    .positional_fields(1)
+            }
+        }
+
+        impl Serialize for Sync {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                type_config::serialize(self, serializer, Self::serialize)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Sync {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                type_config::deserialize(deserializer, Self::deserialize)
+            }
+        }
+
+        let sync = Sync {
+            dst: "d".to_string(),
+            dry_run: true,
+            inner: Plain { dry_run: true },
+        };
+        // the config stays with its own struct
+        let args = [
+            "[",
+            "d",
+            "--dry-run",
+            "--inner",
+            "[",
+            "--dry_run",
+            "-t",
+            "]",
+            "]",
+        ];
+        assert_eq!(crate::to_raw_params(&sync).unwrap(), args);
+        assert_eq!(from_iter::<Sync, _>(args.into_iter()).unwrap(), sync);
+        let args = ["[", "d", "--dry_run", "--inner", "[", "--dry-run", "]", "]"];
+        assert!(from_iter::<Sync, _>(args.into_iter()).is_err());
+
+        let options = DeserializerOptions::new().negation(true);
+        let args = ["[", "--dst", "d", "--no-dry-run", "]"];
+        let sync: Sync = from_iter_with(args.into_iter(), &options).unwrap();
+        assert!(!sync.dry_run);
+    }
+
+    #[test]
+    fn nested_documents() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Job {
+            name: String,
+            env: HashMap<String, String>,
+            retry: Retry,
+        }
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Retry {
+            count: u32,
+        }
+
+        let args = [
+            "[",
+            "--name",
+            "[ not a document",
+            "--env",
+            "[ --HOME '/home/a b' ]",
+            "--retry",
+            " [ --count 3 ] ",
+            "]",
+        ];
+        let options = DeserializerOptions::new().nested_documents(true);
+        let job: Job = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(job.name, "[ not a document");
+        assert_eq!(job.env["HOME"], "/home/a b");
+        assert_eq!(job.retry, Retry { count: 3 });
+        assert!(from_iter::<Job, _>(args.into_iter()).is_err());
+
+        // documents may nest in quotes
+        #[derive(Debug, Deserialize)]
+        struct Queue {
+            job: Job,
+        }
+        let args = [
+            "[",
+            "--job",
+            "[ --name a --env [--] --retry '[ --count 3 ]' ]",
+            "]",
+        ];
+        let queue: Queue = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(queue.job.retry.count, 3);
+        let args = ["[", "--count", "1", "]"];
+        assert!(from_iter_with::<Retry, _>(args.into_iter(), &options).is_ok());
+        let args = [
+            "[",
+            "--name",
+            "a",
+            "--env",
+            "[--]",
+            "--retry",
+            "[ --count 3 ] 4",
+            "]",
+        ];
+        assert!(from_iter_with::<Job, _>(args.into_iter(), &options).is_err());
+    }
+
+    #[test]
+    fn lazy_sequences() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: i32,
+        }
+        let iter = |args: &[&str]| {
+            let args = std::iter::once("./binary").chain(args.iter().copied());
+            seq_iter::<Point, _>(args.map(String::from)).collect::<Vec<_>>()
+        };
+        let points = iter(&[
+            "[", "[", "--x", "1", "]", "[--]", "[", "--x", "-2", "]", "]",
+        ]);
+        // the missing field ends it
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].as_ref().unwrap(), &Point { x: 1 });
+        assert!(points[1].is_err());
+        let points = iter(&["[", "[", "--x", "1", "]", "[", "--x", "-2", "]", "]"]);
+        assert_eq!(points[1].as_ref().unwrap(), &Point { x: -2 });
+        assert!(iter(&[]).is_empty());
+        assert!(iter(&["[]"]).is_empty());
+        assert!(iter(&["[", "--x", "1", "]"])[0].is_err());
+        assert!(iter(&["[]", "x"])[0].is_err());
+        let unclosed = iter(&["[", "[", "--x", "1", "]"]);
+        assert_eq!(unclosed.len(), 2);
+        assert!(unclosed[1].is_err());
+
+        // the args are only read as far as the elements taken
+        let endless = ["./binary", "["].into_iter().chain(iter::repeat("7"));
+        let numbers = seq_iter::<u32, _>(endless.map(String::from));
+        let numbers: Vec<u32> = numbers.take(3).map(Result::unwrap).collect();
+        assert_eq!(numbers, [7, 7, 7]);
+        let args = ["./binary", "[", "1", "x", "]"].map(String::from);
+        let err = seq_iter::<u32, _>(args.into_iter())
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "arg 3: invalid number `x`");
+    }
+
+    #[test]
+    fn source_maps() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Copy {
+            src: String,
+            force: bool,
+            #[serde(default)]
+            tags: Vec<String>,
+        }
+        let map = |args: &[&str], options: DeserializerOptions| {
+            let args = std::iter::once("./binary").chain(args.iter().copied());
+            let (_, map) = from_args_mapped::<Copy, _>(args.map(String::from), &options).unwrap();
+            map.iter()
+                .map(|(pointer, range)| (pointer.to_string(), range))
+                .collect::<Vec<_>>()
+        };
+        let args = [
+            "[",
+            "a",
+            "--no-force",
+            "--tags",
+            "[",
+            "--",
+            "x",
+            "",
+            "y",
+            "]",
+            "]",
+        ];
+        let options = DeserializerOptions::new()
+            .positional_fields(1)
+            .negation(true);
+        assert_eq!(
+            map(&args, options),
+            [
+                ("".to_string(), 1..12),
+                ("/src".to_string(), 2..3),
+                ("/force".to_string(), 3..4),
+                ("/tags".to_string(), 4..11),
+                ("/tags/0".to_string(), 6..8),
+                ("/tags/1".to_string(), 9..10),
+            ]
+        );
+        let options = DeserializerOptions::new()
+            .unbracketed(true)
+            .dialect(crate::Dialect::new().brackets("{", "}"));
+        let args = ["--src", "[", "--force", "-t", "--tags", "{}"];
+        let map = map(&args, options);
+        assert_eq!(map[0], ("".to_string(), 1..7));
+        assert_eq!(map[1], ("/src".to_string(), 1..3));
+        assert_eq!(map[3], ("/tags".to_string(), 5..7));
+        // positional structs are still read by field
+        let (_, map) = from_args_mapped::<(u8, Copy), _>(
+            ["./binary", "[", "1", "[", "a", "true", "[]", "]", "]"]
+                .map(String::from)
+                .into_iter(),
+            &DeserializerOptions::new().positional_structs(true),
+        )
+        .unwrap();
+        assert_eq!(map.get("/1/force"), Some(5..6));
+    }
+
+    #[test]
+    fn truncated_input() {
+        for args in [
+            &["[", "--str"][..],
+            &["[", "--e", "[", "Tuple", "[", "1"],
+            &["[", "--seq", "[", "a"],
+            &["[", "--map", "[--]", "--e"],
+        ] {
+            let err = from_iter::<Test, _>(args.iter().copied()).unwrap_err();
+            assert!(matches!(err.inner(), Error::Eof), "{:?}: {}", args, err);
+            assert_eq!(err.index(), Some(args.len()));
+            assert_eq!(err.token(), None);
+        }
+        let err = from_iter::<E, _>(["[", "Unit"].into_iter()).unwrap_err();
+        assert!(matches!(err.inner(), Error::Eof));
+        // the same as without the brackets
+        assert_eq!(
+            from_iter::<E, _>(["[", "Unit", "]"].into_iter()).unwrap(),
+            E::Unit
+        );
+    }
+
+    #[test]
+    fn arbitrary_input() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Fuzz {
+            #[serde(default)]
+            a: Vec<String>,
+            #[serde(default)]
+            n: u32,
+            #[serde(default)]
+            v: bool,
+            inner: Option<Box<Fuzz>>,
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Flattened {
+            #[serde(flatten)]
+            fuzz: Fuzz,
+            #[serde(flatten)]
+            extra: HashMap<String, crate::Value>,
+        }
+        const PARAMS: [&str; 19] = [
+            "[", "]", "[]", "[--]", "--", "", "--a", "--n", "--v", "--inner", "-v", "-t", "-f",
+            "-n", "1", "x", "}", "--a=1", "--no-v",
+        ];
+        // xorshift, for the same params on every run
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move |below: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % below as u64) as usize
+        };
+        // every combination of the options that move params around
+        for combination in 0..64 {
+            let on = |bit: u32| combination & (1 << bit) != 0;
+            let mut options = DeserializerOptions::new()
+                .repeated_keys(on(0))
+                .flags(on(1))
+                .inline_values(on(2))
+                .negation(on(3))
+                .duplicate_keys(match combination >> 4 {
+                    0 => DuplicateKeys::Default,
+                    1 => DuplicateKeys::First,
+                    2 => DuplicateKeys::Last,
+                    _ => DuplicateKeys::Error,
+                });
+            if on(1) {
+                options = options.counter("-v", "n");
+            }
+            for _ in 0..1000 {
+                let len = 1 + random(12);
+                let mut args = vec!["["];
+                args.extend((0..len).map(|_| PARAMS[random(PARAMS.len())]));
+                if random(4) > 0 {
+                    args.push("]");
+                }
+                let parse = || {
+                    let _ = from_iter_with::<crate::Value, _>(args.iter().copied(), &options);
+                    let _ = from_iter_with::<Fuzz, _>(args.iter().copied(), &options);
+                    let _ = from_iter_with::<Flattened, _>(args.iter().copied(), &options);
+                    let _ =
+                        from_iter_with::<HashMap<String, u32>, _>(args.iter().copied(), &options);
+                };
+                if std::panic::catch_unwind(std::panic::AssertUnwindSafe(parse)).is_err() {
+                    panic!("{:?} with options {}", args, combination);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn error_variants() {
+        let err = |args: &[&'static str]| from_iter::<Test, _>(args.iter().copied()).unwrap_err();
+        assert!(matches!(
+            err(&["]"]).inner(),
+            Error::UnbalancedBracket { found: None }
+        ));
+        let trailing = from_iter::<u32, _>(["1", "2"].into_iter()).unwrap_err();
+        assert!(matches!(trailing.inner(), Error::TrailingTokens { .. }));
+        assert_eq!(trailing.index(), Some(1));
+        assert_eq!(
+            trailing.to_string(),
+            "arg 1 `2`: 1 unexpected param after the end of the document: `2`"
+        );
+        let trailing = from_iter::<u32, _>(["1", "]", "--x", "2", "]"].into_iter()).unwrap_err();
+        assert_eq!(
+            trailing.to_string(),
+            "arg 1 `]`: 4 unexpected params after the end of the document: `]` `--x` `2` ..."
+        );
+        match trailing.inner() {
+            Error::TrailingTokens { remaining, first } => {
+                assert_eq!((*remaining, first.join(" ")), (Some(4), "] --x 2".into()))
+            }
+            err => panic!("{:?}", err),
+        }
+        match err(&["[", "--int", "x", "]"]).inner() {
+            Error::InvalidNumber(param) => assert_eq!(param, "x"),
+            err => panic!("{:?}", err),
+        }
+        match err(&["[", "--str", "a", "b", "]"]).inner() {
+            Error::ExpectedKey(param) => assert_eq!(param, "b"),
+            err => panic!("{:?}", err),
+        }
+        assert_eq!(
+            Error::ExpectedKey("b".to_string()).to_string(),
+            "expected a key, found `b`"
+        );
+    }
+
+    #[test]
+    fn error_positions() {
+        let args = ["./binary", "[", "--str", "a", "--int", " 1x ", "]"];
+        let err = from_args::<Test, _>(args.map(String::from).into_iter()).unwrap_err();
+        assert_eq!(err.index(), Some(5));
+        assert_eq!(err.token(), Some(" 1x "));
+        assert_eq!(err.to_string(), "arg 5 ` 1x `: invalid number `1x`");
+        assert_eq!(err.error_report().kind, "invalid_number");
+        let err = from_iter::<Vec<u8>, _>(["[", "1", "[]", "]"].into_iter()).unwrap_err();
+        assert_eq!(err.index(), Some(2));
+        // nested documents fail at the param they are in
+        let options = DeserializerOptions::new().nested_documents(true);
+        let err = from_iter_with::<Vec<Vec<u8>>, _>(["[", "[ 1 x ]", "]"].into_iter(), &options)
+            .unwrap_err();
+        assert_eq!(err.token(), Some("[ 1 x ]"));
+    }
+
+    #[test]
+    fn closing_brackets() {
+        let err = |args: &[&'static str]| from_iter::<E, _>(args.iter().copied()).unwrap_err();
+        assert_eq!(
+            err(&["[", "Newtype", "1", "2", "]"]).to_string(),
+            "arg 3 `2`: expected `]`, found `2`"
+        );
+        assert!(matches!(err(&["[", "Newtype", "1"]).inner(), Error::Eof));
+        let err = from_iter::<(u8, u8), _>(["[", "1", "2", "3", "]"].into_iter()).unwrap_err();
+        assert_eq!(err.token(), Some("3"));
+        // a mistyped closing bracket is reported at the `[` it leaves open
+        let err = from_iter::<Test, _>(["[", "--int", "1", "}"].into_iter()).unwrap_err();
+        assert!(matches!(err.inner(), Error::UnbalancedBracket { .. }));
+        let err = from_iter::<Vec<u32>, _>(["[", "1", "}"].into_iter()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "arg 0 `[`: `[` is never closed, `}` is not `]`"
+        );
+        let err = from_iter::<Vec<Vec<u32>>, _>(["[", "[", "1", ")", "]"].into_iter()).unwrap_err();
+        assert_eq!((err.index(), err.token()), (Some(1), Some("[")));
+        let seq: Vec<String> = from_iter(["[", "}", "--", ")", "]"].into_iter()).unwrap();
+        assert_eq!(seq, ["}", ")"]);
+        let err = from_iter::<Vec<String>, _>(["[", "--", "}"].into_iter()).unwrap_err();
+        assert!(matches!(err.inner(), Error::Eof));
+    }
+
+    #[test]
+    fn strings() {
+        let test: Test = from_str(
+            r#"[ --str "a \"b\"" --int 1 --data -t --seq [ 'c d' e\ f ] --map [--] --e Unit ]"#,
+        )
+        .unwrap();
+        assert_eq!(test.str, r#"a "b""#);
+        assert_eq!(test.seq, ["c d", "e f"]);
+        assert!(from_str::<Test>("[ --str 'a ]").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn os_args() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let args = || {
+            let invalid = OsString::from_vec(b"a\xffb".to_vec());
+            ["./binary", "[", "--path"]
+                .map(OsString::from)
+                .into_iter()
+                .chain([invalid, OsString::from("]")])
+        };
+        let err = from_os_args::<HashMap<String, String>, _>(args()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 3 `a\u{fffd}b`: invalid UTF-8");
+        let options = DeserializerOptions::new().lossy_utf8(true);
+        let map: HashMap<String, String> = from_os_args_with(args(), &options).unwrap();
+        assert_eq!(map["path"], "a\u{fffd}b");
+    }
+
+    #[test]
+    fn borrowed() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Borrowed<'a> {
+            key: &'a str,
+            escaped: &'a str,
+            #[serde(borrow)]
+            map: HashMap<&'a str, &'a str>,
+        }
+        let params = vec![
+            "[",
+            "--key",
+            " a ",
+            "--escaped",
+            "--",
+            "-1",
+            "--map",
+            "[",
+            "--b",
+            "c",
+            "]",
+            "]",
+        ];
+        let borrowed: Borrowed = from_slice(&params).unwrap();
+        assert_eq!(borrowed.key, "a");
+        assert_eq!(borrowed.escaped, "-1");
+        assert_eq!(borrowed.map["b"], "c");
+        // params changed on the way can't be borrowed
+        let options = DeserializerOptions::new().nested_documents(true);
+        let params = ["[", "[ a b ]", "]"];
+        let err = from_slice_with::<Vec<Vec<&str>>>(&params, &options).unwrap_err();
+        assert!(err.to_string().contains("borrowed"), "{}", err);
+        let owned: Vec<Vec<String>> = from_slice_with(&params, &options).unwrap();
+        assert_eq!(owned, [["a", "b"]]);
+    }
 
-    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
-    enum E {
-        Unit,
-        Newtype(u32),
-        Tuple(u32, u32),
-        Struct { a: u32 },
+    #[test]
+    fn strictness() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            #[serde(alias = "colour")]
+            color: bool,
+            #[serde(default)]
+            env: HashMap<String, String>,
+            #[serde(default)]
+            zip: Option<String>,
+        }
+        let args = [
+            "[", "--colour", "-t", "--env", "[", "--a", "b", "]", "--x", "1", "]",
+        ];
+        let parse =
+            |options: &DeserializerOptions| from_iter_with::<Args, _>(args.into_iter(), options);
+        assert!(parse(&DeserializerOptions::new()).unwrap().color);
+        let options = Deserializer::builder().deny_unknown_keys(true);
+        let err = parse(&options).unwrap_err();
+        assert_eq!((err.index(), err.token()), (Some(8), Some("--x")));
+        assert!(err.to_string().contains("unknown field `x`"), "{}", err);
+        let args = ["[", "--color", "-f", "--env", "[", "--a", "b", "]", "]"];
+        let args: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(args.env["a"], "b");
+
+        let args = ["[", "--color", "-t", "]", "extra"];
+        let err = from_iter::<Args, _>(args.into_iter()).unwrap_err();
+        assert!(matches!(err.inner(), Error::TrailingTokens { .. }));
+        let options = Deserializer::builder().trailing_params(true);
+        assert!(from_iter_with::<Args, _>(args.into_iter(), &options).is_ok());
+        let stream = args.into_iter().map(String::from);
+        assert!(from_stream_with::<Args, _>(stream, &options).is_ok());
+
+        let args = ["[", "--color", "-t", "--zip", "01234", "]"];
+        assert!(from_iter::<Args, _>(args.into_iter()).is_err());
+        let options = Deserializer::builder().numbers_as_strings(true);
+        let args: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(args.zip.as_deref(), Some("01234"));
+        let err =
+            from_iter_with::<Args, _>(["[", "--zip", "-t", "]"].into_iter(), &options).unwrap_err();
+        assert!(err.to_string().contains("expected a string"), "{}", err);
     }
 
     #[test]
-    fn deserialize() {
-        let v: Vec<&str> = vec![
-            "./binary",
+    fn flag_mode() {
+        #[derive(Debug, Deserialize)]
+        struct Inner {
+            quiet: bool,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            verbose: bool,
+            force: bool,
+            color: Option<bool>,
+            inner: Inner,
+            name: String,
+            port: u16,
+        }
+        let options = DeserializerOptions::new().flags(true);
+        let args = [
             "[",
-            "--str",
-            "data",
-            "--int",
-            "123",
-            "--int1",
-            "456",
-            "--int2",
-            "-n",
-            "--seq",
+            "--verbose",
+            "--inner",
+            "[--]",
+            "--name",
+            "a",
+            "--port",
+            "1",
+            "]",
+        ];
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert!(parsed.verbose && !parsed.force && !parsed.inner.quiet);
+        assert_eq!(
+            (parsed.color, parsed.name.as_str(), parsed.port),
+            (None, "a", 1)
+        );
+        let err = from_iter::<Args, _>(args.into_iter()).unwrap_err();
+        assert!(err.to_string().contains("missing value"), "{}", err);
+        let args = [
             "[",
-            "--",
-            "'hello there'",
-            "general",
-            "kenobi",
+            "--no-color",
+            "--inner",
+            "[--]",
+            "--name",
+            "a",
+            "--port",
+            "1",
             "]",
-            "--data",
-            "-t",
-            "--map",
+        ];
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!((parsed.verbose, parsed.color), (false, Some(false)));
+
+        // missing fields that aren't bools are still reported, all of them
+        let args = ["[", "--inner", "[", "--quiet", "]", "]"];
+        let err = from_iter_with::<Args, _>(args.into_iter(), &options).unwrap_err();
+        assert_eq!(err.to_string(), "missing: --name, --port");
+        let args = ["[", "--force", "--inner", "[--]", "--name", "a", "]"];
+        let err = from_iter_with::<Args, _>(args.into_iter(), &options).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `port`");
+
+        // however many bools are absent, the params are read once, again when
+        // other fields are left to serde, and again for every name of a field
+        // that turns out to be another one of it
+        #[derive(Debug, Deserialize)]
+        struct Item {
+            a: bool,
+            #[serde(alias = "bee")]
+            b: bool,
+            #[serde(default)]
+            tag: String,
+        }
+        #[derive(Clone)]
+        struct Counted<'a>(&'a Cell<usize>);
+        impl<'de> DeserializeSeed<'de> for Counted<'_> {
+            type Value = Vec<Item>;
+
+            fn deserialize<D>(self, deserializer: D) -> std::result::Result<Vec<Item>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                self.0.set(self.0.get() + 1);
+                Vec::deserialize(deserializer)
+            }
+        }
+        let reads = Cell::new(0);
+        let mut args = vec!["[".to_string()];
+        for i in 0..100 {
+            match i % 3 {
+                0 => args.push("[--]".to_string()),
+                1 => args.extend(["[", "--bee", "--tag", "x", "]"].map(String::from)),
+                _ => args.extend(["[", "--a", "]"].map(String::from)),
+            }
+        }
+        args.push("]".to_string());
+        let items = deserialize_params(
+            Counted(&reads),
+            args.into_iter().map(Cow::Owned),
+            &options,
+            0,
+            false,
+            false,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(reads.get(), 3);
+        assert_eq!(items.len(), 100);
+        assert!(!items[0].a && !items[0].b && items[0].tag.is_empty());
+        assert!(!items[1].a && items[1].b && items[1].tag == "x");
+        assert!(items[2].a && !items[2].b);
+
+        // hand-written impls tell about fields the way derived ones do,
+        // however they word anything else
+        #[derive(Debug, PartialEq)]
+        struct Manual {
+            quiet: bool,
+            level: u8,
+        }
+        impl<'de> Deserialize<'de> for Manual {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Manual, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                struct ManualVisitor;
+                impl<'de> Visitor<'de> for ManualVisitor {
+                    type Value = Manual;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("options")
+                    }
+
+                    fn visit_map<A>(self, mut map: A) -> std::result::Result<Manual, A::Error>
+                    where
+                        A: MapAccess<'de>,
+                    {
+                        let (mut quiet, mut level) = (None, None);
+                        while let Some(key) = map.next_key::<String>()? {
+                            match key.as_str() {
+                                "quiet" | "silent" if quiet.is_some() => {
+                                    return Err(de::Error::duplicate_field("quiet"))
+                                }
+                                "quiet" | "silent" => quiet = Some(map.next_value()?),
+                                "level" => level = Some(map.next_value()?),
+                                _ => return Err(de::Error::custom("no such option")),
+                            }
+                        }
+                        Ok(Manual {
+                            quiet: quiet.ok_or_else(|| de::Error::missing_field("quiet"))?,
+                            level: level.unwrap_or_default(),
+                        })
+                    }
+                }
+                deserializer.deserialize_struct(
+                    "Manual",
+                    &["quiet", "silent", "level"],
+                    ManualVisitor,
+                )
+            }
+        }
+        let manual = |args: &[&'static str]| {
+            from_iter_with::<Manual, _>(args.iter().copied(), &options).unwrap()
+        };
+        let expected = |quiet, level| Manual { quiet, level };
+        assert_eq!(manual(&["[", "--level", "2", "]"]), expected(false, 2));
+        assert_eq!(manual(&["[--]"]), expected(false, 0));
+        assert_eq!(manual(&["[", "--silent", "]"]), expected(true, 0));
+        assert_eq!(
+            manual(&["[", "--quiet", "--level", "1", "]"]),
+            expected(true, 1)
+        );
+        let err = from_iter::<Manual, _>(["[--]"].into_iter()).unwrap_err();
+        assert!(matches!(err, Error::MissingField("quiet")), "{:?}", err);
+    }
+
+    #[test]
+    fn repeated_keys() {
+        #[derive(Debug, Deserialize)]
+        struct Inner {
+            tag: Vec<u32>,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            include: Vec<String>,
+            #[serde(default)]
+            groups: Vec<Vec<String>>,
+            inner: Inner,
+            name: String,
+        }
+        let options = DeserializerOptions::new().repeated_keys(true);
+        let args = [
             "[",
-            "--one",
+            "--include",
+            "a",
+            "--inner",
+            "[",
+            "--tag",
+            "1",
+            "--tag",
             "2",
-            "--three",
-            "4",
             "]",
-            "--e",
-            "Unit",
+            "--include",
+            "--",
+            "-b",
+            "--name",
+            "x",
+            "--groups",
+            "[",
+            "c",
+            "]",
+            "--groups",
+            "[]",
             "]",
         ];
-        let v = v.into_iter().map(String::from);
-        let t: Test = from_args(v).unwrap();
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(parsed.include, ["a", "-b"]);
+        assert_eq!(parsed.inner.tag, [1, 2]);
+        assert_eq!(parsed.groups, [vec!["c".to_string()], vec![]]);
+        assert_eq!(parsed.name, "x");
+        let args = ["[", "--tag", "[", "1", "]", "--tag", "[]", "]"];
+        let err = from_iter::<Inner, _>(args.into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 5 `--tag`: duplicate field `tag`");
+        // every value is an element of its own
+        let err = from_iter_with::<Inner, _>(args.into_iter(), &options).unwrap_err();
+        assert!(err.to_string().contains("expected u32"), "{}", err);
+
+        // fields that don't take an array still can't be repeated
+        let args = ["[", "--include", "[]", "--name", "x", "--name", "y", "]"];
+        let err = from_iter_with::<Args, _>(args.into_iter(), &options).unwrap_err();
+        assert!(err.to_string().contains("expected a string"), "{}", err);
+
+        // a single value is an array of its own, in nested structs as well
+        let args = ["[", "--include", "a", "--inner", "[", "--tag", "1", "]"];
+        let args = args
+            .into_iter()
+            .chain(["--name", "x", "--include", "b", "]"]);
+        let parsed: Args = from_iter_with(args, &options).unwrap();
         assert_eq!(
-            t,
-            Test {
-                str: "data".to_string(),
-                int: 123,
-                int1: Some(456),
-                int2: None,
-                int3: None,
-                data: true,
-                seq: vec![
-                    "'hello there'".to_string(),
-                    "general".to_string(),
-                    "kenobi".to_string()
-                ],
-                map: HashMap::from([("one".to_string(), 2), ("three".to_string(), 4)]),
-                e: E::Unit,
-            }
+            (parsed.include, parsed.inner.tag),
+            (vec!["a".to_string(), "b".to_string()], vec![1])
+        );
+        let args = ["[", "--tag", "[", "1", "2", "]", "]"];
+        let parsed: Inner = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(parsed.tag, [1, 2]);
+
+        // keys without a value are missing it, unless they are all flags
+        #[derive(Debug, Deserialize)]
+        struct Flag {
+            #[serde(default)]
+            v: bool,
+        }
+        for (args, index) in [
+            (&["[", "--tag", "--tag", "1", "]"][..], 1),
+            (&["[", "--tag", "--tag", "]"], 1),
+            (&["[", "--tag", "1", "--tag", "]"], 3),
+        ] {
+            let err = from_iter_with::<Inner, _>(args.iter().copied(), &options).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("arg {} `--tag`: missing value", index)
+            );
+        }
+        let options = options.flags(true);
+        let args = ["[", "--v", "--v", "]"];
+        assert!(
+            from_iter_with::<Flag, _>(args.into_iter(), &options)
+                .unwrap()
+                .v
         );
     }
 
     #[test]
-    fn unit_struct() {
-        #[derive(Debug, Deserialize, PartialEq, Eq)]
-        struct Test {}
-        let v: Vec<&str> = vec!["[--]"];
-        let t: Test = from_iter(v.into_iter()).unwrap();
-        assert_eq!(t, Test {});
+    fn counters() {
+        #[derive(Debug, Deserialize)]
+        struct Inner {
+            #[serde(default)]
+            verbose: u32,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            #[serde(default)]
+            verbose: u8,
+            inner: Inner,
+            #[serde(default)]
+            env: HashMap<String, String>,
+        }
+        let options = DeserializerOptions::new().counter("-v", "verbose");
+        let inner = ["[", "-v", "-v", "]"];
+        let env = ["[", "--verbose", "x", "]"];
+        let args = ["[", "-v", "--inner"].iter().chain(&inner);
+        let args = args
+            .chain(&["--verbose", "--env"])
+            .chain(&env)
+            .chain(&["-v", "]"]);
+        let parsed: Args = from_iter_with(args.copied(), &options).unwrap();
+        assert_eq!((parsed.verbose, parsed.inner.verbose), (3, 2));
+        assert_eq!(parsed.env["verbose"], "x");
+        let parsed: Args =
+            from_iter_with(["[", "--inner", "[--]", "]"].into_iter(), &options).unwrap();
+        assert_eq!(parsed.verbose, 0);
+        let err =
+            from_iter_with::<Args, _>(["[", "-v", "2", "]"].into_iter(), &options).unwrap_err();
+        assert!(matches!(err.inner(), Error::ExpectedKey(_)), "{}", err);
+
+        // the counted keys are the same whichever comes first
+        let options = DeserializerOptions::new().counter("-v", "n");
+        #[derive(Debug, Deserialize)]
+        struct Count {
+            #[serde(default)]
+            n: u32,
+        }
+        let parse =
+            |args: &[&'static str]| from_iter_with::<Count, _>(args.iter().copied(), &options);
+        assert_eq!(parse(&["[", "-v", "--n", "]"]).unwrap().n, 2);
+        assert_eq!(parse(&["[", "--n", "-v", "]"]).unwrap().n, 2);
+        let err = parse(&["[", "-v", "--n", "1", "]"]).unwrap_err();
+        assert!(matches!(err.inner(), Error::ExpectedKey(_)), "{}", err);
+        let err = parse(&["[", "-v", "--n"]).unwrap_err();
+        assert!(matches!(err.inner(), Error::Eof), "{}", err);
     }
 
     #[test]
-    fn empty_seq() {
-        #[derive(Debug, Deserialize, PartialEq, Eq)]
-        struct Test {
-            seq: Vec<String>,
-            extra: i32,
+    fn kebab_keys() {
+        #[derive(Debug, Deserialize)]
+        struct Inner {
+            dry_run: bool,
         }
-        let v: Vec<&str> = vec!["[", "--seq", "[]", "--extra", "-1", "]"];
-        let t: Test = from_iter(v.into_iter()).unwrap();
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            max_retries: u32,
+            inner: Inner,
+            env: HashMap<String, String>,
+        }
+        let options = DeserializerOptions::new()
+            .key_style(KeyStyle::Kebab)
+            .negation(true);
+        let args = [
+            "[",
+            "--max-retries",
+            "3",
+            "--inner",
+            "[",
+            "--no-dry-run",
+            "]",
+            "--env",
+            "[",
+            "--A-B",
+            "c",
+            "]",
+            "]",
+        ];
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(parsed.max_retries, 3);
+        assert!(!parsed.inner.dry_run);
+        assert_eq!(parsed.env["A-B"], "c");
+        let args = [
+            "[",
+            "--max_retries",
+            "3",
+            "--inner",
+            "[",
+            "--dry_run",
+            "-t",
+            "]",
+            "--env",
+            "[--]",
+            "]",
+        ];
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert!(parsed.inner.dry_run);
+        let args = [
+            "[",
+            "--max-retries",
+            "3",
+            "--inner",
+            "[--]",
+            "--env",
+            "[--]",
+            "]",
+        ];
+        assert!(from_iter::<Args, _>(args.into_iter()).is_err());
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            name: String,
+            #[serde(default)]
+            tags: Vec<String>,
+        }
+        let args = [
+            "[", "--name", "a", "--tags", "[", "--", "--name", "]", "--name", "[", "b", "]", "]",
+        ];
+        let parse = |policy| {
+            let options = DeserializerOptions::new().duplicate_keys(policy);
+            from_iter_with::<Args, _>(args.into_iter(), &options)
+        };
+        let err = parse(DuplicateKeys::Default).unwrap_err();
+        assert_eq!(err.to_string(), "arg 8 `--name`: duplicate field `name`");
+        let err = parse(DuplicateKeys::Error).unwrap_err();
+        assert_eq!(err.to_string(), "arg 1 `--name`: duplicate key `--name`");
+        let args = parse(DuplicateKeys::First).unwrap();
         assert_eq!(
-            t,
-            Test {
-                seq: vec![],
-                extra: -1
-            }
+            (args.name.as_str(), args.tags),
+            ("a", vec!["--name".to_string()])
+        );
+        let err = parse(DuplicateKeys::Last).unwrap_err();
+        assert!(err.to_string().contains("expected a string"), "{}", err);
+
+        let args = [
+            "[", "--a", "1", "--b", "[", "--a", "2", "]", "--a", "3", "]",
+        ];
+        let options = DeserializerOptions::new().duplicate_keys(DuplicateKeys::Last);
+        let value: crate::Value = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(
+            value.to_raw_params().unwrap(),
+            ["[", "--a", "3", "--b", "[", "--a", "2", "]", "]"]
         );
+
+        // a value missing from any of them is missing, whichever is kept
+        for policy in [DuplicateKeys::First, DuplicateKeys::Last] {
+            let options = DeserializerOptions::new().duplicate_keys(policy);
+            for (args, index) in [
+                (&["[", "--n", "--n", "2", "]"][..], 1),
+                (&["[", "--n", "1", "--n", "]"], 3),
+            ] {
+                let err = from_iter_with::<HashMap<String, u32>, _>(args.iter().copied(), &options)
+                    .unwrap_err();
+                assert_eq!(
+                    err.to_string(),
+                    format!("arg {} `--n`: missing value", index)
+                );
+            }
+        }
+
+        // the later keys are found without reading through the rest of the
+        // object for every key
+        let mut args = vec!["./binary".to_string(), "[".to_string()];
+        for value in ["1", "2"] {
+            for i in 0..20000 {
+                args.extend([format!("--k{}", i), value.to_string()]);
+            }
+        }
+        args.push("]".to_string());
+        let map: HashMap<String, u32> = from_args_with(args.into_iter(), &options).unwrap();
+        assert_eq!(map.len(), 20000);
+        assert!(map.values().all(|value| *value == 2));
     }
 
     #[test]
-    fn empty_map() {
-        #[derive(Debug, Deserialize, PartialEq, Eq)]
-        struct Test {
-            map: HashMap<String, String>,
-            extra: i32,
+    fn suggestions() {
+        assert_eq!(edit_distance("prot", "port"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Server {
+            host: String,
+            port: u16,
         }
-        let v: Vec<&str> = vec!["[", "--map", "[--]", "--extra", "-1", "]"];
-        let t: Test = from_iter(v.into_iter()).unwrap();
+        let args = ["[", "--host", "a", "--port", "80", "]"];
+        let server: Server = from_iter(args.into_iter()).unwrap();
+        assert_eq!((server.host.as_str(), server.port), ("a", 80));
+        let args = ["[", "--host", "a", "--prot", "80", "]"];
+        let err = from_iter::<Server, _>(args.into_iter()).unwrap_err();
         assert_eq!(
-            t,
-            Test {
-                map: HashMap::new(),
-                extra: -1,
+            err.to_string(),
+            "arg 3 `--prot`: unknown field `prot`, did you mean `port`?"
+        );
+        match err.inner() {
+            Error::UnknownField {
+                field,
+                expected,
+                suggestion,
+            } => {
+                assert_eq!(field, "prot");
+                assert_eq!(*expected, ["host", "port"]);
+                assert_eq!(suggestion.as_deref(), Some("port"));
             }
+            err => panic!("{:?}", err),
+        }
+        let args = ["[", "--host", "a", "--xyz", "80", "]"];
+        let err = from_iter::<Server, _>(args.into_iter()).unwrap_err();
+        assert!(
+            err.to_string().ends_with("expected `host` or `port`"),
+            "{}",
+            err
+        );
+
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            #[serde(default)]
+            max_retries: u32,
+        }
+        let options = DeserializerOptions::new()
+            .deny_unknown_keys(true)
+            .key_style(KeyStyle::Kebab);
+        let args = ["[", "--max-retries", "3", "]"];
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(parsed.max_retries, 3);
+        let args = ["[", "--max-retry", "3", "]"];
+        let err = from_iter_with::<Args, _>(args.into_iter(), &options).unwrap_err();
+        assert!(
+            err.to_string().ends_with("did you mean `max-retries`?"),
+            "{}",
+            err
         );
     }
 
     #[test]
-    fn deserialize_enum_variants() {
-        let expected = E::Unit;
+    fn catch_all() {
+        #[derive(Debug, Deserialize)]
+        struct Args {
+            name: String,
+            #[serde(flatten)]
+            extra: HashMap<String, crate::Value>,
+        }
+        let args = [
+            "[", "--name", "a", "--x", "1", "--y", "[", "b", "]", "--z", "]",
+        ];
+        let err = from_iter::<Args, _>(args.into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 9 `--z`: missing value");
+        let options = DeserializerOptions::new().flags(true);
+        let parsed: Args = from_iter_with(args.into_iter(), &options).unwrap();
+        assert_eq!(parsed.name, "a");
+        assert_eq!(parsed.extra["x"].as_u64(), Some(1));
+        assert_eq!(parsed.extra["y"].to_raw_params().unwrap(), ["[", "b", "]"]);
+        assert_eq!(parsed.extra["z"].as_bool(), Some(true));
+
+        // the object is let go of however reading it ends
+        let options = DeserializerOptions::new().max_depth(0);
+        assert!(from_iter_with::<Args, _>(args.into_iter(), &options).is_err());
+        assert!(FLATTENING.with(|flattening| flattening.borrow().is_empty()));
+    }
+
+    #[test]
+    fn inline_values() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Server {
+            port: u16,
+            tags: Vec<String>,
+            note: Option<String>,
+        }
+        let options = DeserializerOptions::new().inline_values(true);
+        let spaced = [
+            "[", "--port", "8080", "--tags", "[", "a", "]", "--note", "--", "--x", "]",
+        ];
+        let inline = ["[", "--port=8080", "--tags=[", "a", "]", "--note=--x", "]"];
+        let server: Server = from_iter_with(spaced.into_iter(), &options).unwrap();
         assert_eq!(
-            from_iter::<E, _>(vec!["Unit"].into_iter()).unwrap(),
-            expected
+            from_iter_with::<Server, _>(inline.into_iter(), &options).unwrap(),
+            server
         );
-
-        let expected = E::Newtype(1);
+        let stream = inline.into_iter().map(String::from);
         assert_eq!(
-            from_iter::<E, _>(vec!["[", "--Newtype", "1", "]"].into_iter()).unwrap(),
-            expected
+            from_stream_with::<Server, _>(stream, &options).unwrap(),
+            server
         );
+        assert!(from_iter::<Server, _>(inline.into_iter()).is_err());
 
-        let expected = E::Tuple(1, 2);
+        let err =
+            from_iter_with::<Server, _>(["[", "--port=x", "]"].into_iter(), &options).unwrap_err();
+        assert_eq!(err.to_string(), "arg 1 `--port=x`: invalid number `x`");
+    }
+
+    #[test]
+    fn streamed() {
+        let params = |params: &[&str]| params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        #[derive(Debug, Deserialize)]
+        struct Server {
+            host: String,
+            ports: Vec<u16>,
+        }
+        let args = params(&["[", "--host", "shon", "--ports", "[ ", "80", "]", "]"]);
+        let server: Server = from_stream(args.into_iter()).unwrap();
+        assert_eq!((server.host.as_str(), server.ports), ("shon", vec![80]));
+        // the params can't be read again to find the other missing fields
+        let err = from_stream::<Server, _>(params(&["[", "]"]).into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `host`");
+
+        // only as many params are read as needed, even if they never end
+        let endless = ["[", "1", "2", "]"].into_iter().chain(iter::repeat("x"));
+        let err = from_stream::<Vec<u32>, _>(endless.map(String::from)).unwrap_err();
         assert_eq!(
-            from_iter::<E, _>(vec!["[", "--Tuple", "[", "1", "2", "]", "]"].into_iter()).unwrap(),
-            expected
+            err.to_string(),
+            "arg 4: unexpected params after the end of the document: `x` `x` `x` ..."
         );
+        let err =
+            from_stream::<Vec<u32>, _>(params(&["[", "1", "x", "]"]).into_iter()).unwrap_err();
+        assert_eq!(err.to_string(), "arg 2: invalid number `x`");
+        let err = from_stream::<Vec<u32>, _>(params(&["[", "1"]).into_iter()).unwrap_err();
+        assert_eq!(err.index(), Some(2));
 
-        let expected = E::Struct { a: 1 };
+        let options = DeserializerOptions::new()
+            .unbracketed(true)
+            .dialect(Dialect::new().brackets("(", ")"));
+        let value: HashMap<String, Vec<String>> = from_stream_with(
+            params(&["--a", "(", "[", ")", "", "--b", "()"]).into_iter(),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(value["a"], ["["]);
+        assert!(value["b"].is_empty());
+        let err = from_stream_with::<HashMap<String, u32>, _>(
+            params(&["--a", "1", "--b", "x"]).into_iter(),
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "arg 3: invalid number `x`");
+
+        let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+        let options = DeserializerOptions::new().dialect(dialect);
+        let err = from_stream_with::<Vec<String>, _>(
+            params(&["[", "a", "]", "--"]).into_iter(),
+            &options,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("streamed"), "{}", err);
+    }
+
+    #[test]
+    fn stream_deserializer() {
+        let params = |params: &[&str]| params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Line {
+            level: String,
+            #[serde(default)]
+            code: u32,
+        }
+
+        let args = params(&[
+            "[", "--level", "info", "]", "[", "--level", "warn", "--code", "7", "]",
+        ]);
+        let lines: Vec<Line> = StreamDeserializer::new(args.into_iter())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(lines[1].code, 7);
+        assert_eq!(lines.len(), 2);
+
+        // scalars are documents of their own
+        let numbers = StreamDeserializer::<u8>::new(params(&["1", "", "2"]).into_iter());
+        assert_eq!(numbers.collect::<Result<Vec<_>>>().unwrap(), [1, 2]);
+
+        // the iterator ends after an error
+        let mut lines = StreamDeserializer::<Line>::new(
+            params(&[
+                "[", "--level", "info", "]", "[", "--code", "x", "]", "[", "]",
+            ])
+            .into_iter(),
+        );
+        assert!(lines.next().unwrap().is_ok());
+        let err = lines.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "arg 6: invalid number `x`");
+        assert!(lines.next().is_none());
+        let mut lines = StreamDeserializer::<Line>::new(params(&["[", "]"]).into_iter());
         assert_eq!(
-            from_iter::<E, _>(vec!["[", "--Struct", "[", "--a", "1", "]", "]"].into_iter())
-                .unwrap(),
-            expected
+            lines.next().unwrap().unwrap_err().to_string(),
+            "missing field `level`"
+        );
+
+        let options = DeserializerOptions::new().max_params(3);
+        let mut numbers = StreamDeserializer::<u8>::with_options(
+            params(&["1", "2", "3", "4"]).into_iter(),
+            &options,
         );
+        assert_eq!(numbers.by_ref().take(3).count(), 3);
+        let err = numbers.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "more than 3 params");
     }
 
     #[test]
-    fn ser_then_de() {
-        let initial = Test {
-            str: "data".to_string(),
-            int: 123,
-            int1: Some(456),
-            int2: None,
-            int3: None,
-            data: true,
-            seq: vec![
-                "'hello there'".to_string(),
-                "general".to_string(),
-                "kenobi".to_string(),
-            ],
-            map: HashMap::from([("one".to_string(), 2), ("three".to_string(), 4)]),
-            e: E::Newtype(3),
-        };
-        let mut out = ser::to_params(&initial).unwrap();
-        // shell escapes with regular quotes are weird, so we have to emplace that single quote
-        // back. TODO: output easily reingestible data
-        let pos = out
-            .iter()
-            .position(|i| i == "''\\''hello there'\\'''")
-            .unwrap();
-        out[pos] = "'hello there'".to_string();
-        out.insert(0, "./binary".to_string());
-        let output = from_args(out.into_iter()).unwrap();
-        assert_eq!(initial, output);
+    fn empty_strings() {
+        #[derive(Debug, Deserialize)]
+        struct Named {
+            name: String,
+            #[serde(default)]
+            verbose: bool,
+        }
+
+        let value: Vec<String> = from_iter(["[", "--", "", "", "a", " ", "]"].into_iter()).unwrap();
+        assert_eq!(value, ["", "a"]);
+        let named: Named =
+            from_iter(["[", "--name", "--", "", "--verbose", "-t", "]"].into_iter()).unwrap();
+        assert_eq!((named.name.as_str(), named.verbose), ("", true));
+        let args = ["[", "--name", "--", "", "]"].map(String::from);
+        assert_eq!(from_stream::<Named, _>(args.into_iter()).unwrap().name, "");
+
+        // an escaped `--` doesn't escape the empty param after it
+        let value: Vec<String> = from_iter(["[", "--", "--", "", "]"].into_iter()).unwrap();
+        assert_eq!(value, ["--"]);
+
+        assert_eq!(ser::to_raw_params(&"").unwrap(), ["--", ""]);
+        let value: Vec<String> =
+            from_params(ser::to_raw_params(&["", "a"]).unwrap().into_iter()).unwrap();
+        assert_eq!(value, ["", "a"]);
+    }
+
+    #[test]
+    fn response_file_strings() {
+        let options = DeserializerOptions::new().response_files(true);
+        let strings = vec!["@/nonexistent/args.txt".to_string(), "a@b".to_string()];
+        let params = ser::to_raw_params(&strings).unwrap();
+        assert_eq!(params[1..3], ["--", "@/nonexistent/args.txt"]);
+        let value: Vec<String> = from_params_with(params.into_iter(), &options).unwrap();
+        assert_eq!(value, strings);
+    }
+
+    #[test]
+    fn keep_whitespace() {
+        #[derive(Debug, Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let options = DeserializerOptions::new().keep_whitespace(true);
+        let named: Named =
+            from_iter_with(["[", "--name", " a b\n", "]"].into_iter(), &options).unwrap();
+        assert_eq!(named.name, " a b\n");
+        let args = ["[", "--name", "\t", "]"].map(String::from);
+        let named: Named = from_stream_with(args.into_iter(), &options).unwrap();
+        assert_eq!(named.name, "\t");
+        let err = from_iter_with::<Named, _>(["[", " --name", "a", "]"].into_iter(), &options);
+        assert!(err.is_err());
+
+        // trimmed otherwise
+        let named: Named = from_iter(["[", " --name", " a ", "]"].into_iter()).unwrap();
+        assert_eq!(named.name, "a");
+    }
+
+    #[test]
+    fn identifiers() {
+        let key: Key = from_iter(["--name"].into_iter()).unwrap();
+        assert_eq!(key.0, "name");
+        let key: Key = from_iter(["-o"].into_iter()).unwrap();
+        assert_eq!(key.0, "-o");
     }
 }
@@ -0,0 +1,120 @@
+//! Serializes a `rust_decimal::Decimal` as its exact decimal word, for use
+//! with `#[serde(with = "serde_shon::decimal")]`.
+//!
+//! The derived impls of `Decimal` go through a string or a float, which
+//! either quotes the number or loses precision on the way. With this module
+//! the param is written as a plain number and read back digit for digit.
+//! Other formats see the usual `Decimal` representation. Enabled by the
+//! `rust_decimal` feature.
+//!
+//! ```
+//! use rust_decimal::Decimal;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Payment {
+//!     #[serde(with = "serde_shon::decimal")]
+//!     amount: Decimal,
+//! }
+//!
+//! let payment = Payment { amount: "0.10".parse().unwrap() };
+//! let args = ["[", "--amount", "0.10", "]"];
+//! assert_eq!(serde_shon::to_raw_params(&payment).unwrap(), args);
+//! assert_eq!(serde_shon::from_iter::<Payment, _>(args.into_iter()).unwrap(), payment);
+//! ```
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+use crate::private;
+
+pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(private::DECIMAL, &value.to_string())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct DecimalVisitor;
+
+    impl<'de> Visitor<'de> for DecimalVisitor {
+        type Value = Decimal;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a decimal number")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Decimal, E>
+        where
+            E: de::Error,
+        {
+            // rounding would defeat the point, so anything that doesn't fit
+            // is an error
+            Decimal::from_str_exact(v)
+                .or_else(|_| Decimal::from_scientific(v))
+                .map_err(|err| E::custom(format!("invalid decimal {}: {}", v, err)))
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Decimal, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            <Decimal as Deserialize>::deserialize(deserializer)
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(private::DECIMAL, DecimalVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Prices {
+        #[serde(with = "crate::decimal")]
+        a: Decimal,
+        #[serde(with = "crate::decimal")]
+        b: Decimal,
+    }
+
+    #[test]
+    fn exact() {
+        let prices = Prices {
+            a: "-0.30".parse().unwrap(),
+            b: "12345678901234567890.123456789".parse().unwrap(),
+        };
+        let params = crate::to_raw_params(&prices).unwrap();
+        assert_eq!(
+            params,
+            [
+                "[",
+                "--a",
+                "-0.30",
+                "--b",
+                "12345678901234567890.123456789",
+                "]"
+            ]
+        );
+        let back: Prices = crate::de::from_params(params.into_iter()).unwrap();
+        assert_eq!(back, prices);
+        assert_eq!(back.a.to_string(), "-0.30");
+
+        let args = ["[", "--a", "--", "1.5", "--b", "1e2", "]"];
+        let parsed: Prices = crate::from_iter(args.into_iter()).unwrap();
+        assert_eq!(parsed.a.to_string(), "1.5");
+        assert_eq!(parsed.b, Decimal::from(100));
+
+        let args = ["[", "--a", "x", "--b", "1", "]"];
+        assert!(crate::from_iter::<Prices, _>(args.into_iter()).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+// Variations of the SHON syntax, which are translated from and into the
+// standard tokens at the edges so the parser itself only knows one syntax.
+
+use crate::parse::{self, CLOSE, EMPTY_ARRAY, EMPTY_OBJECT, OPEN, STRING_ESCAPE};
+use crate::{Error, Result};
+
+/// The tokens a document is written with, to be set on both the
+/// `Serializer` and the `DeserializerOptions`.
+///
+/// ```
+/// use serde_shon::{Dialect, DeserializerOptions, Serializer};
+///
+/// // zsh reads `[` as the start of a glob qualifier
+/// let dialect = Dialect::new().brackets("{", "}");
+/// let mut ser = Serializer::new().dialect(dialect.clone());
+/// ser.serialize_document(&vec![vec!["[", "b"]]).unwrap();
+/// let params = ser.into_params();
+/// assert_eq!(params, ["{", "{", "--", "[", "b", "}", "}"]);
+///
+/// let options = DeserializerOptions::new().dialect(dialect);
+/// let args = std::iter::once("./binary".to_string()).chain(params);
+/// let value: Vec<Vec<String>> = serde_shon::from_args_with(args, &options).unwrap();
+/// assert_eq!(value, [["[", "b"]]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialect {
+    open: String,
+    close: String,
+    double_dash: DoubleDash,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            open: OPEN.to_string(),
+            close: CLOSE.to_string(),
+            double_dash: DoubleDash::default(),
+        }
+    }
+}
+
+/// What a bare `--` param stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DoubleDash {
+    /// The next param is a string, whatever it looks like.
+    #[default]
+    StringEscape,
+    /// The end of the options, like in most command line tools. The params
+    /// after it go into the field of the outermost struct marked with
+    /// `#[shon(rest)]`, as strings. Strings can't be escaped then, so
+    /// serializing one that looks like a number or key is an error.
+    EndOfOptions,
+}
+
+impl Dialect {
+    /// The standard syntax.
+    pub fn new() -> Self {
+        Dialect::default()
+    }
+
+    /// Opens and closes arrays and objects with the given tokens instead of
+    /// `[` and `]`, like `{` and `}` or `(` and `)`. The empty array and
+    /// object become `{}` and `{--}`. Params that look like the standard
+    /// brackets are plain strings then.
+    ///
+    /// # Panics
+    ///
+    /// If the tokens are equal, empty, have whitespace around them, start
+    /// with a dash or are numbers, which would make them ambiguous.
+    pub fn brackets<O, C>(mut self, open: O, close: C) -> Self
+    where
+        O: Into<String>,
+        C: Into<String>,
+    {
+        let (open, close) = (open.into(), close.into());
+        for token in [&open, &close] {
+            assert!(
+                !token.is_empty()
+                    && token.trim() == token
+                    && !token.starts_with('-')
+                    && parse::classify(token) != parse::Token::EmptyArray
+                    && parse::classify(token) != parse::Token::EmptyObject
+                    && token.parse::<f64>().is_err(),
+                "invalid bracket token {:?}",
+                token
+            );
+        }
+        assert_ne!(open, close, "brackets have to differ");
+        self.open = open;
+        self.close = close;
+        self
+    }
+
+    /// Picks what a bare `--` stands for.
+    ///
+    /// ```
+    /// # #[cfg(feature = "derive")] {
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_shon::{shon, Dialect, DeserializerOptions, DoubleDash, Serializer};
+    ///
+    /// #[shon]
+    /// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    /// struct Run {
+    ///     image: String,
+    ///     #[shon(rest)]
+    ///     command: Vec<String>,
+    /// }
+    ///
+    /// let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+    /// let args = ["./binary", "[", "--image", "alpine", "]", "--", "ls", "--all"];
+    /// let options = DeserializerOptions::new().dialect(dialect.clone());
+    /// let run: Run = serde_shon::from_args_with(args.map(String::from).into_iter(), &options)
+    ///     .unwrap();
+    /// assert_eq!(run.command, ["ls", "--all"]);
+    ///
+    /// let mut ser = Serializer::new().dialect(dialect);
+    /// ser.serialize_document(&run).unwrap();
+    /// assert_eq!(ser.into_params(), args[1..]);
+    /// # }
+    /// ```
+    pub fn double_dash(mut self, meaning: DoubleDash) -> Self {
+        self.double_dash = meaning;
+        self
+    }
+
+    pub(crate) fn ends_options(&self) -> bool {
+        self.double_dash == DoubleDash::EndOfOptions
+    }
+
+    /// Splits off the params after the `--` that ends the options, if the
+    /// dialect has one.
+    pub(crate) fn split_rest<S>(&self, params: &mut Vec<S>) -> Result<Option<Vec<S>>>
+    where
+        S: AsRef<str>,
+    {
+        if !self.ends_options() {
+            return Ok(None);
+        }
+        let mut depth = 0usize;
+        for (i, param) in params.iter().enumerate() {
+            match param.as_ref().trim() {
+                token if token == self.open => depth += 1,
+                token if token == self.close => depth = depth.saturating_sub(1),
+                STRING_ESCAPE if depth > 0 => {
+                    return Err(Error::Message(
+                        "`--` ends the options, which can't happen within brackets".to_string(),
+                    ))
+                }
+                STRING_ESCAPE => {
+                    let rest = params.split_off(i + 1);
+                    params.pop();
+                    return Ok(Some(rest));
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Splits off the params after the document, if it starts with a
+    /// bracket, see `DeserializerOptions::trailing_rest`.
+    pub(crate) fn split_trailing<S>(&self, params: &mut Vec<S>) -> Option<Vec<S>>
+    where
+        S: AsRef<str>,
+    {
+        if params.first().map(|param| param.as_ref().trim()) != Some(self.open.as_str()) {
+            return None;
+        }
+        let mut depth = 0usize;
+        let mut escaped = false;
+        for (i, param) in params.iter().enumerate() {
+            match param.as_ref().trim() {
+                _ if escaped => escaped = false,
+                STRING_ESCAPE => escaped = true,
+                token if token == self.open => depth += 1,
+                token if token == self.close => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Some(params.split_off(i + 1));
+            }
+        }
+        None
+    }
+
+    fn is_standard(&self) -> bool {
+        self.open == OPEN && self.close == CLOSE
+    }
+
+    pub(crate) fn opens(&self, param: &str) -> bool {
+        param.trim_start().starts_with(self.open.as_str())
+    }
+
+    // The structural tokens of the dialect, in the order of the standard
+    // ones they stand for.
+    fn tokens(&self) -> [String; 4] {
+        [
+            self.open.clone(),
+            self.close.clone(),
+            format!("{}{}", self.open, self.close),
+            format!("{}{}{}", self.open, STRING_ESCAPE, self.close),
+        ]
+    }
+
+    /// Turns params written in this dialect into standard ones.
+    pub(crate) fn read(&self, params: Vec<String>) -> Vec<String> {
+        let params = params.into_iter().enumerate().collect();
+        self.read_positioned(params)
+            .into_iter()
+            .map(|(_, param)| param)
+            .collect()
+    }
+
+    /// Like `read`, keeping the position in the input of every param. The
+    /// escapes it adds take the position of the param they escape.
+    pub(crate) fn read_positioned<S>(&self, params: Vec<(usize, S)>) -> Vec<(usize, S)>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        match self.reader() {
+            Some(reader) => reader.translate(params),
+            None => params,
+        }
+    }
+
+    /// Turns params written in this dialect into standard ones one at a
+    /// time, unless it is the standard one.
+    pub(crate) fn reader(&self) -> Option<Translate> {
+        match self.is_standard() {
+            true => None,
+            false => Some(Translate::new(self.tokens(), STANDARD.map(String::from))),
+        }
+    }
+
+    /// Turns standard params into ones written in this dialect.
+    pub(crate) fn write(&self, params: Vec<String>) -> Vec<String> {
+        if self.is_standard() {
+            return params;
+        }
+        let params = params.into_iter().enumerate().collect();
+        Translate::new(STANDARD.map(String::from), self.tokens())
+            .translate(params)
+            .into_iter()
+            .map(|(_, param)| param)
+            .collect()
+    }
+}
+
+const STANDARD: [&str; 4] = [OPEN, CLOSE, EMPTY_ARRAY, EMPTY_OBJECT];
+
+// Replaces the structural tokens `from` with the ones in `to`. Strings that
+// look like one of `to` get escaped, and escaped params are left alone.
+pub(crate) struct Translate {
+    from: [String; 4],
+    to: [String; 4],
+    // the last param was an escape
+    escaped: bool,
+}
+
+impl Translate {
+    fn new(from: [String; 4], to: [String; 4]) -> Self {
+        Translate {
+            from,
+            to,
+            escaped: false,
+        }
+    }
+
+    fn translate<S>(mut self, params: Vec<(usize, S)>) -> Vec<(usize, S)>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        let mut out = Vec::with_capacity(params.len());
+        for (position, param) in params {
+            self.push(&mut out, position, param);
+        }
+        out
+    }
+
+    /// Translates the next param, adding the params it turns into to `out`.
+    pub(crate) fn push<S>(&mut self, out: &mut Vec<(usize, S)>, position: usize, param: S)
+    where
+        S: AsRef<str> + From<String>,
+    {
+        let token = param.as_ref().trim();
+        if self.escaped {
+            self.escaped = false;
+        } else if token == STRING_ESCAPE {
+            self.escaped = true;
+        } else if let Some(i) = self.from.iter().position(|t| t == token) {
+            out.push((position, self.to[i].clone().into()));
+            return;
+        } else if self.to.iter().any(|t| t == token) {
+            out.push((position, STRING_ESCAPE.to_string().into()));
+        }
+        out.push((position, param));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn params(params: &[&str]) -> Vec<String> {
+        params.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn translate_brackets() {
+        let dialect = Dialect::new().brackets("(", ")");
+        let standard = params(&["[", "--a", "[]", "--b", "[--]", "--c", "--", "(", "(", "]"]);
+        let translated = params(&[
+            "(", "--a", "()", "--b", "(--)", "--c", "--", "(", "--", "(", ")",
+        ]);
+        assert_eq!(dialect.write(standard.clone()), translated);
+        assert_eq!(
+            dialect.read(translated),
+            params(&["[", "--a", "[]", "--b", "[--]", "--c", "--", "(", "--", "(", "]"])
+        );
+        assert_eq!(
+            dialect.read(params(&[" ( ", "[", "--", "]", ")"])),
+            params(&["[", "--", "[", "--", "]", "]"])
+        );
+        assert_eq!(Dialect::new().read(standard.clone()), standard);
+    }
+
+    #[test]
+    fn split_rest() {
+        let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+        let mut options = params(&["[", "--a", "1", "]", "--", "x", "--", "]"]);
+        let rest = dialect.split_rest(&mut options).unwrap();
+        assert_eq!(options, params(&["[", "--a", "1", "]"]));
+        assert_eq!(rest, Some(params(&["x", "--", "]"])));
+        assert!(dialect.split_rest(&mut params(&["[", "--", "]"])).is_err());
+        assert_eq!(dialect.split_rest(&mut params(&["a"])).unwrap(), None);
+        let mut escaped = params(&["--", "-1"]);
+        assert_eq!(Dialect::new().split_rest(&mut escaped).unwrap(), None);
+    }
+
+    #[test]
+    fn split_trailing() {
+        let dialect = Dialect::new().brackets("(", ")");
+        let mut document = params(&["(", "--a", "--", ")", "(", ")", ")", "x", "("]);
+        let rest = dialect.split_trailing(&mut document);
+        assert_eq!(document, params(&["(", "--a", "--", ")", "(", ")", ")"]));
+        assert_eq!(rest, Some(params(&["x", "("])));
+        assert_eq!(dialect.split_trailing(&mut params(&["x", "y"])), None);
+        assert_eq!(dialect.split_trailing(&mut params(&["(", "x"])), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid bracket token")]
+    fn invalid_brackets() {
+        let _ = Dialect::new().brackets("-(", ")");
+    }
+}
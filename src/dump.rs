@@ -0,0 +1,191 @@
+// A structural view of params, for finding out why a document doesn't parse
+// into the type it is meant for.
+
+use std::fmt::Write;
+
+use crate::parse::{self, classify, Scalar, Token};
+use crate::{Error, Result};
+
+/// Describes the structure of the given params, without the binary in front,
+/// as an indented tree. Every line names what a value was parsed as, along
+/// with the position of its params in `args`.
+///
+/// ```
+/// let args = ["[", "--name", "shon", "--ports", "[", "80", "--", "443", "]", "]"];
+/// assert_eq!(
+///     serde_shon::dump_tree(&args).unwrap(),
+///     "object (args 0..9)\n  \
+///        name: string \"shon\" (arg 2)\n  \
+///        ports: array (args 4..8)\n    \
+///          number 80 (arg 5)\n    \
+///          string \"443\" (args 6..7)\n"
+/// );
+/// ```
+///
+/// Params that don't fit the grammar are reported with their position.
+pub fn dump_tree<T>(args: &[T]) -> Result<String>
+where
+    T: AsRef<str>,
+{
+    let mut escaped = false;
+    let mut dump = Dump {
+        tokens: args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| (i, arg.as_ref().trim()))
+            .filter(|(_, arg)| parse::keep(&mut escaped, arg))
+            .collect(),
+        pos: 0,
+        lines: Vec::new(),
+    };
+    if dump.tokens.is_empty() {
+        return Ok("null (no args)\n".to_string());
+    }
+    dump.value(0, "")?;
+    if let Some((index, arg)) = dump.tokens.get(dump.pos) {
+        return Err(Error::Message(format!(
+            "unexpected `{}` at arg {} after the end of the document",
+            arg, index
+        )));
+    }
+    let mut out = String::new();
+    for line in dump.lines {
+        let _ = writeln!(out, "{}", line);
+    }
+    Ok(out)
+}
+
+struct Dump<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    pos: usize,
+    lines: Vec<String>,
+}
+
+impl<'a> Dump<'a> {
+    fn next(&mut self, inside: Option<usize>) -> Result<(usize, &'a str)> {
+        let token = self.tokens.get(self.pos).copied().ok_or_else(|| {
+            Error::Message(match inside {
+                Some(open) => format!("unexpected end of input, `[` at arg {} is not closed", open),
+                None => "unexpected end of input".to_string(),
+            })
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn peek(&self, open: usize) -> Result<Token<'a>> {
+        self.tokens
+            .get(self.pos)
+            .map(|(_, arg)| classify(arg))
+            .ok_or_else(|| {
+                Error::Message(format!(
+                    "unexpected end of input, `[` at arg {} is not closed",
+                    open
+                ))
+            })
+    }
+
+    fn line(&mut self, depth: usize, label: &str, text: String) -> usize {
+        self.lines
+            .push(format!("{:width$}{}{}", "", label, text, width = depth * 2));
+        self.lines.len() - 1
+    }
+
+    fn value(&mut self, depth: usize, label: &str) -> Result<()> {
+        let (index, arg) = self.next(None)?;
+        let text = match classify(arg) {
+            Token::True => "true".to_string(),
+            Token::False => "false".to_string(),
+            Token::Null => "null".to_string(),
+            Token::Escape => {
+                let (end, arg) = self.next(None)?;
+                let text = format!("string {:?} (args {}..{})", arg, index, end);
+                self.line(depth, label, text);
+                return Ok(());
+            }
+            Token::Open => return self.group(depth, label, index),
+            Token::Close => {
+                return Err(Error::Message(format!(
+                    "unexpected `]` at arg {} without an open `[`",
+                    index
+                )))
+            }
+            Token::EmptyArray => "empty array".to_string(),
+            Token::EmptyObject => "empty object".to_string(),
+            Token::Key(key) => format!("string {:?}", key),
+            Token::Word(word) => match parse::word(word) {
+                Scalar::U64(uint) => format!("number {}", uint),
+                Scalar::I64(int) => format!("number {}", int),
+                Scalar::F64(float) => format!("number {}", float),
+                Scalar::Str(s) => format!("string {:?}", s),
+            },
+        };
+        self.line(depth, label, format!("{} (arg {})", text, index));
+        Ok(())
+    }
+
+    fn group(&mut self, depth: usize, label: &str, open: usize) -> Result<()> {
+        let object = matches!(self.peek(open)?, Token::Key(_));
+        let line = self.line(depth, label, String::new());
+        while self.peek(open)? != Token::Close {
+            if !object {
+                self.value(depth + 1, "")?;
+                continue;
+            }
+            let (index, arg) = self.next(Some(open))?;
+            let Token::Key(key) = classify(arg) else {
+                return Err(Error::Message(format!(
+                    "expected a key at arg {} in the object opened at arg {}, found `{}`",
+                    index, open, arg
+                )));
+            };
+            self.value(depth + 1, &format!("{}: ", key))?;
+        }
+        let (close, _) = self.next(Some(open))?;
+        let kind = if object { "object" } else { "array" };
+        self.lines[line].push_str(&format!("{} (args {}..{})", kind, open, close));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dump() {
+        let args = [
+            "[", "--a", "-t", "--b", "[]", "--c", "[", "--d", "-n", "]", "--e", "-1.5", "]",
+        ];
+        assert_eq!(
+            dump_tree(&args).unwrap(),
+            "object (args 0..12)
+  a: true (arg 2)
+  b: empty array (arg 4)
+  c: object (args 6..9)
+    d: null (arg 8)
+  e: number -1.5 (arg 11)
+"
+        );
+        assert_eq!(dump_tree(&["", " "]).unwrap(), "null (no args)\n");
+        assert_eq!(dump_tree(&["x"]).unwrap(), "string \"x\" (arg 0)\n");
+    }
+
+    #[test]
+    fn dump_errors() {
+        let err = |args: &[&str]| dump_tree(args).unwrap_err().to_string();
+        assert_eq!(
+            err(&["[", "1", "[", "2", "]"]),
+            "unexpected end of input, `[` at arg 0 is not closed"
+        );
+        assert_eq!(
+            err(&["[", "--a", "1", "b", "2", "]"]),
+            "expected a key at arg 3 in the object opened at arg 0, found `b`"
+        );
+        assert_eq!(
+            err(&["a", "b"]),
+            "unexpected `b` at arg 1 after the end of the document"
+        );
+        assert_eq!(err(&["]"]), "unexpected `]` at arg 0 without an open `[`");
+    }
+}
@@ -0,0 +1,340 @@
+// Flattening of values into environment variables and back, for tools that
+// read their configuration from the environment instead of the command line.
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Serialize};
+
+use crate::{Error, Result, Value};
+
+const SEPARATOR: &str = "__";
+
+/// Flattens a value into environment variable assignments. Nested keys are
+/// joined with `__` and uppercased, and array elements are keyed by their
+/// index. The prefix is put in front of every name as is.
+///
+/// ```
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Db {
+///     host: String,
+///     port: u16,
+/// }
+/// #[derive(Serialize)]
+/// struct Config {
+///     db: Db,
+/// }
+///
+/// let config = Config { db: Db { host: "localhost".into(), port: 5432 } };
+/// assert_eq!(
+///     serde_shon::to_env_vars(&config, "APP_").unwrap(),
+///     vec![
+///         ("APP_DB__HOST".to_string(), "localhost".to_string()),
+///         ("APP_DB__PORT".to_string(), "5432".to_string()),
+///     ]
+/// );
+/// ```
+pub fn to_env_vars<T>(value: &T, prefix: &str) -> Result<Vec<(String, String)>>
+where
+    T: Serialize,
+{
+    let mut vars = Vec::new();
+    flatten(&crate::to_value(value)?, prefix, &mut Vec::new(), &mut vars)?;
+    Ok(vars)
+}
+
+fn flatten(
+    value: &Value,
+    prefix: &str,
+    path: &mut Vec<String>,
+    vars: &mut Vec<(String, String)>,
+) -> Result<()> {
+    let name = || format!("{}{}", prefix, path.join(SEPARATOR).to_uppercase());
+    match value {
+        Value::Null => {}
+        Value::Bool(b) => vars.push((name(), b.to_string())),
+        Value::Number(n) => vars.push((name(), n.to_string())),
+        Value::String(s) => vars.push((name(), s.clone())),
+        Value::Array(a) => {
+            for (i, v) in a.iter().enumerate() {
+                path.push(i.to_string());
+                flatten(v, prefix, path, vars)?;
+                path.pop();
+            }
+        }
+        Value::Object(o) => {
+            for (k, v) in o.iter() {
+                if k.is_empty() || k.contains(SEPARATOR) {
+                    return Err(Error::Message(format!(
+                        "key {:?} can't be used in a variable name",
+                        k
+                    )));
+                }
+                path.push(k.to_string());
+                flatten(v, prefix, path, vars)?;
+                path.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The reverse of `to_env_vars`. Only variables starting with the prefix are
+/// considered, their names are lowercased and split on `__` into nested keys.
+/// The values are parsed into whatever type the target expects.
+pub fn from_env_vars<'a, T, I>(vars: I, prefix: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut root = Env::Node(Vec::new());
+    for (name, value) in vars {
+        let Some(path) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let path = path.to_lowercase();
+        let segments: Vec<&str> = path.split(SEPARATOR).collect();
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(Error::Message(format!("invalid variable name {}", name)));
+        }
+        root.insert(&segments, value)?;
+    }
+    T::deserialize(root)
+}
+
+/// Like `from_env_vars`, reading the variables of the current process.
+pub fn from_env<'a, T>(prefix: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_env_vars(std::env::vars(), prefix)
+}
+
+// Variables collected into a tree by their names. The leaves keep the raw
+// strings until the target type asks for something specific.
+enum Env {
+    Leaf(String),
+    Node(Vec<(String, Env)>),
+}
+
+impl Env {
+    fn insert(&mut self, segments: &[&str], value: String) -> Result<()> {
+        let Env::Node(entries) = self else {
+            return Err(Error::Message(format!(
+                "variable for {} is both a value and nested",
+                segments[0]
+            )));
+        };
+        let (key, rest) = (segments[0], &segments[1..]);
+        let pos = match entries.iter().position(|(k, _)| k == key) {
+            Some(pos) => pos,
+            None => {
+                entries.push((key.to_string(), Env::Node(Vec::new())));
+                entries.len() - 1
+            }
+        };
+        let entry = &mut entries[pos].1;
+        if rest.is_empty() {
+            if matches!(entry, Env::Node(n) if !n.is_empty()) {
+                return Err(Error::Message(format!(
+                    "variable for {} is both a value and nested",
+                    key
+                )));
+            }
+            *entry = Env::Leaf(value);
+            Ok(())
+        } else {
+            entry.insert(rest, value)
+        }
+    }
+
+    fn leaf(self) -> Result<String> {
+        match self {
+            Env::Leaf(s) => Ok(s),
+            Env::Node(_) => Err(Error::Message(
+                "expected a value, got nested keys".to_string(),
+            )),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Env {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                let s = self.leaf()?;
+                match s.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(Error::Message(format!("invalid value {}", s))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Env {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Env::Leaf(s) => visitor.visit_string(s),
+            Env::Node(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let Env::Node(entries) = self else {
+            return Err(Error::Message("expected indexed variables".to_string()));
+        };
+        let mut entries = entries
+            .into_iter()
+            .map(|(k, v)| match k.parse::<usize>() {
+                Ok(i) => Ok((i, v)),
+                Err(_) => Err(Error::Message(format!("invalid index {}", k))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort_by_key(|(i, _)| *i);
+        visitor.visit_seq(SeqDeserializer::new(entries.into_iter().map(|(_, v)| v)))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.leaf()?.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Db {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Config {
+        name: String,
+        level: Level,
+        verbose: bool,
+        ratio: f64,
+        db: Db,
+        tags: Vec<String>,
+        user: Option<String>,
+    }
+
+    #[test]
+    fn env_roundtrip() {
+        let config = Config {
+            name: "1234".to_string(),
+            level: Level::Info,
+            verbose: true,
+            ratio: 0.5,
+            db: Db {
+                host: "localhost".to_string(),
+                port: 5432,
+            },
+            tags: vec!["a".to_string(), "b c".to_string()],
+            user: None,
+        };
+        let vars = to_env_vars(&config, "APP_").unwrap();
+        assert_eq!(
+            vars,
+            [
+                ("APP_NAME", "1234"),
+                ("APP_LEVEL", "Info"),
+                ("APP_VERBOSE", "true"),
+                ("APP_RATIO", "0.5"),
+                ("APP_DB__HOST", "localhost"),
+                ("APP_DB__PORT", "5432"),
+                ("APP_TAGS__0", "a"),
+                ("APP_TAGS__1", "b c"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+
+        let mut vars = vars;
+        vars.push(("OTHER".to_string(), "ignored".to_string()));
+        vars.reverse();
+        assert_eq!(from_env_vars::<Config, _>(vars, "APP_").unwrap(), config);
+    }
+
+    #[test]
+    fn env_errors() {
+        let vars = |v: &[(&str, &str)]| {
+            v.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert!(from_env_vars::<Db, _>(vars(&[("HOST", "h"), ("PORT", "x")]), "").is_err());
+        assert!(from_env_vars::<Db, _>(vars(&[("HOST__A", "h"), ("HOST", "h")]), "").is_err());
+        assert!(from_env_vars::<Db, _>(vars(&[("HOST____A", "h")]), "").is_err());
+    }
+}
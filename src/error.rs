@@ -1,13 +1,190 @@
 use std::fmt::{self, Display};
+use std::io;
+use std::process::ExitCode;
 
-use serde::{de, ser};
+use serde::ser::SerializeStruct;
+use serde::{de, ser, Serialize, Serializer};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+// How many of the params left over after the document are shown.
+pub(crate) const TRAILING_SHOWN: usize = 3;
+
+// How many of the fields found missing are shown.
+pub(crate) const MISSING_SHOWN: usize = 5;
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    // TODO: actually work with error messages here
+    /// Any other problem, like a value of the wrong type, as described by
+    /// serde or the (de)serializer.
     Message(String),
+    /// A value that can't be serialized, as described by serde or the
+    /// serializer, which is up to the program rather than its arguments.
+    Serialize(String),
+    Io(io::Error),
+    /// Not a failure: the help or version text that was asked for, like with
+    /// `--help`, for the program to print before it exits successfully.
+    Help(String),
+    /// The params ended in the middle of a value, like in `[ --key`.
+    Eof,
+    /// A `]` without an array or object to close, or a `[` that is never
+    /// closed, with the other kind of closing bracket found in its place.
+    UnbalancedBracket {
+        found: Option<String>,
+    },
+    /// Params left over after the whole document was read, with how many
+    /// there are, which isn't known for `from_stream`, and the first few.
+    TrailingTokens {
+        remaining: Option<usize>,
+        first: Vec<String>,
+    },
+    /// A param that was expected to be a number, but isn't one.
+    InvalidNumber(String),
+    /// A param within an object where a key was expected.
+    ExpectedKey(String),
+    /// A field of a struct that isn't given and has no default.
+    MissingField(&'static str),
+    /// A field of a struct given more than once.
+    DuplicateField(&'static str),
+    /// A key that is none of the fields of a struct, with the fields there
+    /// are, and the one it was probably meant to be, which is shown instead
+    /// of them if a field is spelled closely enough.
+    UnknownField {
+        field: String,
+        expected: &'static [&'static str],
+        suggestion: Option<String>,
+    },
+    /// Arrays and objects nested deeper than allowed, see
+    /// `DeserializerOptions::max_depth`.
+    DepthLimitExceeded,
+    /// Another error, with the index of the arg it happened at and the arg
+    /// itself, which is `None` past the end.
+    At {
+        index: usize,
+        token: Option<String>,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// The conventional process exit status for this error: `0` for help
+    /// or version text, `1` for io and serialization errors, which aren't
+    /// about the given arguments, and `2` for the usage errors that are.
+    pub fn exit_code(&self) -> u8 {
+        match self.inner() {
+            Error::Help(_) => 0,
+            Error::Io(_) | Error::Serialize(_) => 1,
+            _ => 2,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Error::Message(_) => "message",
+            Error::Serialize(_) => "serialize",
+            Error::Io(_) => "io",
+            Error::Help(_) => "help",
+            Error::Eof => "eof",
+            Error::UnbalancedBracket { .. } => "unbalanced_bracket",
+            Error::TrailingTokens { .. } => "trailing_tokens",
+            Error::InvalidNumber(_) => "invalid_number",
+            Error::ExpectedKey(_) => "expected_key",
+            Error::MissingField(_) => "missing_field",
+            Error::DuplicateField(_) => "duplicate_field",
+            Error::UnknownField { .. } => "unknown_field",
+            Error::DepthLimitExceeded => "depth_limit_exceeded",
+            Error::At { error, .. } => error.kind(),
+        }
+    }
+
+    /// The error without the arg it happened at.
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::At { error, .. } => error.inner(),
+            _ => self,
+        }
+    }
+
+    /// The index of the arg the error happened at, if known. For
+    /// `from_args`, the binary in front is at 0.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Error::At { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// The arg the error happened at, if there is one.
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            Error::At { token, .. } => token.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// A structured description of the error, for tools that want to pass
+    /// failures on as data rather than as display text.
+    pub fn error_report(&self) -> ErrorReport {
+        ErrorReport::from(self)
+    }
+
+    /// Writes the error to the writer, usually stderr, and returns the
+    /// matching exit code, so it can be returned straight from `main`. Help
+    /// text is written as it is.
+    ///
+    /// ```
+    /// let err = serde_shon::from_iter::<u32, _>(["x"].into_iter()).unwrap_err();
+    /// let mut stderr = Vec::new();
+    /// let code = err.report(&mut stderr);
+    /// assert_eq!(code, std::process::ExitCode::from(2));
+    /// assert_eq!(stderr, b"error: arg 0 `x`: invalid number `x`\n");
+    /// ```
+    pub fn report<W>(&self, mut writer: W) -> ExitCode
+    where
+        W: io::Write,
+    {
+        // nothing is left to tell about failing to write the error
+        let _ = match self.inner() {
+            Error::Help(text) => writeln!(writer, "{}", text),
+            _ => writeln!(writer, "error: {}", self),
+        };
+        ExitCode::from(self.exit_code())
+    }
+}
+
+/// A serializable summary of an `Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    /// The error variant in snake case, like `"message"` or `"eof"`.
+    pub kind: &'static str,
+    /// The display text of the error.
+    pub message: String,
+    /// See `Error::exit_code`.
+    pub exit_code: u8,
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> Self {
+        ErrorReport {
+            kind: err.kind(),
+            message: err.to_string(),
+            exit_code: err.exit_code(),
+        }
+    }
+}
+
+impl Serialize for ErrorReport {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut report = serializer.serialize_struct("ErrorReport", 3)?;
+        report.serialize_field("kind", self.kind)?;
+        report.serialize_field("message", &self.message)?;
+        report.serialize_field("exit_code", &self.exit_code)?;
+        report.end()
+    }
 }
 
 impl ser::Error for Error {
@@ -15,7 +192,7 @@ impl ser::Error for Error {
     where
         T: Display,
     {
-        Error::Message(msg.to_string())
+        Error::Serialize(msg.to_string())
     }
 }
 
@@ -26,14 +203,147 @@ impl de::Error for Error {
     {
         Error::Message(msg.to_string())
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Error::DuplicateField(field)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Error::UnknownField {
+            field: field.to_string(),
+            expected,
+            suggestion: None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::Message(msg) => f.write_str(msg),
+            Error::Message(msg) | Error::Serialize(msg) | Error::Help(msg) => f.write_str(msg),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Eof => f.write_str("unexpected end of input"),
+            Error::UnbalancedBracket { found: None } => f.write_str("unexpected `]`"),
+            Error::UnbalancedBracket { found: Some(found) } => {
+                write!(f, "`[` is never closed, `{}` is not `]`", found)
+            }
+            Error::TrailingTokens { remaining, first } => {
+                match remaining {
+                    Some(1) => f.write_str("1 unexpected param")?,
+                    Some(remaining) => write!(f, "{} unexpected params", remaining)?,
+                    None => f.write_str("unexpected params")?,
+                }
+                f.write_str(" after the end of the document:")?;
+                for param in first {
+                    write!(f, " `{}`", param)?;
+                }
+                // there may be more than the ones shown
+                match remaining.map_or(first.len() == TRAILING_SHOWN, |r| r > first.len()) {
+                    true => f.write_str(" ..."),
+                    false => Ok(()),
+                }
+            }
+            Error::InvalidNumber(param) => write!(f, "invalid number `{}`", param),
+            Error::ExpectedKey(param) => write!(f, "expected a key, found `{}`", param),
+            Error::MissingField(field) => write!(f, "missing field `{}`", field),
+            Error::DuplicateField(field) => write!(f, "duplicate field `{}`", field),
+            Error::UnknownField {
+                field,
+                suggestion: Some(suggestion),
+                ..
+            } => write!(
+                f,
+                "unknown field `{}`, did you mean `{}`?",
+                field, suggestion
+            ),
+            // worded like serde does
+            Error::UnknownField {
+                field, expected, ..
+            } => {
+                write!(f, "unknown field `{}`, ", field)?;
+                match expected {
+                    [] => f.write_str("there are no fields"),
+                    [one] => write!(f, "expected `{}`", one),
+                    [one, other] => write!(f, "expected `{}` or `{}`", one, other),
+                    _ => {
+                        f.write_str("expected one of ")?;
+                        for (i, name) in expected.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            write!(f, "`{}`", name)?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            Error::DepthLimitExceeded => f.write_str("arrays and objects nested too deeply"),
+            Error::At {
+                index,
+                token: Some(token),
+                error,
+            } => write!(f, "arg {} `{}`: {}", index, token, error),
+            Error::At { index, error, .. } => write!(f, "arg {}: {}", index, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::At { error, .. } => Some(error),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        let err = crate::from_iter::<u32, _>(["x"].into_iter()).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        let err = crate::from_iter::<(u32, u32), _>(["[", "1", "]"].into_iter()).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        let err = Error::from(io::Error::other("broken pipe"));
+        assert_eq!(err.exit_code(), 1);
+        let mut out = Vec::new();
+        assert_eq!(err.report(&mut out), ExitCode::from(1));
+        assert_eq!(out, b"error: io error: broken pipe\n");
+
+        // the program is at fault for values it can't serialize
+        let map = std::collections::BTreeMap::from([(vec![1, 2], 3)]);
+        let err = crate::to_raw_params(&map).unwrap_err();
+        assert!(matches!(err, Error::Serialize(_)), "{:?}", err);
+        assert_eq!(err.exit_code(), 1);
+
+        let err = Error::Help("usage: app [ --name <name> ]".to_string());
+        let mut out = Vec::new();
+        assert_eq!(err.report(&mut out), ExitCode::from(0));
+        assert_eq!(out, b"usage: app [ --name <name> ]\n");
+        assert_eq!(err.error_report().kind, "help");
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn error_report() {
+        let err = Error::Message("bad input".to_string());
+        assert_eq!(
+            crate::to_string(&err.error_report()).unwrap(),
+            "[ --kind message --message 'bad input' --exit_code 2 ]"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// Quoting of serialized params for the different shells the output of
+// `to_string` may end up in.
+
+use std::borrow::Cow;
+
+use crate::parse::{CLOSE, EMPTY_ARRAY, EMPTY_OBJECT, OPEN};
+
+/// The shell that a serialized string is meant to be pasted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shell {
+    /// `sh`, `bash`, `zsh` and friends.
+    #[default]
+    Posix,
+    /// Nushell, which parses brackets as list literals and has its own
+    /// string syntax.
+    Nushell,
+    /// A recipe line in a Makefile. Make expands `$` before the line reaches
+    /// the shell, so it is doubled on top of the POSIX quoting. `%` is only
+    /// special in rules and functions, never in recipes, and is left alone.
+    Make,
+    /// The shell form of a `RUN` instruction in a Dockerfile, which is passed
+    /// to `sh` without any variable substitution by Docker itself.
+    Dockerfile,
+}
+
+impl Shell {
+    /// Escapes a single param so that the shell passes it on unchanged.
+    pub fn escape(self, token: &str) -> Cow<'_, str> {
+        match self {
+            Shell::Posix => match token {
+                // structural tokens are left as-is so the output stays
+                // readable
+                OPEN | CLOSE | EMPTY_ARRAY | EMPTY_OBJECT => Cow::Borrowed(token),
+                _ => shell_escape::unix::escape(token.into()),
+            },
+            Shell::Nushell => escape_nu(token),
+            Shell::Make => match Shell::Posix.escape(token) {
+                Cow::Borrowed(escaped) if !escaped.contains('$') => Cow::Borrowed(escaped),
+                escaped => Cow::Owned(escaped.replace('$', "$$")),
+            },
+            Shell::Dockerfile => Shell::Posix.escape(token),
+        }
+    }
+
+    // Makefiles and Dockerfiles end an instruction at the end of the line,
+    // and their line continuations can't be part of a quoted string, so a
+    // newline can't be passed on in a param at all.
+    pub(crate) fn check(self, token: &str) -> crate::Result<()> {
+        let name = match self {
+            Shell::Make => "Makefile",
+            Shell::Dockerfile => "Dockerfile",
+            Shell::Posix | Shell::Nushell => return Ok(()),
+        };
+        if token.contains(['\n', '\r']) {
+            return Err(crate::Error::Message(format!(
+                "param {:?} contains a line break, which can't be written in a {}",
+                token, name
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn escape_nu(token: &str) -> Cow<'_, str> {
+    let bare = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+    if !token.is_empty() && token.chars().all(bare) {
+        return Cow::Borrowed(token);
+    }
+    if !token.contains('\'') {
+        return Cow::Owned(format!("'{}'", token));
+    }
+    // raw strings can hold anything, as long as there are more hashes than
+    // in any `'#...` sequence of the token itself
+    let mut hashes = "#".to_string();
+    while token.contains(&format!("'{}", hashes)) {
+        hashes.push('#');
+    }
+    Cow::Owned(format!("r{}'{}'{}", hashes, token, hashes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn posix() {
+        assert_eq!(Shell::Posix.escape("["), "[");
+        assert_eq!(Shell::Posix.escape("--a"), "--a");
+        assert_eq!(Shell::Posix.escape("a b"), "'a b'");
+        assert_eq!(Shell::Posix.escape("it's"), r#"'it'\''s'"#);
+    }
+
+    #[test]
+    fn nushell() {
+        assert_eq!(Shell::Nushell.escape("--a"), "--a");
+        assert_eq!(Shell::Nushell.escape("-1.5"), "-1.5");
+        assert_eq!(Shell::Nushell.escape("["), "'['");
+        assert_eq!(Shell::Nushell.escape("[--]"), "'[--]'");
+        assert_eq!(Shell::Nushell.escape(""), "''");
+        assert_eq!(Shell::Nushell.escape("$env (x)"), "'$env (x)'");
+        assert_eq!(Shell::Nushell.escape("it's"), "r#'it's'#");
+        assert_eq!(Shell::Nushell.escape("a'#b"), "r##'a'#b'##");
+    }
+
+    #[test]
+    fn make_and_dockerfile() {
+        assert_eq!(Shell::Make.escape("["), "[");
+        assert_eq!(Shell::Make.escape("50%"), "'50%'");
+        assert_eq!(Shell::Make.escape("$HOME"), "'$$HOME'");
+        assert_eq!(Shell::Make.escape("a$b c"), "'a$$b c'");
+        assert_eq!(Shell::Dockerfile.escape("$HOME"), "'$HOME'");
+        assert!(Shell::Make.check("a\nb").is_err());
+        assert!(Shell::Dockerfile.check("a\r\nb").is_err());
+        assert!(Shell::Posix.check("a\nb").is_ok());
+    }
+}
@@ -0,0 +1,253 @@
+// Custom tokens registered by users, which stand for ordinary params. They
+// are expanded before parsing, so the grammar itself never sees them.
+
+use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use crate::parse::STRING_ESCAPE;
+use crate::{Error, Result};
+
+/// A domain-specific token like `-x<hex>` for bytes or `-d<epoch>` for dates,
+/// registered through `DeserializerOptions::extension` and
+/// `Serializer::extension`.
+///
+/// An extension token is its prefix followed by a non-empty body, and stands
+/// for the params that `expand` turns the body into. Strings that happen to
+/// look like an extension token are written with `--` in front like any
+/// other string starting with a dash, so they are never expanded.
+///
+/// ```
+/// use serde_shon::{DeserializerOptions, Extension, Serializer};
+///
+/// // `-x` followed by hex digits, standing for an array of bytes
+/// struct Hex;
+///
+/// impl Extension for Hex {
+///     fn prefix(&self) -> &str {
+///         "-x"
+///     }
+///
+///     fn expand(&self, body: &str) -> serde_shon::Result<Vec<String>> {
+///         let mut params = vec!["[".to_string()];
+///         for i in (0..body.len()).step_by(2) {
+///             let byte = body
+///                 .get(i..i + 2)
+///                 .and_then(|b| u8::from_str_radix(b, 16).ok())
+///                 .ok_or_else(|| serde_shon::Error::Message(format!("invalid hex {}", body)))?;
+///             params.push(byte.to_string());
+///         }
+///         params.push("]".to_string());
+///         Ok(params)
+///     }
+///
+///     fn collapse(&self, params: &[String]) -> Option<String> {
+///         let bytes = params.strip_prefix(&["[".to_string()])?.strip_suffix(&["]".to_string()])?;
+///         let bytes: Option<Vec<u8>> = bytes.iter().map(|b| b.parse().ok()).collect();
+///         Some(bytes?.iter().map(|b| format!("{:02x}", b)).collect())
+///     }
+/// }
+///
+/// let options = DeserializerOptions::new().extension(Hex);
+/// let bytes: Vec<u8> = serde_shon::from_iter_with(["-x00ff"].into_iter(), &options).unwrap();
+/// assert_eq!(bytes, [0, 255]);
+///
+/// let mut ser = Serializer::new().extension(Hex);
+/// ser.serialize_document(&bytes).unwrap();
+/// assert_eq!(ser.into_params(), ["-x00ff"]);
+/// ```
+///
+/// Extensions are unwind safe like the rest of the options, so a parse with
+/// them can still be wrapped in `std::panic::catch_unwind`.
+pub trait Extension: Send + Sync + RefUnwindSafe + UnwindSafe {
+    /// The prefix that marks the extension's tokens. It has to be a dash
+    /// followed by a letter, which keeps it apart from numbers and keys.
+    fn prefix(&self) -> &str;
+
+    /// The params that the body of a token stands for.
+    fn expand(&self, body: &str) -> Result<Vec<String>>;
+
+    /// The reverse of `expand`: a body standing for the given params of a
+    /// single value, if the extension has its own way of writing them.
+    fn collapse(&self, params: &[String]) -> Option<String> {
+        let _ = params;
+        None
+    }
+}
+
+// The extensions registered with the (de)serializer.
+#[derive(Clone, Default)]
+pub(crate) struct Extensions(Vec<Arc<dyn Extension>>);
+
+impl Extensions {
+    pub(crate) fn push<E>(&mut self, extension: E)
+    where
+        E: Extension + 'static,
+    {
+        let prefix = extension.prefix();
+        let mut chars = prefix.chars();
+        assert!(
+            chars.next() == Some('-') && chars.next().is_some_and(|c| c.is_alphabetic()),
+            "extension prefix {:?} has to be a dash followed by a letter",
+            prefix
+        );
+        self.0.push(Arc::new(extension));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Replaces every extension token with the params it stands for, which
+    /// take the position of the token in the input.
+    pub(crate) fn expand<S>(&self, params: Vec<(usize, S)>) -> Result<Vec<(usize, S)>>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        if self.is_empty() {
+            return Ok(params);
+        }
+        let mut expanded = Vec::with_capacity(params.len());
+        let mut expand = self.expander();
+        for (position, param) in params {
+            expand.push(&mut expanded, position, param)?;
+        }
+        Ok(expanded)
+    }
+
+    /// Expands params one at a time, see `expand`.
+    pub(crate) fn expander(&self) -> Expand {
+        Expand {
+            extensions: self.clone(),
+            escaped: false,
+        }
+    }
+
+    /// The extension token for the params of a value, if any extension
+    /// collapses them.
+    pub(crate) fn collapse(&self, params: &[String]) -> Option<String> {
+        self.0.iter().find_map(|extension| {
+            let body = extension.collapse(params).filter(|b| !b.is_empty())?;
+            Some(format!("{}{}", extension.prefix(), body))
+        })
+    }
+}
+
+// Expands the extension tokens in params given one at a time, remembering
+// whether the last one escaped the next.
+pub(crate) struct Expand {
+    extensions: Extensions,
+    escaped: bool,
+}
+
+impl Expand {
+    /// Adds the params that the next param stands for to `out`.
+    pub(crate) fn push<S>(
+        &mut self,
+        out: &mut Vec<(usize, S)>,
+        position: usize,
+        param: S,
+    ) -> Result<()>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        let token = param.as_ref().trim();
+        let extension = match self.escaped {
+            true => None,
+            false => self.extensions.0.iter().find(|e| {
+                token
+                    .strip_prefix(e.prefix())
+                    .is_some_and(|body| !body.is_empty())
+            }),
+        };
+        self.escaped = !self.escaped && token == STRING_ESCAPE;
+        match extension {
+            Some(extension) => {
+                let params = extension
+                    .expand(&token[extension.prefix().len()..])
+                    .map_err(|err| {
+                        Error::Message(format!("invalid extension token {}: {}", token, err))
+                    })?;
+                out.extend(params.into_iter().map(|param| (position, param.into())));
+            }
+            None => out.push((position, param)),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|e| e.prefix()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // `-d<days>` for a date as days since the epoch, with no way back
+    struct Days;
+
+    impl Extension for Days {
+        fn prefix(&self) -> &str {
+            "-d"
+        }
+
+        fn expand(&self, body: &str) -> Result<Vec<String>> {
+            let days: u64 = body
+                .parse()
+                .map_err(|_| Error::Message("not a number".to_string()))?;
+            Ok(vec![(days * 86400).to_string()])
+        }
+    }
+
+    fn expand(params: &[&str]) -> Result<Vec<String>> {
+        let mut extensions = Extensions::default();
+        extensions.push(Days);
+        let params = params.iter().map(|p| p.to_string()).enumerate().collect();
+        let expanded: Vec<(usize, String)> = extensions.expand(params)?;
+        Ok(expanded.into_iter().map(|(_, param)| param).collect())
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(expand(&["[", " -d2 ", "]"]).unwrap(), ["[", "172800", "]"]);
+        assert_eq!(expand(&["--", "-d2", "-d"]).unwrap(), ["--", "-d2", "-d"]);
+        assert_eq!(expand(&["--", "--", "-d1"]).unwrap(), ["--", "--", "86400"]);
+        assert_eq!(
+            expand(&["-dx"]).unwrap_err().to_string(),
+            "invalid extension token -dx: not a number"
+        );
+        assert!(Extensions::default().collapse(&["1".to_string()]).is_none());
+    }
+
+    #[test]
+    fn unwind_safe() {
+        let options = crate::DeserializerOptions::new().extension(Days);
+        let parsed = std::panic::catch_unwind(|| {
+            crate::from_iter_with::<u64, _>(["-d1"].into_iter(), &options)
+        });
+        assert_eq!(parsed.unwrap().unwrap(), 86400);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_prefix() {
+        struct Number;
+
+        impl Extension for Number {
+            fn prefix(&self) -> &str {
+                "-1"
+            }
+
+            fn expand(&self, _: &str) -> Result<Vec<String>> {
+                Ok(Vec::new())
+            }
+        }
+
+        Extensions::default().push(Number);
+    }
+}
@@ -0,0 +1,115 @@
+// A figment provider for command line SHON, enabled by the `figment`
+// feature.
+
+use ::figment::value::{Dict, Map};
+use ::figment::{Error, Metadata, Profile, Provider};
+
+/// A `figment::Provider` that reads a SHON object from the command line.
+///
+/// ```ignore
+/// let config: Config = Figment::new()
+///     .merge(Toml::file("config.toml"))
+///     .merge(Shon::args())
+///     .extract()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Shon {
+    params: Vec<String>,
+    profile: Profile,
+}
+
+impl Shon {
+    /// Reads the params from `env::args()`, skipping the binary.
+    pub fn args() -> Self {
+        Self::from_args(std::env::args())
+    }
+
+    /// Takes the params from an `env::args()` style iterator, skipping the
+    /// binary.
+    pub fn from_args<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        Self::from_params(iter.skip(1))
+    }
+
+    /// Takes the params as-is, without a binary in front.
+    pub fn from_params<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = String>,
+    {
+        Shon {
+            params: iter.collect(),
+            profile: Profile::Default,
+        }
+    }
+
+    /// Sets the profile the values are provided for, `Profile::Default` by
+    /// default.
+    pub fn profile<P: Into<Profile>>(mut self, profile: P) -> Self {
+        self.profile = profile.into();
+        self
+    }
+}
+
+impl Provider for Shon {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("SHON arguments")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let dict = if self.params.iter().all(|p| p.trim().is_empty()) {
+            Dict::new()
+        } else {
+            crate::de::from_params(self.params.iter().cloned())
+                .map_err(|err| Error::from(err.to_string()))?
+        };
+        Ok(self.profile.collect(dict))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::figment::providers::Serialized;
+    use ::figment::Figment;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Config {
+        host: String,
+        port: u16,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn layers_over_defaults() {
+        let defaults = Config {
+            host: "localhost".to_string(),
+            port: 80,
+            tags: vec![],
+        };
+        let args = [
+            "./binary", "[", "--port", "8080", "--tags", "[", "a", "]", "]",
+        ];
+        let config: Config = Figment::new()
+            .merge(Serialized::defaults(defaults))
+            .merge(Shon::from_args(args.into_iter().map(String::from)))
+            .extract()
+            .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                host: "localhost".to_string(),
+                port: 8080,
+                tags: vec!["a".to_string()],
+            }
+        );
+
+        let empty = Shon::from_args(["./binary".to_string()].into_iter());
+        assert!(empty.data().unwrap()[&Profile::Default].is_empty());
+        let bad = Shon::from_params(["x".to_string()].into_iter());
+        assert!(bad.data().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// `@path` params standing for the params in the file at the path, like the
+// response files of compilers and linkers.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::parse::STRING_ESCAPE;
+use crate::{words, Error, Result};
+
+// Files named in files are read as well, up to this many levels deep.
+const MAX_DEPTH: usize = 16;
+
+// Expands `@path` params given one at a time, remembering whether the last
+// one escaped the next.
+#[derive(Default)]
+pub(crate) struct ResponseFiles {
+    escaped: bool,
+}
+
+impl ResponseFiles {
+    /// Adds the params that the next param stands for to `out`. Params read
+    /// from files take the position of the `@path` param.
+    pub(crate) fn push<'a>(
+        &mut self,
+        out: &mut Vec<(usize, Cow<'a, str>)>,
+        position: usize,
+        param: Cow<'a, str>,
+    ) -> Result<()> {
+        let escaped = self.escaped;
+        self.escaped = !escaped && param == STRING_ESCAPE;
+        match escaped {
+            true => {
+                out.push((position, param));
+                Ok(())
+            }
+            false => read(out, position, param, &mut Vec::new()),
+        }
+    }
+}
+
+// `reading` holds the files being read, to catch a file naming itself.
+fn read<'a>(
+    out: &mut Vec<(usize, Cow<'a, str>)>,
+    position: usize,
+    param: Cow<'a, str>,
+    reading: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let path = match param.strip_prefix('@') {
+        Some(path) if !path.is_empty() => path,
+        _ => {
+            out.push((position, param));
+            return Ok(());
+        }
+    };
+    let error = |err| Error::Message(format!("can't read `{}`: {}", param, err));
+    let path = fs::canonicalize(path).map_err(error)?;
+    if reading.contains(&path) {
+        let err = format!("`{}` names a file that is already being read", param);
+        return Err(Error::Message(err));
+    }
+    if reading.len() == MAX_DEPTH {
+        let err = format!("`{}` is nested in more than {} files", param, MAX_DEPTH);
+        return Err(Error::Message(err));
+    }
+    let params = words::split_document(&fs::read_to_string(&path).map_err(error)?)?;
+    reading.push(path);
+    let mut escaped = false;
+    for param in params {
+        let escapes = !escaped && param == STRING_ESCAPE;
+        match escaped {
+            true => out.push((position, Cow::Owned(param))),
+            false => read(out, position, Cow::Owned(param), reading)?,
+        }
+        escaped = escapes;
+    }
+    reading.pop();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expand(params: &[&'static str]) -> Result<Vec<String>> {
+        let mut files = ResponseFiles::default();
+        let mut out = Vec::new();
+        for (position, param) in params.iter().enumerate() {
+            files.push(&mut out, position, Cow::Borrowed(*param))?;
+        }
+        Ok(out
+            .into_iter()
+            .map(|(_, param)| param.into_owned())
+            .collect())
+    }
+
+    #[test]
+    fn response_files() {
+        let dir = std::env::temp_dir().join(format!("serde_shon_files_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.join(name);
+            fs::write(&path, contents).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let inner = write("inner.txt", "# nested\n--b 'c d'\n");
+        let outer = write("outer.txt", &format!("--a 1 @{} -- @x\n", inner));
+        let looped = write("loop.txt", "");
+        write("loop.txt", &format!("--a @{}", looped));
+
+        let outer: &'static str = format!("@{}", outer).leak();
+        assert_eq!(
+            expand(&["[", outer, "--", "@kept", "]"]).unwrap(),
+            ["[", "--a", "1", "--b", "c d", "--", "@x", "--", "@kept", "]"]
+        );
+        assert_eq!(expand(&["@", "a@b"]).unwrap(), ["@", "a@b"]);
+
+        let looped: &'static str = format!("@{}", looped).leak();
+        let err = expand(&[looped]).unwrap_err();
+        assert!(err.to_string().ends_with("already being read"), "{}", err);
+        let err = expand(&["@/nonexistent/args.txt"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("can't read `@/nonexistent/args.txt`"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
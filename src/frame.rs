@@ -0,0 +1,158 @@
+// Length-prefixed frames of params, for processes exchanging SHON messages
+// over pipes or sockets. Nothing is quoted or escaped, as every param comes
+// with its length.
+//
+// A frame is the byte length of its body followed by the body, which is the
+// number of params followed by every param as its byte length and its UTF-8
+// bytes. All lengths and counts are big-endian `u32`s.
+
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Error, Result};
+
+/// The largest frame body `read_frame` accepts, so a broken or hostile peer
+/// can't make the reader allocate without bounds.
+pub const MAX_FRAME_LEN: usize = 64 << 20;
+
+/// Writes the params of the value as a single frame.
+///
+/// ```
+/// let mut pipe = Vec::new();
+/// serde_shon::write_frame(&mut pipe, &vec!["a b", "c\nd"]).unwrap();
+/// serde_shon::write_frame(&mut pipe, &1).unwrap();
+///
+/// let mut pipe = pipe.as_slice();
+/// let first: Vec<String> = serde_shon::read_frame(&mut pipe).unwrap().unwrap();
+/// assert_eq!(first, ["a b", "c\nd"]);
+/// assert_eq!(serde_shon::read_frame::<_, u32>(&mut pipe).unwrap(), Some(1));
+/// assert_eq!(serde_shon::read_frame::<_, u32>(&mut pipe).unwrap(), None);
+/// ```
+pub fn write_frame<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: Write,
+    T: Serialize,
+{
+    let params = crate::to_raw_params(value)?;
+    let mut body = Vec::new();
+    body.extend(len(params.len())?);
+    for param in &params {
+        body.extend(len(param.len())?);
+        body.extend(param.as_bytes());
+    }
+    if body.len() > MAX_FRAME_LEN {
+        return Err(Error::Message(format!(
+            "frame of {} bytes is larger than {} bytes",
+            body.len(),
+            MAX_FRAME_LEN
+        )));
+    }
+    let mut frame = len(body.len())?.to_vec();
+    frame.extend(body);
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the next frame and deserializes its params. Returns `None` when the
+/// reader ends right before a frame, and an error when it ends within one.
+pub fn read_frame<R, T>(mut reader: R) -> Result<Option<T>>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut header = [0; 4];
+    match reader.read(&mut header[..1])? {
+        0 => return Ok(None),
+        _ => reader.read_exact(&mut header[1..])?,
+    }
+    let body_len = u32::from_be_bytes(header) as usize;
+    if body_len > MAX_FRAME_LEN {
+        return Err(Error::Message(format!(
+            "frame of {} bytes is larger than {} bytes",
+            body_len, MAX_FRAME_LEN
+        )));
+    }
+    let mut body = vec![0; body_len];
+    reader.read_exact(&mut body)?;
+
+    let mut body = Body(&body);
+    let count = body.len()?;
+    let mut params = Vec::new();
+    for _ in 0..count {
+        let param_len = body.len()?;
+        let param = body.take(param_len)?;
+        let param = String::from_utf8(param.to_vec())
+            .map_err(|_| Error::Message("param in frame is not valid UTF-8".to_string()))?;
+        params.push(param);
+    }
+    if !body.0.is_empty() {
+        return Err(Error::Message("trailing bytes in frame".to_string()));
+    }
+    crate::de::from_params(params.into_iter()).map(Some)
+}
+
+fn len(len: usize) -> Result<[u8; 4]> {
+    let len = u32::try_from(len)
+        .map_err(|_| Error::Message(format!("length {} doesn't fit into a frame", len)))?;
+    Ok(len.to_be_bytes())
+}
+
+// The part of a frame body that is still to be read.
+struct Body<'a>(&'a [u8]);
+
+impl<'a> Body<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Message {
+        id: u32,
+        body: String,
+    }
+
+    #[test]
+    fn frames() {
+        let message = Message {
+            id: 7,
+            body: "-- ]\0\n'".to_string(),
+        };
+        let mut pipe = Vec::new();
+        write_frame(&mut pipe, &message).unwrap();
+        assert_eq!(&pipe[..8], [0, 0, 0, 54, 0, 0, 0, 7]);
+        write_frame(&mut pipe, &message).unwrap();
+
+        let mut reader = pipe.as_slice();
+        for _ in 0..2 {
+            let read: Message = read_frame(&mut reader).unwrap().unwrap();
+            assert_eq!(read, message);
+        }
+        assert!(read_frame::<_, Message>(&mut reader).unwrap().is_none());
+
+        assert!(read_frame::<_, Message>(&pipe[..10]).is_err());
+        let huge = (MAX_FRAME_LEN as u32 + 1).to_be_bytes();
+        assert!(read_frame::<_, Message>(&huge[..]).is_err());
+        let short = [0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 9];
+        assert!(read_frame::<_, Message>(&short[..]).is_err());
+    }
+}
@@ -1,7 +1,78 @@
+#[cfg(feature = "shell")]
+mod audit;
+mod bytes;
+#[cfg(feature = "clap")]
+mod clap;
+#[cfg(feature = "config")]
+mod config;
 mod de;
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
+mod dialect;
+mod dump;
+mod env;
 mod error;
+#[cfg(feature = "shell")]
+mod escape;
+mod extension;
+#[cfg(feature = "figment")]
+mod figment;
+mod files;
+mod frame;
+mod options;
+mod parse;
+pub mod prelude;
+#[cfg(feature = "shell")]
+mod pretty;
+#[doc(hidden)]
+pub mod private;
+mod process;
+#[cfg(feature = "shell")]
+mod record;
+mod redacted;
 mod ser;
+mod source_map;
+pub mod time;
+#[cfg(feature = "tracing")]
+mod tracing;
+pub mod type_config;
+mod validate;
+mod value;
+mod words;
 
+#[cfg(feature = "shell")]
+pub use audit::*;
+pub use bytes::BytesEncoding;
+#[cfg(feature = "clap")]
+pub use clap::*;
+#[cfg(feature = "config")]
+pub use config::*;
 pub use de::*;
+pub use dialect::*;
+pub use dump::*;
+pub use env::*;
 pub use error::*;
+#[cfg(feature = "shell")]
+pub use escape::*;
+pub use extension::Extension;
+#[cfg(feature = "figment")]
+pub use figment::*;
+pub use frame::*;
+pub use options::*;
+pub use parse::*;
+#[cfg(feature = "shell")]
+pub use pretty::*;
+pub use process::*;
+#[cfg(feature = "shell")]
+pub use record::*;
+pub use redacted::*;
 pub use ser::*;
+pub use source_map::SourceMap;
+#[cfg(feature = "tracing")]
+pub use tracing::*;
+pub use type_config::{KeyStyle, ShonConfig, TypeConfig};
+pub use validate::*;
+pub use value::*;
+
+#[cfg(feature = "derive")]
+pub use serde_shon_derive::shon;
@@ -0,0 +1,614 @@
+// Settings that change how params are deserialized. Everything is off by
+// default, so the plain `from_args` and `from_iter` only accept strict SHON.
+
+use crate::extension::{Extension, Extensions};
+use crate::{Dialect, KeyStyle};
+
+// How deeply arrays and objects may nest unless told otherwise, which keeps
+// the recursive deserializer well within the stack of a thread.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Settings for `from_args_with` and `from_iter_with`.
+///
+/// ```
+/// # use serde::Deserialize;
+/// use serde_shon::DeserializerOptions;
+///
+/// #[derive(Deserialize)]
+/// struct Args {
+///     color: bool,
+/// }
+///
+/// let options = DeserializerOptions::new().negation(true);
+/// let args = ["[", "--no-color", "]"].into_iter();
+/// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+/// assert!(!args.color);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeserializerOptions {
+    pub(crate) negation: bool,
+    pub(crate) positional: bool,
+    pub(crate) positional_fields: usize,
+    pub(crate) unbracketed: bool,
+    pub(crate) compact: bool,
+    pub(crate) nested: bool,
+    pub(crate) lossy: bool,
+    pub(crate) deny_unknown_keys: bool,
+    pub(crate) trailing_params: bool,
+    pub(crate) trailing_rest: bool,
+    pub(crate) keep_whitespace: bool,
+    pub(crate) numbers_as_strings: bool,
+    pub(crate) inline_values: bool,
+    pub(crate) flags: bool,
+    pub(crate) repeated_keys: bool,
+    pub(crate) counters: Vec<(String, String)>,
+    pub(crate) key_style: KeyStyle,
+    pub(crate) duplicate_keys: DuplicateKeys,
+    pub(crate) radix_prefixes: bool,
+    pub(crate) special_floats: bool,
+    pub(crate) bool_words: bool,
+    pub(crate) strict_dashes: bool,
+    pub(crate) stdin_marker: bool,
+    pub(crate) response_files: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_params: Option<usize>,
+    pub(crate) max_bytes: Option<usize>,
+    pub(crate) dialect: Dialect,
+    pub(crate) extensions: Extensions,
+}
+
+impl DeserializerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets `--no-<name>` stand for `--<name> -f` in structs, when the struct
+    /// has a field `<name>` but none called `no-<name>`. Together with bare
+    /// keys, which set `Option<bool>` fields to `Some(true)`, this gives
+    /// optional bools all three states without a value.
+    pub fn negation(mut self, enabled: bool) -> Self {
+        self.negation = enabled;
+        self
+    }
+
+    /// Fills the fields of a struct in declaration order when its brackets
+    /// don't start with a key, as in `[ host 8080 true ]`. Fields left out at
+    /// the end take their defaults. The types of the fields then decide how
+    /// words are read: a string field takes `8080` as it is, and a bool field
+    /// also takes `true` and `false`.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Target {
+    ///     host: String,
+    ///     port: u16,
+    ///     tls: bool,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().positional_structs(true);
+    /// let args = ["[", "8080", "8080", "true", "]"].into_iter();
+    /// let target: Target = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!((target.host.as_str(), target.port, target.tls), ("8080", 8080, true));
+    /// ```
+    pub fn positional_structs(mut self, enabled: bool) -> Self {
+        self.positional = enabled;
+        self
+    }
+
+    /// Fills the first `count` fields of the outermost struct from the
+    /// params before its first key, like the fields marked with
+    /// `#[shon(positional)]`. Together with `unbracketed`, this parses
+    /// conventional command lines like `copy SRC DST --force -t`.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Copy {
+    ///     src: String,
+    ///     dst: String,
+    ///     #[serde(default)]
+    ///     force: bool,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().unbracketed(true).positional_fields(2);
+    /// let args = ["copy", "a.txt", "b.txt", "--force", "-t"].map(String::from);
+    /// let copy: Copy = serde_shon::from_args_with(args.into_iter(), &options).unwrap();
+    /// assert_eq!((copy.src.as_str(), copy.dst.as_str(), copy.force), ("a.txt", "b.txt", true));
+    /// ```
+    pub fn positional_fields(mut self, count: usize) -> Self {
+        self.positional_fields = count;
+        self
+    }
+
+    /// Takes the params as the contents of the top-level value, without the
+    /// brackets around them, so `--name shon` reads like `[ --name shon ]`.
+    pub fn unbracketed(mut self, enabled: bool) -> Self {
+        self.unbracketed = enabled;
+        self
+    }
+
+    /// Reads a single param holding a whole document, like `[ --a 1 ]`, into
+    /// a struct, map or sequence, for params that went through something joining them
+    /// into a single string. The param is split like a POSIX shell would,
+    /// and may hold further nested documents in quotes.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().nested_documents(true);
+    /// let args = ["[", "--env", "[ --HOME '/home/a b' ]", "]"];
+    /// let env: HashMap<String, HashMap<String, String>> =
+    ///     serde_shon::from_iter_with(args.into_iter(), &options).unwrap();
+    /// assert_eq!(env["env"]["HOME"], "/home/a b");
+    /// ```
+    pub fn nested_documents(mut self, enabled: bool) -> Self {
+        self.nested = enabled;
+        self
+    }
+
+    /// Fails on keys that aren't a field of the struct they are in, instead
+    /// of skipping them with their value, like `#[serde(deny_unknown_fields)]`
+    /// does for a single struct. Maps still take any key, and so do structs
+    /// with a `#[serde(flatten)]` field catching the keys they don't know.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Args {
+    ///     #[serde(default)]
+    ///     color: bool,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().deny_unknown_keys(true);
+    /// let args = ["[", "--colour", "-t", "]"].into_iter();
+    /// let err = serde_shon::from_iter_with::<Args, _>(args, &options).unwrap_err();
+    /// assert_eq!(err.to_string(), "arg 1 `--colour`: unknown field `colour`, did you mean `color`?");
+    /// ```
+    pub fn deny_unknown_keys(mut self, enabled: bool) -> Self {
+        self.deny_unknown_keys = enabled;
+        self
+    }
+
+    /// Ignores params left over after the document, instead of failing on
+    /// them, for params that other tools append their own to.
+    pub fn trailing_params(mut self, enabled: bool) -> Self {
+        self.trailing_params = enabled;
+        self
+    }
+
+    /// Hands the params after a bracketed document to the `#[shon(rest)]`
+    /// field of the outermost struct, as they are, like
+    /// `DoubleDash::EndOfOptions` does for the params after `--`. Not
+    /// supported by `from_stream`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "derive")] {
+    /// use serde::Deserialize;
+    /// use serde_shon::{shon, DeserializerOptions};
+    ///
+    /// #[shon]
+    /// #[derive(Deserialize)]
+    /// struct Build {
+    ///     jobs: u32,
+    ///     #[shon(rest)]
+    ///     files: Vec<String>,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().trailing_rest(true);
+    /// let args = ["mytool", "[", "--jobs", "4", "]", "file1", "--file2"];
+    /// let build: Build = serde_shon::from_args_with(args.map(String::from).into_iter(), &options)
+    ///     .unwrap();
+    /// assert_eq!((build.jobs, build.files), (4, vec!["file1".into(), "--file2".into()]));
+    /// # }
+    /// ```
+    pub fn trailing_rest(mut self, enabled: bool) -> Self {
+        self.trailing_rest = enabled;
+        self
+    }
+
+    /// Takes params byte for byte, instead of trimming the whitespace around
+    /// them, so strings may start or end with spaces. Brackets, keys and
+    /// `--` then have to be given without any.
+    ///
+    /// ```
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().keep_whitespace(true);
+    /// let args = ["[", " indented", "", " ", "]"].into_iter();
+    /// let lines: Vec<String> = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!(lines, [" indented", " "]);
+    /// ```
+    pub fn keep_whitespace(mut self, enabled: bool) -> Self {
+        self.keep_whitespace = enabled;
+        self
+    }
+
+    /// Takes words for string values as they are, even if they look like
+    /// numbers, so `--zip 01234` fills a `String` field without `--` in
+    /// front. `positional_structs` does this as well.
+    pub fn numbers_as_strings(mut self, enabled: bool) -> Self {
+        self.numbers_as_strings = enabled;
+        self
+    }
+
+    /// Reads bool fields like the flags of most command line tools: a bare
+    /// key, followed by another key or the end of the struct, is `true`,
+    /// and a field that isn't given at all is `false`. This is like
+    /// `#[shon(flag)]` for the fields of all structs, and enables `negation`
+    /// as well, so `--no-<name>` is `false`. A bare key caught by a
+    /// `#[serde(flatten)]` map of `Value`s is `true` as well. With
+    /// `from_stream`, fields that aren't given still need
+    /// `#[serde(default)]`.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     verbose: bool,
+    ///     force: bool,
+    ///     name: String,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().flags(true);
+    /// let args = ["[", "--verbose", "--name", "shon", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert!(args.verbose && !args.force);
+    /// ```
+    pub fn flags(mut self, enabled: bool) -> Self {
+        self.flags = enabled;
+        self
+    }
+
+    /// Gathers the values of a key given more than once in a struct into an
+    /// array, for fields like `include: Vec<String>` that are given as
+    /// `--include a --include b`. Without this, repeating a key is an error.
+    /// Every value is one element, so `--include [ a b ]` is an array in
+    /// there, while a key given only once is an array of just its value,
+    /// unless that is an array already. Not supported by `from_stream`.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     include: Vec<String>,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().repeated_keys(true);
+    /// let args = ["[", "--include", "a", "--include", "b", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!(args.include, ["a", "b"]);
+    /// ```
+    pub fn repeated_keys(mut self, enabled: bool) -> Self {
+        self.repeated_keys = enabled;
+        self
+    }
+
+    /// Counts how often `token` is given in a struct with the field `field`,
+    /// which takes the count, like `-v -v -v` for `verbose: u8`. The key of
+    /// the field counts as well, and neither takes a value. Fields that
+    /// aren't given at all need `#[serde(default)]` to be `0`.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     #[serde(default)]
+    ///     verbose: u8,
+    ///     name: String,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().counter("-v", "verbose");
+    /// let args = ["[", "-v", "--name", "shon", "-v", "--verbose", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!(args.verbose, 3);
+    /// ```
+    pub fn counter<T, F>(mut self, token: T, field: F) -> Self
+    where
+        T: Into<String>,
+        F: Into<String>,
+    {
+        self.counters.push((token.into(), field.into()));
+        self
+    }
+
+    /// Takes `--key=value` like `--key value`, as many command line tools
+    /// do. Values that look like a key are strings then, as in
+    /// `--message=--help`. Keys containing `=` can't be given with a value
+    /// this way, as the param is split at the first one.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().inline_values(true);
+    /// let args = ["[", "--host=shon", "--user", "admin", "]"].into_iter();
+    /// let args: HashMap<String, String> = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!((args["host"].as_str(), args["user"].as_str()), ("shon", "admin"));
+    /// ```
+    pub fn inline_values(mut self, enabled: bool) -> Self {
+        self.inline_values = enabled;
+        self
+    }
+
+    /// Replaces invalid UTF-8 in the args given to `from_os_args_with` with
+    /// `U+FFFD`, instead of failing on them.
+    pub fn lossy_utf8(mut self, enabled: bool) -> Self {
+        self.lossy = enabled;
+        self
+    }
+
+    /// Reads the keys of struct fields in the given style as well, for
+    /// structs that don't pick their own with a `ShonConfig`. With
+    /// `KeyStyle::Kebab`, `--max-retries` fills the field `max_retries`
+    /// without `#[serde(rename_all = "kebab-case")]`. The keys of maps are
+    /// taken as they are.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::{DeserializerOptions, KeyStyle};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     max_retries: u32,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().key_style(KeyStyle::Kebab);
+    /// let args = ["[", "--max-retries", "3", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!(args.max_retries, 3);
+    /// ```
+    pub fn key_style(mut self, style: KeyStyle) -> Self {
+        self.key_style = style;
+        self
+    }
+
+    /// What to do with a key given more than once in the same struct or
+    /// map, see `DuplicateKeys`.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde_shon::{DeserializerOptions, DuplicateKeys};
+    ///
+    /// let args = ["[", "--a", "1", "--a", "2", "]"];
+    /// let parse = |policy| {
+    ///     let options = DeserializerOptions::new().duplicate_keys(policy);
+    ///     serde_shon::from_iter_with::<HashMap<String, u32>, _>(args.into_iter(), &options)
+    /// };
+    /// assert_eq!(parse(DuplicateKeys::First).unwrap()["a"], 1);
+    /// assert_eq!(parse(DuplicateKeys::Last).unwrap()["a"], 2);
+    /// assert!(parse(DuplicateKeys::Error).is_err());
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Reads integers written with a `0x`, `0o` or `0b` prefix, like
+    /// `0xff`, `0o755` and `-0b1010`, which are strings otherwise.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     mode: u32,
+    ///     mask: u8,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().radix_prefixes(true);
+    /// let args = ["[", "--mode", "0o755", "--mask", "0xF0", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!((args.mode, args.mask), (0o755, 0xf0));
+    /// ```
+    pub fn radix_prefixes(mut self, enabled: bool) -> Self {
+        self.radix_prefixes = enabled;
+        self
+    }
+
+    /// Reads `inf`, `-inf` and `nan`, in any case, as floats for `f32` and
+    /// `f64` values, which is how the serializer writes them. They are
+    /// strings otherwise, and to `Value`, which has no such numbers.
+    ///
+    /// ```
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().special_floats(true);
+    /// let args = ["[", "1e-9", "-inf", "NaN", "]"].into_iter();
+    /// let floats: Vec<f64> = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert_eq!(floats[..2], [1e-9, f64::NEG_INFINITY]);
+    /// assert!(floats[2].is_nan());
+    /// ```
+    pub fn special_floats(mut self, enabled: bool) -> Self {
+        self.special_floats = enabled;
+        self
+    }
+
+    /// Takes `true`, `false`, `yes` and `no`, in any case, for bool values,
+    /// besides `-t` and `-f`. Other values still read these words as
+    /// strings.
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Args {
+    ///     verbose: bool,
+    ///     force: bool,
+    /// }
+    ///
+    /// let options = DeserializerOptions::new().bool_words(true);
+    /// let args = ["[", "--verbose", "yes", "--force", "False", "]"].into_iter();
+    /// let args: Args = serde_shon::from_iter_with(args, &options).unwrap();
+    /// assert!(args.verbose && !args.force);
+    /// ```
+    pub fn bool_words(mut self, enabled: bool) -> Self {
+        self.bool_words = enabled;
+        self
+    }
+
+    /// Settles what a value starting with a dash is. Numbers like `-1` and
+    /// `-1.5` are always numbers, and never short keys, while `-t`, `-f`
+    /// and `-n` are always `true`, `false` and null. Any other word, like
+    /// `-x` or `-t3`, is a string by default, and an error with this
+    /// option, which catches flags given where a value was meant to go.
+    /// Either way, `-- -x` is the string `-x`, which is how the serializer
+    /// writes it, and a lone `-` is a string.
+    ///
+    /// ```
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().strict_dashes(true);
+    /// let parse = |args: &[&'static str]| {
+    ///     serde_shon::from_iter_with::<Vec<String>, _>(args.iter().copied(), &options)
+    /// };
+    /// assert_eq!(parse(&["[", "--", "-x", "-", "]"]).unwrap(), ["-x", "-"]);
+    /// assert!(parse(&["[", "-x", "]"]).is_err());
+    /// ```
+    pub fn strict_dashes(mut self, enabled: bool) -> Self {
+        self.strict_dashes = enabled;
+        self
+    }
+
+    /// Reads the params in place of a lone `-` from stdin, so a huge
+    /// document can be piped in, as in `echo '[ 1 2 ]' | tool [ --data - ]`.
+    /// Stdin is split into params like `from_reader` does, and with
+    /// `from_stream` it is read only as far as the deserializer gets. Only
+    /// the first `-` is read this way, and `-- -` is still the string `-`.
+    pub fn stdin_marker(mut self, enabled: bool) -> Self {
+        self.stdin_marker = enabled;
+        self
+    }
+
+    /// Reads the params in place of an `@path` param from the file at the
+    /// path, like the response files of compilers, which helps with command
+    /// lines too long for the shell. Files are split into params like
+    /// `from_reader` does, and may name further files, though not one that
+    /// is already being read. `-- @path` is still a string. As it reads any
+    /// file it is told to, leave this off for untrusted input.
+    ///
+    /// ```
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let path = std::env::temp_dir().join("serde_shon_response_file.txt");
+    /// std::fs::write(&path, "2 3").unwrap();
+    /// let file = format!("@{}", path.display());
+    ///
+    /// let options = DeserializerOptions::new().response_files(true);
+    /// let args = ["./binary", "[", "1", &file, "]"].map(String::from);
+    /// let numbers: Vec<u32> = serde_shon::from_args_with(args.into_iter(), &options).unwrap();
+    /// assert_eq!(numbers, [1, 2, 3]);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn response_files(mut self, enabled: bool) -> Self {
+        self.response_files = enabled;
+        self
+    }
+
+    /// How many arrays and objects may nest in each other, 128 by default.
+    /// Deeper input fails with `Error::DepthLimitExceeded` instead of
+    /// overflowing the stack, so only raise this for trusted input.
+    ///
+    /// ```
+    /// use serde_shon::{DeserializerOptions, Error, Value};
+    ///
+    /// let options = DeserializerOptions::new().max_depth(2);
+    /// let parse = |args: &[&'static str]| {
+    ///     serde_shon::from_iter_with::<Value, _>(args.iter().copied(), &options)
+    /// };
+    /// assert!(parse(&["[", "[", "1", "]", "]"]).is_ok());
+    /// let err = parse(&["[", "[", "[", "1", "]", "]", "]"]).unwrap_err();
+    /// assert!(matches!(err.inner(), Error::DepthLimitExceeded));
+    /// ```
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// How many params may be given at most, not counting the binary in
+    /// front for `from_args`. Params are counted as they are read, before
+    /// any are parsed, so a service taking untrusted params gives up on too
+    /// many of them early. Params from stdin and response files count too.
+    ///
+    /// ```
+    /// use serde_shon::DeserializerOptions;
+    ///
+    /// let options = DeserializerOptions::new().max_params(4);
+    /// let parse = |args: &[&'static str]| {
+    ///     serde_shon::from_iter_with::<Vec<u32>, _>(args.iter().copied(), &options)
+    /// };
+    /// assert!(parse(&["[", "1", "2", "]"]).is_ok());
+    /// let err = parse(&["[", "1", "2", "3", "]"]).unwrap_err();
+    /// assert_eq!(err.to_string(), "more than 4 params");
+    /// ```
+    pub fn max_params(mut self, max: usize) -> Self {
+        self.max_params = Some(max);
+        self
+    }
+
+    /// How many bytes the params may add up to, like `max_params`.
+    pub fn max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// Reads params written in the given dialect, see `Dialect`.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Whether types are told to expect their human readable forms, which is
+    /// the default. See `Serializer::human_readable`.
+    pub fn human_readable(mut self, enabled: bool) -> Self {
+        self.compact = !enabled;
+        self
+    }
+
+    /// Expands the tokens of the extension into the params they stand for,
+    /// before anything else looks at them. See `Extension`.
+    ///
+    /// # Panics
+    ///
+    /// If the prefix of the extension isn't a dash followed by a letter.
+    pub fn extension<E>(mut self, extension: E) -> Self
+    where
+        E: Extension + 'static,
+    {
+        self.extensions.push(extension);
+        self
+    }
+}
+
+/// What a key given more than once in the same struct or map stands for.
+/// Keys are compared as they are written, so a field given with two of its
+/// aliases isn't seen as a duplicate here. Not supported by `from_stream`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DuplicateKeys {
+    /// Left to the type, which is an error for structs and the last value
+    /// for maps like `HashMap`.
+    #[default]
+    Default,
+    /// Always an error.
+    Error,
+    /// The first value counts, the others are skipped.
+    First,
+    /// The last value counts, the others are skipped.
+    Last,
+}
@@ -0,0 +1,947 @@
+// The SHON grammar, kept apart from the serde glue in `de`:
+//
+//   document := value?
+//   value    := "-t" | "-f" | "-n" | "[]" | "[--]" | "--" ANY | KEY | WORD
+//             | "[" (KEY value)* "]"
+//             | "[" value* "]"
+//
+// An opening bracket directly followed by a key starts an object, anything
+// else starts an array. Words are numbers when they parse as one and strings
+// otherwise, while a key outside of an object is just a string.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
+
+use crate::error::TRAILING_SHOWN;
+use crate::{DuplicateKeys, Error, Result};
+
+/// The `true` value.
+pub const TRUE: &str = "-t";
+/// The `false` value.
+pub const FALSE: &str = "-f";
+/// The null value, standing in for `None` and unit.
+pub const NULL: &str = "-n";
+/// Opens an array or object.
+pub const OPEN: &str = "[";
+/// Closes an array or object.
+pub const CLOSE: &str = "]";
+/// An array without elements.
+pub const EMPTY_ARRAY: &str = "[]";
+/// An object without keys.
+pub const EMPTY_OBJECT: &str = "[--]";
+/// Makes the next param a string, even if it looks like a number, a key or
+/// one of the other reserved tokens.
+pub const STRING_ESCAPE: &str = "--";
+
+/// A single param, classified by the role it plays in the grammar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Token<'a> {
+    True,
+    False,
+    Null,
+    /// `--`, which makes the following param a string whatever it looks like.
+    Escape,
+    Open,
+    Close,
+    EmptyArray,
+    EmptyObject,
+    /// `--name`, holding the name without the dashes.
+    Key(&'a str),
+    /// A number or a string.
+    Word(&'a str),
+}
+
+/// Classifies a single param the way the deserializer sees it.
+///
+/// ```
+/// use serde_shon::{classify, Token};
+///
+/// assert_eq!(classify("--name"), Token::Key("name"));
+/// assert_eq!(classify("[--]"), Token::EmptyObject);
+/// assert_eq!(classify("-1"), Token::Word("-1"));
+/// ```
+pub fn classify(token: &str) -> Token<'_> {
+    match token {
+        TRUE => Token::True,
+        FALSE => Token::False,
+        NULL => Token::Null,
+        STRING_ESCAPE => Token::Escape,
+        OPEN => Token::Open,
+        CLOSE => Token::Close,
+        EMPTY_ARRAY => Token::EmptyArray,
+        EMPTY_OBJECT => Token::EmptyObject,
+        _ => match token.strip_prefix(STRING_ESCAPE) {
+            Some(name) => Token::Key(name),
+            None => Token::Word(token),
+        },
+    }
+}
+
+/// Whether the param is a `--key`.
+pub fn is_key(token: &str) -> bool {
+    matches!(classify(token), Token::Key(_))
+}
+
+/// What a word stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Scalar<'a> {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Str(&'a str),
+}
+
+/// An integer too wide for a `Scalar`, see `prefixed_wide`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Wide {
+    U128(u128),
+    I128(i128),
+}
+
+pub(crate) fn word(word: &str) -> Scalar<'_> {
+    if let Ok(uint) = word.parse::<u64>() {
+        Scalar::U64(uint)
+    } else if let Ok(int) = word.parse::<i64>() {
+        Scalar::I64(int)
+    } else if let (Ok(float), None) = (word.parse::<f64>(), special_float(word)) {
+        Scalar::F64(float)
+    } else {
+        Scalar::Str(word)
+    }
+}
+
+// `true`, `false`, `yes` and `no` in any case, see
+// `DeserializerOptions::bool_words`
+pub(crate) fn bool_word(word: &str) -> Option<bool> {
+    let is = |name: &str| word.eq_ignore_ascii_case(name);
+    if is("true") || is("yes") {
+        Some(true)
+    } else if is("false") || is("no") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// `inf`, `-inf` and `nan` in any case, which Rust reads as floats, but which
+// are only numbers with `DeserializerOptions::special_floats`
+pub(crate) fn special_float(word: &str) -> Option<f64> {
+    let (negative, name) = match word.strip_prefix('-') {
+        Some(name) => (true, name),
+        None => (false, word),
+    };
+    if name.eq_ignore_ascii_case("inf") || name.eq_ignore_ascii_case("infinity") {
+        Some(if negative {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        })
+    } else if name.eq_ignore_ascii_case("nan") && !negative {
+        Some(f64::NAN)
+    } else {
+        None
+    }
+}
+
+// Integers with a `0x`, `0o` or `0b` prefix, see
+// `DeserializerOptions::radix_prefixes`.
+pub(crate) fn prefixed(word: &str) -> Option<Scalar<'_>> {
+    let (negative, radix, digits) = radix_digits(word)?;
+    let uint = u64::from_str_radix(digits, radix).ok()?;
+    match negative {
+        false => Some(Scalar::U64(uint)),
+        true => 0i64.checked_sub_unsigned(uint).map(Scalar::I64),
+    }
+}
+
+// Like `prefixed`, for integers too wide for 64 bits.
+pub(crate) fn prefixed_wide(word: &str) -> Option<Wide> {
+    let (negative, radix, digits) = radix_digits(word)?;
+    let uint = u128::from_str_radix(digits, radix).ok()?;
+    match negative {
+        false => Some(Wide::U128(uint)),
+        true => 0i128.checked_sub_unsigned(uint).map(Wide::I128),
+    }
+}
+
+// whether the prefixed integer is negative, its radix and its digits
+fn radix_digits(word: &str) -> Option<(bool, u32, &str)> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word),
+    };
+    let radix = match digits.get(..2)? {
+        "0x" | "0X" => 16,
+        "0o" | "0O" => 8,
+        "0b" | "0B" => 2,
+        _ => return None,
+    };
+    // the sign only goes in front of the prefix
+    let digits = &digits[2..];
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    Some((negative, radix, digits))
+}
+
+// `-o` style keys, which only show up as aliases of struct fields
+pub(crate) fn is_short_key(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars.next() == Some('-')
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.next().is_none()
+        && !matches!(token, TRUE | FALSE | NULL)
+}
+
+/// Params with their position in the input, read one at a time. Reading
+/// stops at the first error.
+pub(crate) type Stream<'a> = Box<dyn Iterator<Item = Result<(usize, Cow<'a, str>)>> + 'a>;
+
+/// The params still to be parsed, kept in reverse so the next one can be
+/// taken off the end. They are borrowed from the input where possible.
+pub(crate) struct Tokens<'a> {
+    args: Vec<Cow<'a, str>>,
+    // where every param was in the input, for pointing back at it
+    positions: Vec<usize>,
+    // the params dropped by `gather` and the like, which are skipped once
+    // they are next
+    removed: Vec<bool>,
+    // the params not read yet when they are streamed in, in which case
+    // `args` only holds the next one
+    stream: Option<Stream<'a>>,
+    // the error that ended the stream
+    error: Option<Error>,
+    // the position right after the last param taken
+    consumed: usize,
+    // the position of the last param taken, if any
+    taken: Option<usize>,
+    // the number of params taken
+    count: usize,
+    // the last param read from the stream was `--`, see `keep`
+    escaped: bool,
+    // whether the whitespace around params is trimmed
+    trim: bool,
+    // the positions of the `[` that are never closed, as they were closed
+    // by another kind of closing bracket, with that bracket
+    mismatched: Vec<(usize, String)>,
+}
+
+impl<'a> Tokens<'a> {
+    /// The params with their position in the input, with the whitespace
+    /// around them trimmed unless told otherwise.
+    pub(crate) fn positioned<I>(iter: I, trimmed: bool) -> Self
+    where
+        I: Iterator<Item = (usize, Cow<'a, str>)>,
+    {
+        let mut escaped = false;
+        let (mut positions, mut args): (Vec<usize>, Vec<Cow<'a, str>>) = iter
+            .map(|(position, param)| (position, trim(param, trimmed))) // trim whitespace
+            .filter(|(_, p)| keep(&mut escaped, p)) // remove empty elements, unless escaped
+            .unzip();
+        let mismatched = mismatched(&positions, &args);
+        args.reverse();
+        positions.reverse();
+        let consumed = positions.last().copied().unwrap_or_default();
+        Tokens {
+            removed: vec![false; args.len()],
+            args,
+            positions,
+            stream: None,
+            error: None,
+            consumed,
+            taken: None,
+            count: 0,
+            escaped: false,
+            trim: trimmed,
+            mismatched,
+        }
+    }
+
+    /// The params of the stream, which are read as they are needed.
+    pub(crate) fn streamed(stream: Stream<'a>, trimmed: bool) -> Self {
+        let mut tokens = Tokens {
+            stream: Some(stream),
+            ..Tokens::positioned(std::iter::empty(), trimmed)
+        };
+        tokens.refill();
+        tokens.consumed = tokens.position();
+        tokens
+    }
+
+    // reads the next param from the stream once the last one was taken,
+    // skipping empty ones like `positioned`
+    fn refill(&mut self) {
+        if !self.args.is_empty() {
+            return;
+        }
+        while let Some(next) = self.stream.as_mut().and_then(Iterator::next) {
+            match next.map(|(position, param)| (position, trim(param, self.trim))) {
+                Ok((_, param)) if !keep(&mut self.escaped, &param) => {}
+                Ok((position, param)) => {
+                    self.args.push(param);
+                    self.positions.push(position);
+                    self.removed.push(false);
+                    return;
+                }
+                Err(err) => {
+                    self.error = Some(err);
+                    break;
+                }
+            }
+        }
+        self.stream = None;
+    }
+
+    /// The error the stream ended with, if it did.
+    pub(crate) fn take_error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+
+    /// The position of the next param in the input, or right after the
+    /// last one taken at the end.
+    pub(crate) fn position(&self) -> usize {
+        self.positions.last().copied().unwrap_or(self.consumed)
+    }
+
+    /// The position right after the last param taken.
+    pub(crate) fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// The position of the last param taken.
+    pub(crate) fn taken(&self) -> Option<usize> {
+        self.taken
+    }
+
+    /// The bracket the `[` at the position is closed by instead of `]`, if
+    /// it is never closed and there is another kind of closing bracket where
+    /// its `]` belongs. Streamed params are never read that far ahead.
+    pub(crate) fn mismatched(&self, position: usize) -> Option<&str> {
+        let found = self.mismatched.binary_search_by_key(&position, |(p, _)| *p);
+        found.ok().map(|i| self.mismatched[i].1.as_str())
+    }
+
+    /// The number of params taken so far.
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    pub(crate) fn peek(&self) -> Option<Token<'_>> {
+        self.args.last().map(|arg| classify(arg))
+    }
+
+    /// The next param as it is.
+    pub(crate) fn peek_param(&self) -> Option<&str> {
+        self.args.last().map(|arg| arg.as_ref())
+    }
+
+    /// A copy of the array or object in front, brackets included, if all of
+    /// its params were read in already.
+    pub(crate) fn peek_brackets(&self) -> Option<Vec<(usize, String)>> {
+        if self.stream.is_some() || self.peek() != Some(Token::Open) {
+            return None;
+        }
+        let mut depth = 0usize;
+        let mut escaped = false;
+        let len = 1 + self.args.iter().rev().position(|param| {
+            match classify(param) {
+                _ if escaped => escaped = false,
+                Token::Escape => escaped = true,
+                Token::Open => depth += 1,
+                Token::Close => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })?;
+        let start = self.args.len() - len;
+        let positions = self.positions[start..].iter().rev();
+        let params = self.args[start..].iter().rev();
+        let removed = self.removed[start..].iter().rev();
+        Some(
+            positions
+                .zip(params)
+                .zip(removed)
+                .filter(|(_, removed)| !**removed)
+                .map(|((p, a), _)| (*p, a.to_string()))
+                .collect(),
+        )
+    }
+
+    /// The error for the params left over after the document, which takes
+    /// the ones it shows and points at the first. Streamed params are only
+    /// read as far as they are shown.
+    pub(crate) fn trailing(&mut self) -> Error {
+        let remaining = self.stream.is_none();
+        let remaining = remaining.then(|| self.removed.iter().filter(|r| !**r).count());
+        let start = self.positions.last().copied();
+        let first = iter::from_fn(|| self.next())
+            .take(TRAILING_SHOWN)
+            .map(Cow::into_owned)
+            .collect();
+        if let Some(start) = start {
+            self.point_at(start);
+        }
+        Error::TrailingTokens { remaining, first }
+    }
+
+    /// Points errors at the param at the position instead of the one taken
+    /// last.
+    pub(crate) fn point_at(&mut self, position: usize) {
+        self.taken = Some(position);
+    }
+
+    /// Turns the value in front and the values of the later params `key`
+    /// within the same array or object into an array of all of them, in
+    /// place of the first value, if there are any. The later keys are
+    /// dropped with their values. When none of them have a value, the key
+    /// is left as a flag given once, and when only some do, the first one
+    /// without is missing its value.
+    pub(crate) fn gather(&mut self, key: &str, later: &mut LaterKeys) -> Result<()> {
+        let end = self.args.len();
+        let len = self.value_len(end);
+        let found = self.later(key, end - len, later);
+        if found.is_empty() {
+            return Ok(());
+        }
+        match self.bare(len, &found)? {
+            Bare::Flag => return self.drop_keys(&found),
+            Bare::Missing(position) => {
+                if let Some(position) = position {
+                    self.point_at(position);
+                }
+                return Err(missing_value());
+            }
+            Bare::Values => {}
+        }
+        // the array takes the positions of the values around it
+        let first = self.position();
+        let mut last = match len {
+            0 => first,
+            _ => self.position_at(end - len)?,
+        };
+        let mut values = Vec::new();
+        for (at, len) in found {
+            values.extend(self.take(at - len..at + 1)?.into_iter().skip(1));
+        }
+        if let Some((position, _)) = values.last() {
+            last = *position;
+        }
+        let inserted = iter::once((last, Cow::Borrowed(CLOSE))).chain(values.into_iter().rev());
+        self.splice(end - len..end - len, inserted)?;
+        let top = self.args.len();
+        self.splice(top..top, [(first, Cow::Borrowed(OPEN))])?;
+        self.skip_removed();
+        Ok(())
+    }
+
+    /// Removes the later params that are one of `keys` within the same array
+    /// or object, which don't take a value, returning how many there were.
+    pub(crate) fn count_later(&mut self, keys: &[&str], later: &mut LaterKeys) -> Result<usize> {
+        let end = self.args.len();
+        let mut found = 0;
+        for key in keys {
+            for (at, _) in self.later(key, end, later) {
+                self.take(at..at + 1)?;
+                found += 1;
+            }
+        }
+        self.skip_removed();
+        Ok(found)
+    }
+
+    /// Applies the policy to the later params like the key in front, within
+    /// the same array or object, keeping the value of only one of them. A
+    /// value missing from any of them is an error, like for `gather`.
+    pub(crate) fn resolve_duplicates(
+        &mut self,
+        policy: DuplicateKeys,
+        later: &mut LaterKeys,
+    ) -> Result<()> {
+        let Some(key) = self.peek_param().map(str::to_string) else {
+            return Ok(());
+        };
+        let end = self.args.len() - 1;
+        let len = self.value_len(end);
+        let found = self.later(&key, end - len, later);
+        if found.is_empty() {
+            return Ok(());
+        }
+        if let DuplicateKeys::Error = policy {
+            self.next();
+            return Err(Error::Message(format!("duplicate key `{}`", key)));
+        }
+        // whichever of them is kept
+        match self.bare(len, &found)? {
+            Bare::Flag => return self.drop_keys(&found),
+            Bare::Missing(position) => {
+                self.next();
+                if let Some(position) = position {
+                    self.point_at(position);
+                }
+                return Err(missing_value());
+            }
+            Bare::Values => {}
+        }
+        let mut last = Vec::new();
+        for (at, len) in found {
+            last = self.take(at - len..at + 1)?;
+        }
+        if let DuplicateKeys::Last = policy {
+            self.splice(end - len..end, last.into_iter().skip(1).rev())?;
+        }
+        self.skip_removed();
+        Ok(())
+    }
+
+    // Whether the key with a value of `len` params and its later params
+    // all have a value, none do, or only some
+    fn bare(&self, len: usize, found: &[(usize, usize)]) -> Result<Bare> {
+        let bare = found.iter().find(|(_, len)| *len == 0);
+        Ok(match (len, bare) {
+            (0, _) if found.iter().all(|(_, len)| *len == 0) => Bare::Flag,
+            (0, _) => Bare::Missing(None),
+            (_, Some((at, _))) => Bare::Missing(Some(self.position_at(*at)?)),
+            (_, None) => Bare::Values,
+        })
+    }
+
+    // drops the later keys that were found, without their values
+    fn drop_keys(&mut self, found: &[(usize, usize)]) -> Result<()> {
+        for (at, _) in found {
+            self.take(*at..at + 1)?;
+        }
+        self.skip_removed();
+        Ok(())
+    }
+
+    // the later params `key` of the array or object being read, below `end`
+    // on the stack, in the order they are read, with the number of params
+    // of their values
+    fn later(&self, key: &str, end: usize, later: &mut LaterKeys) -> Vec<(usize, usize)> {
+        // streamed params come in at the bottom of the stack, which is only
+        // ever the next one
+        if self.stream.is_some() {
+            later.0 = None;
+        }
+        let keys = later.0.get_or_insert_with(|| self.index_keys());
+        let Some(found) = keys.get_mut(key) else {
+            return Vec::new();
+        };
+        // the ones at `end` and above were read already, or dropped
+        found.retain(|&at| at < end.min(self.args.len()));
+        found
+            .iter()
+            .copied()
+            .filter(|&at| self.removed.get(at) == Some(&false))
+            .filter(|&at| self.args.get(at).is_some_and(|param| param == key))
+            .map(|at| (at, self.value_len(at)))
+            .collect()
+    }
+
+    // the params directly within the array or object being read, by where
+    // they are on the stack, see `LaterKeys`
+    fn index_keys(&self) -> HashMap<String, Vec<usize>> {
+        let mut keys: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut depth = 0usize;
+        let mut escaped = false;
+        let params = self.args.iter().zip(&self.removed).enumerate();
+        for (at, (param, removed)) in params.rev() {
+            match classify(param) {
+                _ if *removed => {}
+                _ if escaped => escaped = false,
+                Token::Escape => escaped = true,
+                Token::Open => depth += 1,
+                Token::Close if depth == 0 => break,
+                Token::Close => depth -= 1,
+                _ if depth == 0 => keys.entry(param.to_string()).or_default().push(at),
+                _ => {}
+            }
+        }
+        keys
+    }
+
+    // The number of params of the value right below `end` on the stack,
+    // which is none for a bare key.
+    fn value_len(&self, end: usize) -> usize {
+        let end = end.min(self.args.len());
+        let mut depth = 0usize;
+        let mut i = end;
+        while let Some(param) = i.checked_sub(1).and_then(|i| self.args.get(i)) {
+            let step = match classify(param) {
+                Token::Close | Token::Key(_) if depth == 0 => break,
+                Token::Open => {
+                    depth += 1;
+                    1
+                }
+                Token::Close => {
+                    depth -= 1;
+                    1
+                }
+                Token::Escape => 2,
+                _ => 1,
+            };
+            i = i.saturating_sub(step);
+            if depth == 0 {
+                break;
+            }
+        }
+        end - i
+    }
+
+    fn position_at(&self, at: usize) -> Result<usize> {
+        self.positions.get(at).copied().ok_or_else(out_of_place)
+    }
+
+    // drops the params in the range of the stack, which are skipped once
+    // they are next, giving them in the order they are read. They stay on
+    // the stack until then, so the positions on it stay the same.
+    fn take(&mut self, range: Range<usize>) -> Result<Vec<(usize, Cow<'a, str>)>> {
+        let mut taken = Vec::with_capacity(range.len());
+        for at in range.rev() {
+            let (Some(removed), Some(position), Some(param)) = (
+                self.removed.get_mut(at),
+                self.positions.get(at),
+                self.args.get_mut(at),
+            ) else {
+                return Err(out_of_place());
+            };
+            *removed = true;
+            taken.push((*position, std::mem::take(param)));
+        }
+        Ok(taken)
+    }
+
+    // takes the dropped params off the top, reading in the next one if it
+    // is streamed
+    fn skip_removed(&mut self) {
+        while self.removed.last() == Some(&true) {
+            self.removed.pop();
+            self.positions.pop();
+            self.args.pop();
+        }
+        self.refill();
+    }
+
+    // puts the params, in the order they are on the stack, in place of the
+    // range of it
+    fn splice<I>(&mut self, range: Range<usize>, params: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (usize, Cow<'a, str>)>,
+    {
+        if range.start > range.end || range.end > self.args.len() {
+            return Err(out_of_place());
+        }
+        let (positions, args): (Vec<_>, Vec<_>) = params.into_iter().unzip();
+        let kept = iter::repeat_n(false, args.len());
+        self.removed.splice(range.clone(), kept);
+        self.positions.splice(range.clone(), positions);
+        self.args.splice(range, args);
+        Ok(())
+    }
+
+    pub(crate) fn next(&mut self) -> Option<Cow<'a, str>> {
+        let position = self.positions.pop()?;
+        self.consumed = position + 1;
+        self.taken = Some(position);
+        self.count += 1;
+        let param = self.args.pop();
+        self.removed.pop();
+        self.skip_removed();
+        param
+    }
+
+    /// Takes the next param, which has to exist.
+    pub(crate) fn expect_next(&mut self) -> Result<Cow<'a, str>> {
+        self.next().ok_or_else(end_of_input)
+    }
+
+    /// Whether the array or object being parsed is closed next.
+    pub(crate) fn at_close(&self) -> Result<bool> {
+        match self.peek() {
+            Some(token) => Ok(token == Token::Close),
+            None => Err(end_of_input()),
+        }
+    }
+
+    /// Consumes the end of an array or object, which has to be next.
+    pub(crate) fn close(&mut self) -> Result<()> {
+        let param = self.expect_next()?;
+        match classify(&param) {
+            Token::Close => Ok(()),
+            _ => Err(Error::Message(format!("expected `]`, found `{}`", param))),
+        }
+    }
+}
+
+// the `[` closed by something like `}` or `)` instead, when that leaves no
+// `[` open, see `Tokens::mismatched`
+fn mismatched(positions: &[usize], args: &[Cow<'_, str>]) -> Vec<(usize, String)> {
+    let mut mismatched = Vec::new();
+    // only brackets that are never closed are looked at again
+    for lenient in [false, true] {
+        let mut open = Vec::new();
+        let mut escaped = false;
+        for (position, param) in positions.iter().zip(args) {
+            match classify(param) {
+                _ if escaped => escaped = false,
+                Token::Escape => escaped = true,
+                Token::Open => open.push(*position),
+                Token::Close => {
+                    open.pop();
+                }
+                Token::Word(word @ ("}" | ")")) if lenient => {
+                    if let Some(position) = open.pop() {
+                        mismatched.push((position, word.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if open.is_empty() {
+            break;
+        }
+    }
+    mismatched.sort_unstable();
+    mismatched
+}
+
+/// Where the params directly within an array or object are, so finding the
+/// later keys like the one read doesn't take going through the rest of it
+/// every time, see `Tokens::gather`. It is filled in when first needed, and
+/// points at the params by how far they are from the bottom of the stack,
+/// which stays the same while the array or object is read: params are only
+/// ever taken off the top or put in front of the ones it points at, and the
+/// ones dropped stay where they are until they reach the top. What is at the
+/// top or above it was read already and is left out.
+#[derive(Default)]
+pub(crate) struct LaterKeys(Option<HashMap<String, Vec<usize>>>);
+
+/// Splits `--key=value` params into the key and its value, see
+/// `DeserializerOptions::inline_values`. Values that would read as a key
+/// are escaped, and escaped params are left alone.
+#[derive(Default)]
+pub(crate) struct InlineValues {
+    // the last param was an escape
+    escaped: bool,
+}
+
+impl InlineValues {
+    pub(crate) fn split<'a>(
+        mut self,
+        params: Vec<(usize, Cow<'a, str>)>,
+    ) -> Vec<(usize, Cow<'a, str>)> {
+        let mut out = Vec::with_capacity(params.len());
+        for (position, param) in params {
+            self.push(&mut out, position, param);
+        }
+        out
+    }
+
+    /// Splits the next param, adding the params it turns into to `out`. Both
+    /// take the position of the param.
+    pub(crate) fn push<'a>(
+        &mut self,
+        out: &mut Vec<(usize, Cow<'a, str>)>,
+        position: usize,
+        param: Cow<'a, str>,
+    ) {
+        let token = param.trim();
+        let at = match classify(token) {
+            Token::Key(key) if !self.escaped => key
+                .split_once('=')
+                .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+                .map(|(key, _)| STRING_ESCAPE.len() + key.len()),
+            _ => None,
+        };
+        self.escaped = !self.escaped && token == STRING_ESCAPE;
+        let Some(at) = at else {
+            out.push((position, param));
+            return;
+        };
+        let (key, value) = match param {
+            Cow::Borrowed(param) => {
+                let param = param.trim();
+                (Cow::Borrowed(&param[..at]), Cow::Borrowed(&param[at + 1..]))
+            }
+            Cow::Owned(param) => {
+                let param = param.trim();
+                (
+                    Cow::Owned(param[..at].to_string()),
+                    Cow::Owned(param[at + 1..].to_string()),
+                )
+            }
+        };
+        out.push((position, key));
+        if let Token::Key(_) | Token::Escape = classify(value.trim()) {
+            out.push((position, Cow::Borrowed(STRING_ESCAPE)));
+        }
+        out.push((position, value));
+    }
+}
+
+pub(crate) fn end_of_input() -> Error {
+    Error::Eof
+}
+
+// A key given more than once, as a flag every time, with a value every
+// time, or missing its value where the position points, if not in front
+enum Bare {
+    Flag,
+    Values,
+    Missing(Option<usize>),
+}
+
+fn missing_value() -> Error {
+    Error::Message("missing value".to_string())
+}
+
+// what moving params around ends with if they aren't where they were found,
+// rather than reaching past the ones there are
+fn out_of_place() -> Error {
+    Error::Message("params moved out of place".to_string())
+}
+
+// Whether to keep the param, which it is unless it is empty, as
+// empty params are usually just left over from splitting. After `--`, an
+// empty param is an empty string.
+pub(crate) fn keep(escaped: &mut bool, param: &str) -> bool {
+    let keep = *escaped || !param.is_empty();
+    *escaped = !*escaped && param == STRING_ESCAPE;
+    keep
+}
+
+// trims the arg within its own buffer instead of copying it, if asked to
+fn trim(arg: Cow<'_, str>, trimmed: bool) -> Cow<'_, str> {
+    match arg {
+        _ if !trimmed => arg,
+        Cow::Borrowed(arg) => Cow::Borrowed(arg.trim()),
+        Cow::Owned(mut arg) => {
+            arg.truncate(arg.trim_end().len());
+            let start = arg.len() - arg.trim_start().len();
+            arg.drain(..start);
+            Cow::Owned(arg)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify_tokens() {
+        assert_eq!(classify("-t"), Token::True);
+        assert_eq!(classify("[--]"), Token::EmptyObject);
+        assert_eq!(classify("--"), Token::Escape);
+        assert_eq!(classify("--name"), Token::Key("name"));
+        assert_eq!(classify("-1"), Token::Word("-1"));
+        assert_eq!(word("-1"), Scalar::I64(-1));
+        assert_eq!(word("0.5"), Scalar::F64(0.5));
+        assert_eq!(word("x"), Scalar::Str("x"));
+        assert_eq!(word("0xff"), Scalar::Str("0xff"));
+        assert_eq!(word("1e-9"), Scalar::F64(1e-9));
+        assert_eq!(bool_word("Yes"), Some(true));
+        assert_eq!(bool_word("FALSE"), Some(false));
+        assert_eq!(bool_word("y"), None);
+        assert_eq!(word("inf"), Scalar::Str("inf"));
+        assert_eq!(word("NaN"), Scalar::Str("NaN"));
+        assert_eq!(special_float("-Infinity"), Some(f64::NEG_INFINITY));
+        assert!(special_float("nan").unwrap().is_nan());
+        assert_eq!(special_float("-nan"), None);
+        assert_eq!(special_float("1e999"), None);
+        assert_eq!(prefixed("0xff"), Some(Scalar::U64(255)));
+        assert_eq!(prefixed("0O17"), Some(Scalar::U64(15)));
+        assert_eq!(prefixed("-0b101"), Some(Scalar::I64(-5)));
+        assert_eq!(prefixed("-0x8000000000000000"), Some(Scalar::I64(i64::MIN)));
+        assert_eq!(prefixed("-0x8000000000000001"), None);
+        assert_eq!(prefixed("0x+1"), None);
+        assert_eq!(prefixed("0x"), None);
+        assert_eq!(prefixed("0b2"), None);
+        assert_eq!(prefixed("x0ff"), None);
+        assert!(is_key("--a"));
+        assert!(!is_key("--"));
+        assert!(is_short_key("-o"));
+        assert!(!is_short_key("-t"));
+        assert!(!is_short_key("-1"));
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(trim(Cow::Owned(" \t-- \n".to_string()), true), "--");
+        assert_eq!(trim(Cow::Borrowed(" a b "), true), "a b");
+        assert_eq!(trim(Cow::Borrowed(" a b "), false), " a b ");
+        let mut tokens = Tokens::positioned(
+            [" [ ", "a ", "  ", " b"]
+                .map(Cow::Borrowed)
+                .into_iter()
+                .enumerate(),
+            true,
+        );
+        assert_eq!(tokens.peek(), Some(Token::Open));
+        assert_eq!(tokens.expect_next().unwrap(), "[");
+        assert!(!tokens.at_close().unwrap());
+        assert_eq!(tokens.position(), 1);
+        let err = tokens.close().unwrap_err();
+        assert_eq!(err.to_string(), "expected `]`, found `a`");
+        assert_eq!(tokens.position(), 3);
+        tokens.next();
+        assert_eq!(tokens.consumed(), 4);
+        assert!(tokens.is_empty());
+        assert!(tokens.at_close().is_err());
+        assert!(tokens.close().is_err());
+    }
+
+    #[test]
+    fn peek_brackets() {
+        let params = ["[", "--a", "--", "]", "--b", "[", "1", "]", "]", "x"];
+        let tokens = Tokens::positioned(params.map(Cow::Borrowed).into_iter().enumerate(), true);
+        let object = tokens.peek_brackets().unwrap();
+        assert_eq!(object.len(), 9);
+        assert_eq!(object[8], (8, "]".to_string()));
+        let tokens = Tokens::positioned(
+            params[1..].iter().copied().map(Cow::Borrowed).enumerate(),
+            true,
+        );
+        assert_eq!(tokens.peek_brackets(), None);
+        let tokens = Tokens::positioned(
+            params[..4].iter().copied().map(Cow::Borrowed).enumerate(),
+            true,
+        );
+        assert_eq!(tokens.peek_brackets(), None);
+    }
+
+    #[test]
+    fn inline_values() {
+        let params = [
+            " --a=1 ", "--b=--c", "--", "--d=e", "--f=", "--=g", "--h==i",
+        ];
+        let params = params.map(Cow::Borrowed).into_iter().enumerate().collect();
+        let split: Vec<_> = InlineValues::default().split(params);
+        assert_eq!(
+            split,
+            [
+                (0, "--a".into()),
+                (0, "1".into()),
+                (1, "--b".into()),
+                (1, "--".into()),
+                (1, "--c".into()),
+                (2, "--".into()),
+                (3, "--d=e".into()),
+                (4, "--f=".into()),
+                (5, "--=g".into()),
+                (6, "--h".into()),
+                (6, "=i".into()),
+            ]
+        );
+        assert!(split
+            .iter()
+            .all(|(_, param)| matches!(param, Cow::Borrowed(_))));
+    }
+}
@@ -0,0 +1,23 @@
+//! The commonly used parts of the crate, to be glob imported with
+//! `use serde_shon::prelude::*;`.
+//!
+//! ```
+//! # #[cfg(feature = "shell")] {
+//! use serde_shon::prelude::*;
+//!
+//! let value: Value = from_iter(["[", "--name", "shon", "]"].into_iter())?;
+//! assert_eq!(to_string(&value)?, "[ --name shon ]");
+//! # }
+//! # Ok::<(), serde_shon::Error>(())
+//! ```
+
+pub use crate::{
+    from_args, from_args_with, from_iter, from_iter_with, from_value, to_raw_params, to_value,
+    DeserializerOptions, Error, Map, Number, Redacted, Validate, Value,
+};
+
+#[cfg(feature = "shell")]
+pub use crate::{to_params, to_string, to_string_for_shell, to_string_pretty, Shell};
+
+#[cfg(feature = "derive")]
+pub use crate::shon;
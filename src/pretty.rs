@@ -0,0 +1,216 @@
+// Multi-line formatting of serialized SHON, joined by shell line
+// continuations so the output can still be pasted into a shell as-is.
+
+use std::iter::Peekable;
+use std::slice::Iter;
+
+use serde::Serialize;
+
+use crate::parse::{classify, Token, CLOSE, OPEN};
+use crate::ser::to_redacted_params;
+use crate::Shell;
+
+const DEFAULT_WIDTH: usize = 80;
+const INDENT: usize = 2;
+const CONTINUATION: &str = " \\";
+
+/// Like `to_string`, but spreads the output over multiple lines that are at
+/// most 80 characters wide where possible.
+pub fn to_string_pretty<T>(value: &T) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    to_string_pretty_with_width(value, DEFAULT_WIDTH)
+}
+
+/// Like `to_string_pretty`, but with a custom target line width. Objects are
+/// broken up into one key per line once they don't fit, while sequences are
+/// filled up to the width before wrapping onto an indented line.
+pub fn to_string_pretty_with_width<T>(value: &T, width: usize) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    let params = to_redacted_params(value)?;
+    let mut tokens = params.iter().peekable();
+    let mut printer = Printer {
+        width,
+        lines: Vec::new(),
+        line: String::new(),
+        indent: 0,
+    };
+    while let Some(node) = Node::parse(&mut tokens) {
+        printer.node(&node);
+    }
+    Ok(printer.finish())
+}
+
+enum Node {
+    Atom(String),
+    // whether the group is an object, and its children
+    Group(bool, Vec<Node>),
+}
+
+impl Node {
+    fn parse(tokens: &mut Peekable<Iter<'_, String>>) -> Option<Node> {
+        let token = tokens.next()?;
+        Some(match classify(token) {
+            Token::Open => {
+                let object = tokens
+                    .peek()
+                    .is_some_and(|next| matches!(classify(next), Token::Key(_)));
+                let mut children = Vec::new();
+                while tokens
+                    .peek()
+                    .is_some_and(|next| classify(next) != Token::Close)
+                {
+                    children.extend(Node::parse(tokens));
+                }
+                tokens.next(); // the closing bracket
+                Node::Group(object, children)
+            }
+            // keep the string sigil together with the string it escapes
+            Token::Escape => match tokens.next() {
+                Some(next) => Node::Atom(format!("-- {}", Shell::Posix.escape(next))),
+                None => Node::Atom(token.clone()),
+            },
+            _ => Node::Atom(Shell::Posix.escape(token).into_owned()),
+        })
+    }
+
+    fn flat(&self) -> String {
+        match self {
+            Node::Atom(atom) => atom.clone(),
+            Node::Group(_, children) => {
+                let mut out = OPEN.to_string();
+                for child in children {
+                    out.push(' ');
+                    out.push_str(&child.flat());
+                }
+                out.push_str(" ]");
+                out
+            }
+        }
+    }
+}
+
+struct Printer {
+    width: usize,
+    lines: Vec<String>,
+    line: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn at_line_start(&self) -> bool {
+        self.line.len() == self.indent
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        let sep = if self.at_line_start() { 0 } else { 1 };
+        self.line.len() + sep + text.len() + CONTINUATION.len() <= self.width
+    }
+
+    fn newline(&mut self) {
+        if !self.line.trim().is_empty() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+        self.line = " ".repeat(self.indent);
+    }
+
+    fn emit(&mut self, text: &str) {
+        if !self.at_line_start() {
+            if !self.fits(text) {
+                self.newline();
+            } else {
+                self.line.push(' ');
+            }
+        }
+        self.line.push_str(text);
+    }
+
+    fn node(&mut self, node: &Node) {
+        let children = match node {
+            Node::Atom(atom) => return self.emit(atom),
+            Node::Group(object, children) => {
+                let flat = node.flat();
+                if self.fits(&flat) {
+                    return self.emit(&flat);
+                }
+                if !self.at_line_start()
+                    && self.indent + flat.len() + CONTINUATION.len() <= self.width
+                {
+                    self.newline();
+                    return self.emit(&flat);
+                }
+                if *object {
+                    return self.object(children);
+                }
+                children
+            }
+        };
+        self.emit(OPEN);
+        self.indent += INDENT;
+        self.newline();
+        for child in children {
+            self.node(child);
+        }
+        self.indent -= INDENT;
+        self.newline();
+        self.emit(CLOSE);
+    }
+
+    fn object(&mut self, children: &[Node]) {
+        self.emit(OPEN);
+        self.indent += INDENT;
+        let mut children = children.iter();
+        while let Some(key) = children.next() {
+            self.newline();
+            self.node(key);
+            if let Some(value) = children.next() {
+                self.node(value);
+            }
+        }
+        self.indent -= INDENT;
+        self.newline();
+        self.emit(CLOSE);
+    }
+
+    fn finish(mut self) -> String {
+        self.newline();
+        self.lines.join(&format!("{}\n", CONTINUATION))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn fits_on_one_line() {
+        let v = vec!["a", "b c"];
+        assert_eq!(to_string_pretty(&v).unwrap(), "[ a 'b c' ]");
+    }
+
+    #[test]
+    fn wraps_long_sequences() {
+        let v: Vec<u32> = (1..=12).collect();
+        assert_eq!(
+            to_string_pretty_with_width(&v, 16).unwrap(),
+            "[ \\\n  1 2 3 4 5 6 \\\n  7 8 9 10 11 \\\n  12 \\\n]"
+        );
+    }
+
+    #[test]
+    fn breaks_objects_per_key() {
+        let map = BTreeMap::from([
+            ("name", vec!["-1".to_string()]),
+            ("values", (1..=6).map(|i| i.to_string()).collect()),
+        ]);
+        assert_eq!(
+            to_string_pretty_with_width(&map, 20).unwrap(),
+            "[ \\\n  --name [ -- -1 ] \\\n  --values [ \\\n    -- 1 -- 2 -- 3 \\\n    -- 4 -- 5 -- 6 \\\n  ] \\\n]"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Support code for the `#[shon]` attribute macro. The (de)serializer
+// recognizes the names defined here, which lets the generated serde
+// attributes pass per-field settings along without any global state.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Newtype struct name for bool fields that may be given as a bare `--key`.
+pub const FLAG: &str = "$serde_shon::private::Flag";
+
+/// Newtype struct name for values that are hidden in shell strings.
+pub const SECRET: &str = "$serde_shon::private::Secret";
+
+/// Newtype struct name for decimals, which are written and read as their
+/// exact word instead of going through a float.
+pub const DECIMAL: &str = "$serde_shon::private::Decimal";
+
+/// Newtype struct name for values that are written as a single param
+/// holding their document.
+pub const NESTED: &str = "$serde_shon::private::Nested";
+
+/// Prefix of the field names given to positional struct fields.
+pub const POSITIONAL: &str = "$shon:positional:";
+
+/// Prefix of the field names given to the fields taking the params after
+/// the end of the options.
+pub const REST: &str = "$shon:rest:";
+
+/// What secrets are replaced with in shell strings.
+pub const REDACTED: &str = "***";
+
+pub fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FlagVisitor;
+
+    impl<'de> Visitor<'de> for FlagVisitor {
+        type Value = bool;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a flag")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<bool, E>
+        where
+            E: de::Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<bool, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            de::Deserialize::deserialize(deserializer)
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(FLAG, FlagVisitor)
+}
+
+pub fn serialize_nested<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(NESTED, value)
+}
+
+pub fn serialize_secret<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(SECRET, value)
+}
+
+/// Reads a struct flattened with `#[serde(flatten)]` from the params of the
+/// object it is flattened into, which the deserializer keeps at hand for it.
+pub fn deserialize_flattened<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: de::Deserialize<'de>,
+{
+    crate::de::deserialize_flattened(deserializer)
+}
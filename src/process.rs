@@ -0,0 +1,99 @@
+use std::ffi::OsStr;
+use std::process::{Command, Output, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{de, ser, words, Error, Result};
+
+/// Runs `program` with the serialized `input` as its arguments and
+/// deserializes whatever SHON it prints on stdout. The program's stderr is
+/// passed through, and a non-zero exit status is reported as an error.
+pub fn call<'a, I, O, P>(program: P, input: &I) -> Result<O>
+where
+    P: AsRef<OsStr>,
+    I: Serialize,
+    O: Deserialize<'a>,
+{
+    let program = program.as_ref();
+    let output = Command::new(program)
+        .args(ser::to_raw_params(input)?)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Message(format!(
+            "{} failed: {}",
+            program.to_string_lossy(),
+            output.status
+        )));
+    }
+    from_stdout_bytes(&output.stdout)
+}
+
+/// Deserializes the stdout of a finished child process. A non-zero exit
+/// status is reported as an error, with the stderr of the process included.
+pub fn from_output<'a, T>(output: &Output) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    if !output.status.success() {
+        return Err(Error::Message(format!(
+            "process failed: {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    from_stdout_bytes(&output.stdout)
+}
+
+/// Deserializes the captured output of a process. Output that contains NUL
+/// bytes is taken as NUL terminated raw params, like `to_xargs_string`
+/// produces them, otherwise it is split into words like a shell would.
+pub fn from_stdout_bytes<'a, T>(bytes: &[u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let stdout = std::str::from_utf8(bytes)
+        .map_err(|_| Error::Message("output is not valid UTF-8".to_string()))?;
+    let params = if stdout.contains('\0') {
+        let stdout = stdout.strip_suffix('\0').unwrap_or(stdout);
+        stdout.split('\0').map(String::from).collect()
+    } else {
+        words::split(stdout)?
+    };
+    de::from_params(params.into_iter())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn call_echo() {
+        let out: Vec<String> = call("echo", &vec!["a", "hello world"]).unwrap();
+        assert_eq!(out, vec!["a", "hello", "world"]);
+        assert!(call::<_, Vec<String>, _>("false", &()).is_err());
+    }
+
+    #[test]
+    fn stdout_bytes() {
+        let words: Vec<String> = from_stdout_bytes(b"[ a 'b c' ]\n").unwrap();
+        assert_eq!(words, vec!["a", "b c"]);
+        let raw: Vec<String> = from_stdout_bytes(b"[\0a\0'b c'\0]\0").unwrap();
+        assert_eq!(raw, vec!["a", "'b c'"]);
+    }
+
+    #[test]
+    fn process_output() {
+        let output = Command::new("printf").arg("[ --a 1 ]").output().unwrap();
+        let value: crate::Value = from_output(&output).unwrap();
+        assert_eq!(value.get("a").and_then(crate::Value::as_u64), Some(1));
+
+        let output = Command::new("sh")
+            .args(["-c", "echo oops >&2; exit 3"])
+            .output()
+            .unwrap();
+        let err = from_output::<crate::Value>(&output).unwrap_err();
+        assert!(err.to_string().contains("oops"));
+    }
+}
@@ -0,0 +1,283 @@
+// Streams of SHON documents, one record per document, for passing values
+// between processes. Newline terminated records make up the "ndshon" format,
+// which works like ndjson: every line holds one document, split into params
+// like a shell would split it.
+
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{words, Error, Result, Shell};
+
+/// What ends every record written by a `ShonWriter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordTerminator {
+    /// One record per line. Line breaks within strings stay inside their
+    /// quotes, so a reader has to keep reading while a quote is open.
+    #[default]
+    Newline,
+    /// NUL terminated records, which can't be confused with anything inside
+    /// of a record.
+    Nul,
+}
+
+impl RecordTerminator {
+    fn as_char(self) -> char {
+        match self {
+            RecordTerminator::Newline => '\n',
+            RecordTerminator::Nul => '\0',
+        }
+    }
+}
+
+/// Writes successive values as terminated records, for example into the
+/// stdin of a child process. Every record holds the params of one value,
+/// quoted the same way `to_string` does, but without hiding secrets.
+///
+/// ```
+/// let mut writer = serde_shon::ShonWriter::new(Vec::new());
+/// writer.write(&vec!["a b", "c"]).unwrap();
+/// writer.write(&1).unwrap();
+/// assert_eq!(writer.into_inner(), b"[ 'a b' c ]\n1\n");
+/// ```
+pub struct ShonWriter<W> {
+    writer: W,
+    terminator: RecordTerminator,
+    auto_flush: bool,
+}
+
+impl<W> ShonWriter<W>
+where
+    W: Write,
+{
+    /// Writes newline terminated records, flushing after every record.
+    pub fn new(writer: W) -> Self {
+        Self::with_terminator(writer, RecordTerminator::Newline)
+    }
+
+    pub fn with_terminator(writer: W, terminator: RecordTerminator) -> Self {
+        ShonWriter {
+            writer,
+            terminator,
+            auto_flush: true,
+        }
+    }
+
+    /// Whether every record is flushed as soon as it is written, which is the
+    /// default. Turning it off leaves flushing to `flush`, for batching
+    /// records into fewer writes through a buffered writer.
+    pub fn auto_flush(mut self, enabled: bool) -> Self {
+        self.auto_flush = enabled;
+        self
+    }
+
+    /// Serializes the value and writes it as a single record.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let mut record = String::new();
+        for (i, param) in crate::to_raw_params(value)?.iter().enumerate() {
+            if param.contains('\0') {
+                return Err(Error::Message(format!(
+                    "param {:?} contains a NUL byte",
+                    param
+                )));
+            }
+            if i > 0 {
+                record.push(' ');
+            }
+            record.push_str(&Shell::Posix.escape(param));
+        }
+        record.push(self.terminator.as_char());
+        // a single write keeps records whole when the other end reads them
+        // as they come in
+        self.writer.write_all(record.as_bytes())?;
+        if self.auto_flush {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes ndshon, one document per line.
+pub type RecordWriter<W> = ShonWriter<W>;
+
+/// Reads ndshon, one document per line, as written by a `RecordWriter`.
+/// Blank lines are skipped, and a line that ends within quotes or with a
+/// line continuation goes on with the next line.
+///
+/// ```
+/// let input = "[ --id 1 ]\n\n[ --id 2 --note 'two\nlines' ]\n";
+/// let mut reader = serde_shon::RecordReader::new(input.as_bytes());
+/// let first: serde_shon::Value = reader.read().unwrap().unwrap();
+/// assert_eq!(first.get("id").and_then(|id| id.as_u64()), Some(1));
+/// let rest: Vec<serde_shon::Value> = reader.records().collect::<Result<_, _>>().unwrap();
+/// assert_eq!(rest[0].get("note").and_then(|n| n.as_str()), Some("two\nlines"));
+/// ```
+pub struct RecordReader<R> {
+    reader: R,
+    line: String,
+}
+
+impl<R> RecordReader<R>
+where
+    R: BufRead,
+{
+    pub fn new(reader: R) -> Self {
+        RecordReader {
+            reader,
+            line: String::new(),
+        }
+    }
+
+    /// Reads the next record, or `None` at the end of the stream.
+    pub fn read<T>(&mut self) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self.next_params()? {
+            Some(params) => crate::de::from_params(params.into_iter()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterates over the remaining records.
+    pub fn records<T>(self) -> Records<R, T>
+    where
+        T: DeserializeOwned,
+    {
+        Records {
+            reader: self,
+            output: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn next_params(&mut self) -> Result<Option<Vec<String>>> {
+        self.line.clear();
+        loop {
+            if self.reader.read_line(&mut self.line)? == 0 {
+                if self.line.trim().is_empty() {
+                    return Ok(None);
+                }
+                // the last line keeps its quote open, let the error say so
+                return words::split(&self.line).map(Some);
+            }
+            let record = self.line.strip_suffix('\n').unwrap_or(&self.line);
+            let record = record.strip_suffix('\r').unwrap_or(record);
+            match words::split_partial(record) {
+                Some(params) if params.is_empty() => self.line.clear(),
+                Some(params) => return Ok(Some(params)),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Iterator over the records of a `RecordReader`.
+pub struct Records<R, T> {
+    reader: RecordReader<R>,
+    output: PhantomData<T>,
+}
+
+impl<R, T> Iterator for Records<R, T>
+where
+    R: BufRead,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufWriter, Read};
+    use std::process::{Command, Stdio};
+
+    use super::*;
+
+    #[test]
+    fn shon_writer() {
+        let mut writer = ShonWriter::with_terminator(Vec::new(), RecordTerminator::Nul);
+        writer.write(&vec!["a\nb", "-1"]).unwrap();
+        writer.write(&()).unwrap();
+        assert_eq!(writer.get_ref(), b"[ 'a\nb' -- -1 ]\0-n\0");
+        assert!(writer.write(&"\0").is_err());
+
+        let mut writer = ShonWriter::new(BufWriter::new(Vec::new())).auto_flush(false);
+        writer.write(&1).unwrap();
+        assert!(writer.get_ref().get_ref().is_empty());
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref().get_ref(), b"1\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn child_stdin() {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut writer = ShonWriter::new(child.stdin.take().unwrap());
+        writer.write(&vec![1, 2]).unwrap();
+        writer.write(&"x y").unwrap();
+        drop(writer);
+        let mut output = String::new();
+        child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        child.wait().unwrap();
+        assert_eq!(output, "[ 1 2 ]\n'x y'\n");
+    }
+
+    #[test]
+    fn ndshon_roundtrip() {
+        let values = vec![
+            vec!["a".to_string(), "multi\nline".to_string()],
+            vec![],
+            vec!["it's".to_string(), "back\\slash".to_string()],
+        ];
+        let mut writer = RecordWriter::new(Vec::new());
+        for value in &values {
+            writer.write(value).unwrap();
+        }
+        let output = writer.into_inner();
+        let reader = RecordReader::new(output.as_slice());
+        let read: Vec<Vec<String>> = reader.records().collect::<Result<_>>().unwrap();
+        assert_eq!(read, values);
+
+        let mut reader = RecordReader::new("[ 1 \\\n 2 ]\r\n'open\n".as_bytes());
+        assert_eq!(reader.read::<Vec<u32>>().unwrap(), Some(vec![1, 2]));
+        assert!(reader.read::<String>().is_err());
+        assert!(reader.read::<String>().unwrap().is_none());
+    }
+}
@@ -0,0 +1,112 @@
+// A wrapper for values that must not show up in logged command lines, for
+// types that don't go through the `#[shon(secret)]` attribute.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::private;
+
+/// Holds a value that is replaced by `***` in `to_string` and the other
+/// functions producing shell strings meant to be read, while `to_raw_params`
+/// keeps the real value for actually running the command. It deserializes
+/// like the wrapped value, and its `Debug` output is redacted as well.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_shon::Redacted;
+///
+/// #[derive(Serialize)]
+/// struct Login {
+///     user: String,
+///     password: Redacted<String>,
+/// }
+///
+/// let login = Login { user: "admin".into(), password: Redacted("hunter2".into()) };
+/// assert_eq!(
+///     serde_shon::to_raw_params(&login).unwrap(),
+///     ["[", "--user", "admin", "--password", "hunter2", "]"]
+/// );
+/// # #[cfg(feature = "shell")]
+/// assert_eq!(
+///     serde_shon::to_string(&login).unwrap(),
+///     "[ --user admin --password '***' ]"
+/// );
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Redacted<T>(pub T);
+
+impl<T> Redacted<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Redacted(value)
+    }
+}
+
+impl<T> Deref for Redacted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Redacted<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Redacted").field(&private::REDACTED).finish()
+    }
+}
+
+impl<T> Serialize for Redacted<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        private::serialize_secret(&self.0, serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Redacted<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Redacted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn redacted() {
+        let token = Redacted(vec!["a b".to_string()]);
+        assert_eq!(crate::to_raw_params(&token).unwrap(), ["[", "a b", "]"]);
+        #[cfg(feature = "shell")]
+        assert_eq!(crate::to_string(&token).unwrap(), "'***'");
+        assert_eq!(format!("{:?}", token), "Redacted(\"***\")");
+        assert_eq!(token.len(), 1);
+
+        let parsed: Redacted<u16> = crate::from_iter(["80"].into_iter()).unwrap();
+        assert_eq!(parsed.into_inner(), 80);
+    }
+}
@@ -1,12 +1,402 @@
+use std::io::Write;
+#[cfg(feature = "shell")]
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
 use serde::{ser, ser::SerializeSeq, Serialize};
 
+use crate::bytes::{self, BytesEncoding};
+use crate::extension::{Extension, Extensions};
+use crate::parse::{
+    self, CLOSE, EMPTY_ARRAY, EMPTY_OBJECT, FALSE, NULL, OPEN, STRING_ESCAPE, TRUE,
+};
+use crate::type_config::{self, TypeConfig};
+#[cfg(feature = "shell")]
+use crate::Shell;
+use crate::{private, words, Dialect};
+
 #[derive(Default)]
 pub struct Serializer {
     output: Vec<String>,
     empty_struct: bool,
+    // hide secrets, for output that is meant to be read rather than executed
+    redact: bool,
+    // put between top-level documents, see `serialize_document`
+    separator: Option<String>,
+    documents: usize,
+    transform: Option<Box<Transform>>,
+    // keys and indices leading to the value being serialized, only kept
+    // track of for the transform
+    path: Vec<String>,
+    // the next index of every array on the path
+    indices: Vec<usize>,
+    extensions: Extensions,
+    // the config of every struct being serialized, with the index of its
+    // next field
+    structs: Vec<(TypeConfig, usize)>,
+    // tells types to use their compact forms, see `human_readable`
+    compact: bool,
+    // how many values the value being serialized is nested in
+    depth: usize,
+    nest_depth: Option<usize>,
+    dialect: Dialect,
+    // the params of the rest field, written after the end of the options
+    rest: Option<Vec<String>>,
+    bytes: BytesEncoding,
 }
 
+type Transform = dyn Fn(&[String], &mut String);
+
+impl Serializer {
+    /// Creates a serializer that collects the params of any number of
+    /// documents.
+    pub fn new() -> Self {
+        Serializer::default()
+    }
+
+    /// Creates a serializer that puts the given param between documents.
+    /// Without one, documents directly follow each other, which is fine as
+    /// every document ends where its value does.
+    pub fn with_separator<S>(separator: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Serializer {
+            separator: Some(separator.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Calls the function with every string and number before it is
+    /// written, along with the keys and array indices leading up to it. The
+    /// function may change the text as it likes, to shorten huge values or
+    /// hide sensitive ones without a custom `Serialize` impl. Map keys and
+    /// the other tokens are left alone.
+    ///
+    /// ```
+    /// #[derive(serde::Serialize)]
+    /// struct Upload {
+    ///     name: String,
+    ///     body: String,
+    /// }
+    ///
+    /// let mut ser = serde_shon::Serializer::new().transform(|path, value| {
+    ///     if path == ["body"] {
+    ///         value.truncate(3);
+    ///     }
+    /// });
+    /// ser.serialize_document(&Upload { name: "a".into(), body: "abcdef".into() })
+    ///     .unwrap();
+    /// assert_eq!(ser.into_params(), ["[", "--name", "a", "--body", "abc", "]"]);
+    /// ```
+    pub fn transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&[String], &mut String) + 'static,
+    {
+        self.transform = Some(Box::new(transform));
+        self
+    }
+
+    /// Whether types are told to write their human readable forms, which is
+    /// the default. Without them, types like `IpAddr` and the encodings in
+    /// `time` pick their compact forms, which should be read back with the
+    /// same setting in `DeserializerOptions`.
+    pub fn human_readable(mut self, enabled: bool) -> Self {
+        self.compact = !enabled;
+        self
+    }
+
+    /// Writes byte strings, like those of `serde_bytes`, in the given
+    /// encoding. The deserializer reads all of them for byte strings.
+    ///
+    /// ```
+    /// use serde_shon::BytesEncoding;
+    ///
+    /// struct Key(Vec<u8>);
+    ///
+    /// impl serde::Serialize for Key {
+    ///     fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    ///         s.serialize_bytes(&self.0)
+    ///     }
+    /// }
+    ///
+    /// let mut ser = serde_shon::Serializer::new().bytes_encoding(BytesEncoding::Hex);
+    /// ser.serialize_document(&Key(vec![0xca, 0xfe])).unwrap();
+    /// assert_eq!(ser.into_params(), ["hex:cafe"]);
+    /// ```
+    pub fn bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes = encoding;
+        self
+    }
+
+    /// Writes every array and object nested in the given number of others as
+    /// a single param holding its document, like `[ --a 1 ]`, which scripts
+    /// that only pass on flat flags forward as is. The top-level value is at
+    /// depth 0. Fields marked with `#[shon(nested)]` are written like this at
+    /// any depth. See `DeserializerOptions::nested_documents` for reading
+    /// them back.
+    ///
+    /// ```
+    /// let value = serde_shon::Value::from_iter([
+    ///     ("name".to_string(), "a".into()),
+    ///     ("tags".to_string(), vec!["x y", "z"].into()),
+    /// ]);
+    /// let mut ser = serde_shon::Serializer::new().nest_at_depth(1);
+    /// ser.serialize_document(&value).unwrap();
+    /// assert_eq!(ser.into_params(), ["[", "--name", "a", "--tags", "[ 'x y' z ]", "]"]);
+    /// ```
+    pub fn nest_at_depth(mut self, depth: usize) -> Self {
+        self.nest_depth = Some(depth);
+        self
+    }
+
+    /// Writes the params in the given dialect, see `Dialect`. Only applies
+    /// to `serialize_document`.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Writes values as the token of the extension, wherever it collapses
+    /// their params. See `Extension`.
+    ///
+    /// # Panics
+    ///
+    /// If the prefix of the extension isn't a dash followed by a letter.
+    pub fn extension<E>(mut self, extension: E) -> Self
+    where
+        E: Extension + 'static,
+    {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Appends the params of another top-level value to the output.
+    ///
+    /// ```
+    /// let mut ser = serde_shon::Serializer::with_separator(";");
+    /// ser.serialize_document(&[1, 2]).unwrap();
+    /// ser.serialize_document(&"a b").unwrap();
+    /// assert_eq!(ser.into_params(), ["[", "1", "2", "]", ";", "a b"]);
+    /// ```
+    pub fn serialize_document<T>(&mut self, value: &T) -> crate::Result<()>
+    where
+        T: Serialize,
+    {
+        if self.documents > 0 {
+            if let Some(separator) = &self.separator {
+                self.output.push(separator.clone());
+            }
+        }
+        let start = self.output.len();
+        self.value(value)?;
+        let params = self.dialect.write(self.output.split_off(start));
+        if self.dialect.ends_options() {
+            if let Some(i) = params.iter().position(|p| p == STRING_ESCAPE) {
+                return Err(crate::Error::Serialize(format!(
+                    "{:?} can't be written as a string, as `--` ends the options",
+                    params.get(i + 1).map_or("", String::as_str)
+                )));
+            }
+        }
+        self.output.extend(params);
+        if let Some(rest) = self.rest.take().filter(|rest| !rest.is_empty()) {
+            self.output.push(STRING_ESCAPE.to_string());
+            self.output.extend(rest);
+        }
+        self.documents += 1;
+        Ok(())
+    }
+
+    /// The params serialized so far.
+    pub fn params(&self) -> &[String] {
+        &self.output
+    }
+
+    /// Consumes the serializer, returning the params of all documents.
+    pub fn into_params(self) -> Vec<String> {
+        self.output
+    }
+
+    // Serializes a value nested in another one or at the top, giving the
+    // extensions a chance to collapse it.
+    fn value<T>(&mut self, value: &T) -> crate::Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let start = self.output.len();
+        self.depth += 1;
+        let result = value.serialize(&mut *self);
+        self.depth -= 1;
+        result?;
+        if !self.extensions.is_empty() {
+            if let Some(token) = self.extensions.collapse(&self.output[start..]) {
+                self.output.truncate(start);
+                self.output.push(token);
+            }
+        }
+        if self.nest_depth == Some(self.depth) {
+            self.nest(start);
+        }
+        Ok(())
+    }
+
+    // Keeps the items of a rest field for after the end of the options,
+    // where they are written as they are.
+    fn rest_field<T>(&mut self, name: &str, value: &T) -> crate::Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.depth != 1 || self.structs.len() != 1 {
+            return Err(crate::Error::Serialize(format!(
+                "only the outermost struct can take the params after `--`, not the one with `{}`",
+                name
+            )));
+        }
+        let mut items = Serializer::new();
+        value.serialize(&mut items)?;
+        let items: Vec<crate::Value> = crate::de::from_params(items.output.into_iter())?;
+        let rest = items
+            .into_iter()
+            .map(|item| match item {
+                crate::Value::String(s) => Ok(s),
+                crate::Value::Number(n) => Ok(n.to_string()),
+                _ => Err(crate::Error::Serialize(format!(
+                    "the params after `--` have to be strings or numbers, unlike `{}`",
+                    name
+                ))),
+            })
+            .collect::<crate::Result<_>>()?;
+        self.rest = Some(rest);
+        Ok(())
+    }
+
+    // Joins the params of an array or object into a single one.
+    fn nest(&mut self, start: usize) {
+        if self.output.len() - start > 1 && self.output[start] == OPEN {
+            let params = self.dialect.write(self.output.split_off(start));
+            self.output.push(words::join(&params));
+        }
+    }
+
+    fn scalar(&mut self, mut value: String) -> String {
+        if let Some(transform) = &self.transform {
+            transform(&self.path, &mut value);
+        }
+        value
+    }
+
+    // The path is only kept up to date when something reads it.
+    fn enter(&mut self, segment: &str) {
+        if self.transform.is_some() {
+            self.path.push(segment.to_string());
+        }
+    }
+
+    fn leave(&mut self) {
+        if self.transform.is_some() {
+            self.path.pop();
+        }
+    }
+
+    fn enter_seq(&mut self) {
+        if self.transform.is_some() {
+            self.path.push(String::new());
+            self.indices.push(0);
+        }
+    }
+
+    fn next_element(&mut self) {
+        if let (Some(segment), Some(index)) = (self.path.last_mut(), self.indices.last_mut()) {
+            *segment = index.to_string();
+            *index += 1;
+        }
+    }
+
+    fn leave_seq(&mut self) {
+        if self.transform.is_some() {
+            self.path.pop();
+            self.indices.pop();
+        }
+    }
+}
+
+#[cfg(feature = "shell")]
 pub fn to_string<T>(value: &T) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    to_string_for_shell(value, Shell::Posix)
+}
+
+/// Like `to_string`, but quotes the params for the given shell.
+#[cfg(feature = "shell")]
+pub fn to_string_for_shell<T>(value: &T, shell: Shell) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    let params = to_redacted_params(value)?;
+    for token in &params {
+        shell.check(token)?;
+    }
+    Ok(params
+        .iter()
+        .map(|token| shell.escape(token))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Like `to_string`, but joins the params with the given separator instead
+/// of a space, for consumers that split the output on something like tabs or
+/// newlines. It is an error if the separator shows up in any of the quoted
+/// params, as the output couldn't be split back apart.
+#[cfg(feature = "shell")]
+pub fn to_string_with_separator<T>(value: &T, separator: &str) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    if separator.is_empty() {
+        return Err(crate::Error::Serialize("separator is empty".to_string()));
+    }
+    let mut output = String::new();
+    for (i, token) in to_redacted_params(value)?.iter().enumerate() {
+        let token = Shell::Posix.escape(token);
+        if token.contains(separator) {
+            return Err(crate::Error::Serialize(format!(
+                "param {} contains the separator {:?}",
+                token, separator
+            )));
+        }
+        if i > 0 {
+            output.push_str(separator);
+        }
+        output.push_str(&token);
+    }
+    Ok(output)
+}
+
+/// Serializes the value into shell-escaped params, like `to_string` without
+/// joining them.
+#[cfg(feature = "shell")]
+pub fn to_params<T>(value: &T) -> crate::Result<Vec<String>>
+where
+    T: Serialize,
+{
+    Ok(to_raw_params(value)?
+        .iter()
+        .map(|param| Shell::Posix.escape(param).into_owned())
+        .collect())
+}
+
+/// Serializes the value into raw params, which can be passed to
+/// `std::process::Command` as they are.
+pub fn to_raw_params<T>(value: &T) -> crate::Result<Vec<String>>
 where
     T: Serialize,
 {
@@ -15,22 +405,177 @@ where
         ..Default::default()
     };
     value.serialize(&mut serializer)?;
-    Ok(serializer.output.join(" "))
+    Ok(serializer.output)
 }
 
-pub fn to_params<T>(value: &T) -> crate::Result<Vec<String>>
+// Like `to_raw_params`, with secrets replaced, for output meant to be read
+// rather than executed.
+#[cfg(feature = "shell")]
+pub(crate) fn to_redacted_params<T>(value: &T) -> crate::Result<Vec<String>>
 where
     T: Serialize,
 {
     let mut serializer = Serializer {
         output: Vec::new(),
+        redact: true,
         ..Default::default()
     };
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+/// Serializes the items as a sequence into raw params, like `to_raw_params`,
+/// one element at a time, so the items never have to be collected up front.
+pub fn to_raw_params_from_iter<I>(iter: I) -> crate::Result<Vec<String>>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    let mut serializer = Serializer {
+        output: Vec::new(),
+        ..Default::default()
+    };
+    let mut seq = ser::Serializer::serialize_seq(&mut serializer, None)?;
+    for item in iter {
+        seq.serialize_element(&item)?;
+    }
+    seq.end()?;
+    Ok(serializer.output)
+}
+
+/// Like `to_raw_params_from_iter`, but writes the shell-escaped output of every
+/// element to the writer as soon as it is serialized, the same way
+/// `to_string` would format it.
+#[cfg(feature = "shell")]
+pub fn to_writer_from_iter<W, I>(mut writer: W, iter: I) -> crate::Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    let mut serializer = Serializer {
+        output: Vec::new(),
+        redact: true,
+        ..Default::default()
+    };
+    let mut first = true;
+    let mut seq = ser::Serializer::serialize_seq(&mut serializer, None)?;
+    write_tokens(&mut writer, &mut seq.output, &mut first)?;
+    for item in iter {
+        seq.serialize_element(&item)?;
+        write_tokens(&mut writer, &mut seq.output, &mut first)?;
+    }
+    seq.end()?;
+    write_tokens(&mut writer, &mut serializer.output, &mut first)?;
+    Ok(())
+}
+
+#[cfg(feature = "shell")]
+fn write_tokens<W>(writer: &mut W, tokens: &mut Vec<String>, first: &mut bool) -> io::Result<()>
+where
+    W: Write,
+{
+    for token in tokens.drain(..) {
+        if !*first {
+            writer.write_all(b" ")?;
+        }
+        *first = false;
+        writer.write_all(Shell::Posix.escape(&token).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Like `to_raw_params`, but once the params grow beyond `max_len` bytes
+/// they are written to a response file in the temp directory instead, one
+/// shell-escaped param per line, and a single `@path` param pointing at it is
+/// returned, as read back with `DeserializerOptions::response_files`. The
+/// caller is responsible for removing the file once it has been consumed.
+#[cfg(feature = "shell")]
+pub fn to_params_or_response_file<T>(value: &T, max_len: usize) -> crate::Result<Vec<String>>
+where
+    T: Serialize,
+{
+    let params = to_raw_params(value)?;
+    // every param costs its own length plus the terminator that ends it on
+    // the command line
+    let len: usize = params.iter().map(|p| p.len() + 1).sum();
+    if len <= max_len {
+        return Ok(params);
+    }
+    let (path, mut file) = create_response_file()?;
+    for param in &params {
+        writeln!(file, "{}", Shell::Posix.escape(param))?;
+    }
+    file.flush()?;
+    Ok(vec![format!("@{}", path.display())])
+}
+
+/// Delimiter placed after every param by `to_xargs_string` and `write_xargs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XargsDelimiter {
+    /// NUL terminated params, to be read with `xargs -0`.
+    Nul,
+    /// Newline terminated params, to be read with `xargs -d '\n'`.
+    Newline,
+}
+
+impl XargsDelimiter {
+    fn as_char(self) -> char {
+        match self {
+            XargsDelimiter::Nul => '\0',
+            XargsDelimiter::Newline => '\n',
+        }
+    }
+}
+
+/// Serializes the value into raw (unescaped) params, each one followed by
+/// the delimiter, ready to be piped into `xargs`.
+pub fn to_xargs_string<T>(value: &T, delimiter: XargsDelimiter) -> crate::Result<String>
+where
+    T: Serialize,
+{
+    let mut output = String::new();
+    for param in to_raw_params(value)? {
+        if param.contains(delimiter.as_char()) {
+            return Err(crate::Error::Serialize(format!(
+                "param {:?} contains the xargs delimiter",
+                param
+            )));
+        }
+        output.push_str(&param);
+        output.push(delimiter.as_char());
+    }
+    Ok(output)
+}
+
+/// Like `to_xargs_string`, but writes the params into the given writer.
+pub fn write_xargs<W, T>(mut writer: W, value: &T, delimiter: XargsDelimiter) -> crate::Result<()>
+where
+    W: Write,
+    T: Serialize,
+{
+    writer.write_all(to_xargs_string(value, delimiter)?.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(feature = "shell")]
+fn create_response_file() -> crate::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    loop {
+        let path = env::temp_dir().join(format!(
+            "serde_shon-{}-{}.args",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+impl ser::Serializer for &mut Serializer {
     type Ok = ();
 
     type Error = crate::Error;
@@ -43,10 +588,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
+    fn is_human_readable(&self) -> bool {
+        !self.compact
+    }
+
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
         self.output.push(match v {
-            true => "-t".to_string(),
-            false => "-f".to_string(),
+            true => TRUE.to_string(),
+            false => FALSE.to_string(),
         });
         Ok(())
     }
@@ -64,7 +613,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.output.push(v.to_string());
+        let token = self.scalar(v.to_string());
+        self.output.push(token);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        let token = self.scalar(v.to_string());
+        self.output.push(token);
         Ok(())
     }
 
@@ -81,7 +637,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        self.output.push(v.to_string());
+        let token = self.scalar(v.to_string());
+        self.output.push(token);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        let token = self.scalar(v.to_string());
+        self.output.push(token);
         Ok(())
     }
 
@@ -90,7 +653,16 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.output.push(v.to_string());
+        // see `DeserializerOptions::special_floats`
+        let token = if v.is_nan() {
+            "nan".to_string()
+        } else if v.is_infinite() {
+            if v > 0.0 { "inf" } else { "-inf" }.to_string()
+        } else {
+            v.to_string()
+        };
+        let token = self.scalar(token);
+        self.output.push(token);
         Ok(())
     }
 
@@ -99,19 +671,29 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        let result = shell_escape::unix::escape(v.into());
-        if result == "-"
-            || result == "--"
-            || result.parse::<i64>().is_ok()
-            || result.parse::<f64>().is_ok()
+        let v = self.scalar(v.to_string());
+        // anything that could be mistaken for a flag, key, bracket, number or
+        // response file, including prefixed integers, needs the string sigil
+        // in front, as does the empty string, which would be dropped otherwise
+        if v.is_empty()
+            || v.starts_with(['-', '@'])
+            || matches!(v.as_str(), OPEN | CLOSE | EMPTY_ARRAY | EMPTY_OBJECT)
+            || v.parse::<i64>().is_ok()
+            || v.parse::<f64>().is_ok()
+            || parse::prefixed(&v).is_some()
         {
-            self.output.push("--".to_string());
+            self.output.push(STRING_ESCAPE.to_string());
         }
-        self.output.push(result.to_string());
+        self.output.push(v);
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(param) = bytes::encode(v, self.bytes) {
+            let param = self.scalar(param);
+            self.output.push(param);
+            return Ok(());
+        }
         let mut seq = self.serialize_seq(Some(v.len()))?;
         for byte in v {
             seq.serialize_element(byte)?;
@@ -123,15 +705,15 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_unit()
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("-n".to_string());
+        self.output.push(NULL.to_string());
         Ok(())
     }
 
@@ -148,18 +730,38 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        if name == private::SECRET && self.redact {
+            return self.serialize_str(private::REDACTED);
+        }
+        if name == private::NESTED {
+            let start = self.output.len();
+            value.serialize(&mut *self)?;
+            self.nest(start);
+            return Ok(());
+        }
+        if name == private::DECIMAL {
+            // the decimal comes as a string, which is written as a bare word
+            // so it reads as a number everywhere else
+            let mut word = Serializer::new();
+            value.serialize(&mut word)?;
+            if word.params().first().map(String::as_str) == Some(STRING_ESCAPE) {
+                word.output.remove(0);
+            }
+            self.output.extend(word.output);
+            return Ok(());
+        }
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -167,7 +769,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         use serde::ser::SerializeMap;
         let mut map = self.serialize_map(Some(1))?;
@@ -177,7 +779,8 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        self.output.push("[".to_string());
+        self.output.push(OPEN.to_string());
+        self.enter_seq();
         Ok(self)
     }
 
@@ -200,14 +803,21 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.output.push("[".to_string());
+        self.output.push(OPEN.to_string());
         self.output.push(format!("--{}", variant)); // TODO key formatter
-        self.output.push("[".to_string());
+        self.output.push(OPEN.to_string());
+        self.enter(variant);
+        self.enter_seq();
         Ok(self)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        self.output.push("[".to_string());
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        if len == Some(0) {
+            self.output.push(EMPTY_OBJECT.to_string());
+            self.empty_struct = true;
+            return Ok(self);
+        }
+        self.output.push(OPEN.to_string());
         Ok(self)
     }
 
@@ -216,9 +826,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.structs.push((type_config::take(), 0));
         match len {
             0 => {
-                self.output.push("[--]".to_string());
+                self.output.push(EMPTY_OBJECT.to_string());
                 self.empty_struct = true;
                 Ok(self)
             }
@@ -233,163 +844,216 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.output.push("[".to_string());
+        self.output.push(OPEN.to_string());
         self.output.push(format!("--{}", variant)); // TODO key formatter
-        self.output.push("[".to_string());
+        self.output.push(OPEN.to_string());
+        self.enter(variant);
         Ok(self)
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     type Ok = ();
 
     type Error = crate::Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.next_element();
+        self.value(value)
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
+        self.leave_seq();
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
 
     type Error = crate::Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.next_element();
+        self.value(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
+        self.leave_seq();
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl ser::SerializeTupleStruct for &mut Serializer {
     type Ok = ();
 
     type Error = crate::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.next_element();
+        self.value(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
+        self.leave_seq();
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = crate::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.next_element();
+        self.value(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
-        self.output.push("]".to_string());
+        self.leave_seq();
+        self.leave();
+        self.output.push(CLOSE.to_string());
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl ser::SerializeMap for &mut Serializer {
     type Ok = ();
     type Error = crate::Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         // TODO: implement custom keyserialiezr that only serializes str instead of... this
-        key.serialize(&mut **self)?;
-        let mut key = self.output.pop().unwrap();
-        if key.starts_with('\'') {
-            key.remove(0);
-            self.output.push(format!("'--{}", key));
-        } else {
-            self.output.push(format!("--{}", key));
+        let start = self.output.len();
+        // keys are left to the caller, the transform is only for values
+        let transform = self.transform.take();
+        let result = key.serialize(&mut **self);
+        self.transform = transform;
+        result?;
+        let mut tokens = self.output.split_off(start);
+        // number-like strings come with a `--` sigil, which the key prefix
+        // already takes care of
+        let key = match tokens.len() {
+            1 => tokens.pop(),
+            2 if tokens[0] == STRING_ESCAPE => tokens.pop(),
+            _ => None,
         }
+        .ok_or_else(|| crate::Error::Serialize("map key must be a single token".to_string()))?;
+        self.enter(&key);
+        self.output.push(format!("--{}", key));
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.value(value)?;
+        self.leave();
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
+        if self.empty_struct {
+            self.empty_struct = false;
+            return Ok(());
+        }
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl ser::SerializeStruct for &mut Serializer {
     type Ok = ();
     type Error = crate::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.output.push(format!("--{}", key)); // TODO key formatter
-        value.serialize(&mut **self)
+        if let Some(name) = key.strip_prefix(private::REST) {
+            if self.dialect.ends_options() {
+                self.rest_field(name, value)?;
+                return self.skip_field(key);
+            }
+        }
+        let (config, index) = self.structs.last().copied().unwrap_or_default();
+        let keyed = !key.starts_with(private::POSITIONAL) && index >= config.positional;
+        let name = key
+            .strip_prefix(private::POSITIONAL)
+            .or_else(|| key.strip_prefix(private::REST))
+            .unwrap_or(key);
+        if keyed {
+            self.output
+                .push(format!("--{}", config.key_style.key(name)));
+        }
+        self.enter(name);
+        let start = self.output.len();
+        self.value(value)?;
+        if keyed && config.flags && self.output[start..] == [TRUE] {
+            self.output.pop();
+        }
+        self.leave();
+        self.skip_field(key)
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        if let Some((_, index)) = self.structs.last_mut() {
+            *index += 1;
+        }
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.structs.pop();
         if self.empty_struct {
             self.empty_struct = false;
             return Ok(());
         }
-        self.output.push("]".to_string());
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl ser::SerializeStructVariant for &mut Serializer {
     type Ok = ();
     type Error = crate::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        self.output.push(format!("--{}", key)); // TODO key formatter
-        value.serialize(&mut **self)
+        if !key.starts_with(private::POSITIONAL) {
+            self.output.push(format!("--{}", key)); // TODO key formatter
+        }
+        self.enter(key.strip_prefix(private::POSITIONAL).unwrap_or(key));
+        self.value(value)?;
+        self.leave();
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.output.push("]".to_string());
-        self.output.push("]".to_string());
+        self.leave();
+        self.output.push(CLOSE.to_string());
+        self.output.push(CLOSE.to_string());
         Ok(())
     }
 }
@@ -399,6 +1063,7 @@ mod test {
     use super::*;
 
     #[test]
+    #[cfg(feature = "shell")]
     fn test_struct() {
         #[derive(Serialize)]
         struct Test {
@@ -415,6 +1080,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(feature = "shell")]
     fn test_unit_struct() {
         #[derive(Serialize)]
         struct Test {}
@@ -424,6 +1090,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(feature = "shell")]
     fn test_enum() {
         #[derive(Serialize)]
         enum E {
@@ -449,4 +1116,262 @@ mod test {
         let expected = r#"[ --Struct [ --a 1 ] ]"#;
         assert_eq!(to_string(&s).unwrap(), expected);
     }
+
+    #[test]
+    fn test_params_are_raw() {
+        let seq = vec!["hello world", "10", "it's"];
+        assert_eq!(
+            to_raw_params(&seq).unwrap(),
+            vec!["[", "hello world", "--", "10", "it's", "]"]
+        );
+        #[cfg(feature = "shell")]
+        assert_eq!(
+            to_params(&seq).unwrap(),
+            vec!["[", "'hello world'", "--", "10", r#"'it'\''s'"#, "]"]
+        );
+        #[cfg(feature = "shell")]
+        assert_eq!(
+            to_string(&seq).unwrap(),
+            r#"[ 'hello world' -- 10 'it'\''s' ]"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn test_nushell() {
+        let seq = vec!["hello world", "$x"];
+        assert_eq!(
+            to_string_for_shell(&seq, Shell::Nushell).unwrap(),
+            "'[' 'hello world' '$x' ']'"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn test_separator() {
+        let seq = vec!["hello world", "a\tb"];
+        assert_eq!(
+            to_string_with_separator(&seq, "\n").unwrap(),
+            "[\n'hello world'\n'a\tb'\n]"
+        );
+        assert!(to_string_with_separator(&seq, "\t").is_err());
+        assert!(to_string_with_separator(&seq, "").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn test_make() {
+        let seq = vec!["hello world", "$x"];
+        assert_eq!(
+            to_string_for_shell(&seq, Shell::Make).unwrap(),
+            "[ 'hello world' '$$x' ]"
+        );
+        assert!(to_string_for_shell(&vec!["a\nb"], Shell::Dockerfile).is_err());
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let map = std::collections::BTreeMap::from([("10", 1), ("a b", 2)]);
+        assert_eq!(
+            to_raw_params(&map).unwrap(),
+            vec!["[", "--10", "1", "--a b", "2", "]"]
+        );
+    }
+
+    #[test]
+    fn test_xargs() {
+        let seq = vec!["a b", "c"];
+        assert_eq!(
+            to_xargs_string(&seq, XargsDelimiter::Nul).unwrap(),
+            "[\0a b\0c\0]\0"
+        );
+        assert_eq!(
+            to_xargs_string(&seq, XargsDelimiter::Newline).unwrap(),
+            "[\na b\nc\n]\n"
+        );
+        assert!(to_xargs_string(&vec!["a\nb"], XargsDelimiter::Newline).is_err());
+
+        let mut out = Vec::new();
+        write_xargs(&mut out, &seq, XargsDelimiter::Nul).unwrap();
+        assert_eq!(out, b"[\0a b\0c\0]\0");
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let iter = (1..=3).map(|i| format!("item {}", i));
+        assert_eq!(
+            to_raw_params_from_iter(iter).unwrap(),
+            vec!["[", "item 1", "item 2", "item 3", "]"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn test_writer_from_iter() {
+        let iter = (1..=3).map(|i| format!("item {}", i));
+        let mut out = Vec::new();
+        to_writer_from_iter(&mut out, iter).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[ 'item 1' 'item 2' 'item 3' ]"
+        );
+
+        let mut out = Vec::new();
+        to_writer_from_iter(&mut out, Vec::<u32>::new()).unwrap();
+        assert_eq!(out, b"[ ]");
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn test_response_file() {
+        let seq = vec!["a", "hello world", "c"];
+        assert_eq!(
+            to_params_or_response_file(&seq, 1024).unwrap(),
+            to_raw_params(&seq).unwrap()
+        );
+        // the raw params are measured, not what a shell would need
+        assert_eq!(to_params_or_response_file(&seq, 21).unwrap().len(), 5);
+
+        let params = to_params_or_response_file(&seq, 4).unwrap();
+        assert_eq!(params.len(), 1);
+        let path = params[0].strip_prefix('@').unwrap();
+        let contents = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(contents, "[\na\n'hello world'\nc\n]\n");
+
+        // either way, the params read back as the value
+        let options = crate::DeserializerOptions::new().response_files(true);
+        let seq = vec!["a", "it's", "two\nlines", "", "-t", "@x", "[", "c"];
+        for max_len in [1024, 4] {
+            let params = to_params_or_response_file(&seq, max_len).unwrap();
+            let args = std::iter::once("./binary".to_string()).chain(params.clone());
+            let parsed: Vec<String> = crate::from_args_with(args, &options).unwrap();
+            if let [file] = &params[..] {
+                std::fs::remove_file(file.strip_prefix('@').unwrap()).unwrap();
+            }
+            assert_eq!(parsed, seq);
+        }
+    }
+
+    #[test]
+    fn test_documents() {
+        let mut ser = Serializer::new();
+        ser.serialize_document(&1).unwrap();
+        ser.serialize_document(&vec!["a"]).unwrap();
+        ser.serialize_document(&Option::<u8>::None).unwrap();
+        assert_eq!(ser.params(), ["1", "[", "a", "]", "-n"]);
+
+        let mut ser = Serializer::with_separator("--");
+        ser.serialize_document(&true).unwrap();
+        assert_eq!(ser.params(), ["-t"]);
+        ser.serialize_document(&"x").unwrap();
+        assert_eq!(ser.into_params(), ["-t", "--", "x"]);
+    }
+
+    #[test]
+    fn test_nest_at_depth() {
+        #[derive(Serialize)]
+        struct Job {
+            name: &'static str,
+            env: Vec<(&'static str, &'static str)>,
+            empty: Vec<u8>,
+        }
+        let job = Job {
+            name: "a",
+            env: vec![("HOME", "/home/a b")],
+            empty: vec![],
+        };
+        let serialize = |depth| {
+            let mut ser = Serializer::new().nest_at_depth(depth);
+            ser.serialize_document(&job).unwrap();
+            ser.into_params()
+        };
+        assert_eq!(
+            serialize(1),
+            [
+                "[",
+                "--name",
+                "a",
+                "--env",
+                "[ [ HOME '/home/a b' ] ]",
+                "--empty",
+                "[ ]",
+                "]"
+            ]
+        );
+        assert_eq!(
+            serialize(2),
+            [
+                "[",
+                "--name",
+                "a",
+                "--env",
+                "[",
+                "[ HOME '/home/a b' ]",
+                "]",
+                "--empty",
+                "[",
+                "]",
+                "]"
+            ]
+        );
+        assert_eq!(
+            serialize(0),
+            ["[ --name a --env [ [ HOME '/home/a b' ] ] --empty [ ] ]"]
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        use std::cell::RefCell;
+        use std::collections::BTreeMap;
+        use std::rc::Rc;
+
+        #[derive(Serialize)]
+        enum E {
+            Tuple(u32, &'static str),
+        }
+        #[derive(Serialize)]
+        struct Test {
+            name: &'static str,
+            tags: Vec<&'static str>,
+            env: BTreeMap<&'static str, &'static str>,
+            e: E,
+            ok: bool,
+        }
+
+        let test = Test {
+            name: "shon",
+            tags: vec!["a", "b"],
+            env: BTreeMap::from([("KEY", "v")]),
+            e: E::Tuple(1, "x"),
+            ok: true,
+        };
+        let paths = Rc::new(RefCell::new(Vec::new()));
+        let seen = paths.clone();
+        let mut ser = Serializer::new().transform(move |path, value| {
+            seen.borrow_mut().push(path.join("."));
+            value.make_ascii_uppercase();
+            if value == "1" {
+                *value = "-1".to_string();
+            }
+        });
+        ser.serialize_document(&test).unwrap();
+        assert_eq!(
+            *paths.borrow(),
+            [
+                "name",
+                "tags.0",
+                "tags.1",
+                "env.KEY",
+                "e.Tuple.0",
+                "e.Tuple.1"
+            ]
+        );
+        assert_eq!(
+            ser.into_params().join(" "),
+            "[ --name SHON --tags [ A B ] --env [ --KEY V ] \
+             --e [ --Tuple [ -1 X ] ] --ok -t ]"
+        );
+    }
 }
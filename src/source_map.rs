@@ -0,0 +1,122 @@
+// Keeps track of which params every value was read from, for tools that
+// want to point back at the command line, like in error messages.
+
+use std::ops::Range;
+
+/// The params that every value of a document was read from, see
+/// `from_args_mapped`.
+///
+/// Values are found by their JSON pointer, like with `Value::pointer`. The
+/// segments are the field names of struct fields, the keys as written for
+/// maps and the index for the elements of arrays. The document itself is
+/// at `""`. Ranges are indices into the args, including the binary in
+/// front, and cover the key of a value as well.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    // in the order the values started
+    ranges: Vec<(String, Range<usize>)>,
+}
+
+impl SourceMap {
+    /// The args the value at the pointer was read from.
+    pub fn get(&self, pointer: &str) -> Option<Range<usize>> {
+        self.ranges
+            .iter()
+            .rev()
+            .find(|(p, _)| p == pointer)
+            .map(|(_, range)| range.clone())
+    }
+
+    /// All values with the args they were read from, outer values before
+    /// the ones within them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.ranges
+            .iter()
+            .map(|(pointer, range)| (pointer.as_str(), range.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+// Builds a source map while a document is read. Positions are handed in as
+// indices into the params and moved by the offset of the params in the args.
+pub(crate) struct Recorder {
+    map: SourceMap,
+    offset: usize,
+    // the entries of the values being read, innermost last
+    open: Vec<usize>,
+}
+
+impl Recorder {
+    pub(crate) fn new(offset: usize) -> Self {
+        Recorder {
+            map: SourceMap::default(),
+            offset,
+            open: Vec::new(),
+        }
+    }
+
+    // starts the value with the given segment, or the document without one
+    pub(crate) fn enter(&mut self, segment: Option<&str>, start: usize) {
+        let pointer = match (self.open.last(), segment) {
+            (Some(&parent), Some(segment)) => format!(
+                "{}/{}",
+                self.map.ranges[parent].0,
+                segment.replace('~', "~0").replace('/', "~1")
+            ),
+            _ => String::new(),
+        };
+        let start = start + self.offset;
+        self.open.push(self.map.ranges.len());
+        self.map.ranges.push((pointer, start..start));
+    }
+
+    // ends the innermost value right before the given position
+    pub(crate) fn leave(&mut self, end: usize) {
+        if let Some(entry) = self.open.pop() {
+            let range = &mut self.map.ranges[entry].1;
+            range.end = range.start.max(end + self.offset);
+        }
+    }
+
+    // the pointer of the innermost value being read
+    pub(crate) fn pointer(&self) -> &str {
+        match self.open.last() {
+            Some(&entry) => &self.map.ranges[entry].0,
+            None => "",
+        }
+    }
+
+    pub(crate) fn finish(self) -> SourceMap {
+        self.map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pointers() {
+        let mut recorder = Recorder::new(1);
+        recorder.enter(None, 0);
+        recorder.enter(Some("a/b"), 1);
+        recorder.enter(Some("0"), 2);
+        recorder.leave(3);
+        recorder.leave(3);
+        recorder.leave(4);
+        let map = recorder.finish();
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [("", 1..5), ("/a~1b", 2..4), ("/a~1b/0", 3..4)]
+        );
+        assert_eq!(map.get("/a~1b/0"), Some(3..4));
+        assert_eq!(map.get("/c"), None);
+    }
+}
@@ -0,0 +1,474 @@
+//! Compact encodings of `SystemTime` and `Duration`, for use with
+//! `#[serde(with = "serde_shon::time::system_time")]` and
+//! `#[serde(with = "serde_shon::time::duration")]`.
+//!
+//! The derived impls of both write a struct of seconds and nanoseconds,
+//! which is a mouthful on a command line. With these modules, human readable
+//! formats get an RFC 3339 timestamp like `2024-05-01T12:00:00Z` and a
+//! duration like `1h 30m`, while others get the number of seconds, with a
+//! fraction if there is one. SHON is human readable unless the serializer
+//! is set up otherwise, see `Serializer::human_readable`. Either form is
+//! read back regardless.
+//!
+//! ```
+//! use std::time::{Duration, SystemTime};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Job {
+//!     #[serde(with = "serde_shon::time::system_time")]
+//!     start: SystemTime,
+//!     #[serde(with = "serde_shon::time::duration")]
+//!     timeout: Duration,
+//! }
+//!
+//! let job = Job {
+//!     start: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+//!     timeout: Duration::from_secs(90),
+//! };
+//! let args = ["[", "--start", "2023-11-14T22:13:20Z", "--timeout", "1m 30s", "]"];
+//! assert_eq!(serde_shon::to_raw_params(&job).unwrap(), args);
+//! assert_eq!(serde_shon::from_iter::<Job, _>(args.into_iter()).unwrap(), job);
+//!
+//! let mut ser = serde_shon::Serializer::new().human_readable(false);
+//! ser.serialize_document(&job).unwrap();
+//! assert_eq!(
+//!     ser.into_params(),
+//!     ["[", "--start", "1700000000", "--timeout", "90", "]"]
+//! );
+//! let args = ["[", "--start", "1700000000", "--timeout", "90.0", "]"];
+//! assert_eq!(serde_shon::from_iter::<Job, _>(args.into_iter()).unwrap(), job);
+//! ```
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+pub mod system_time {
+    use super::*;
+
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (secs, nanos) = unix_time(*time);
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&format_rfc3339(secs, nanos));
+        }
+        match nanos {
+            0 => serializer.serialize_i64(secs),
+            _ => serializer.serialize_f64(secs as f64 + f64::from(nanos) / 1e9),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SystemTimeVisitor;
+
+        impl Visitor<'_> for SystemTimeVisitor {
+            type Value = SystemTime;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an RFC 3339 timestamp or unix seconds")
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<SystemTime, E>
+            where
+                E: de::Error,
+            {
+                from_unix(v, 0).ok_or_else(|| E::custom("timestamp out of range"))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<SystemTime, E>
+            where
+                E: de::Error,
+            {
+                let secs = i64::try_from(v).map_err(|_| E::custom("timestamp out of range"))?;
+                self.visit_i64(secs)
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<SystemTime, E>
+            where
+                E: de::Error,
+            {
+                let offset = Duration::try_from_secs_f64(v.abs()).map_err(E::custom)?;
+                match v.is_sign_negative() {
+                    true => SystemTime::UNIX_EPOCH.checked_sub(offset),
+                    false => SystemTime::UNIX_EPOCH.checked_add(offset),
+                }
+                .ok_or_else(|| E::custom("timestamp out of range"))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<SystemTime, E>
+            where
+                E: de::Error,
+            {
+                if let Ok(secs) = v.parse::<f64>() {
+                    return self.visit_f64(secs);
+                }
+                parse_rfc3339(v).ok_or_else(|| E::custom(format!("invalid timestamp `{}`", v)))
+            }
+        }
+
+        deserializer.deserialize_any(SystemTimeVisitor)
+    }
+}
+
+pub mod duration {
+    use super::*;
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&format_duration(*duration));
+        }
+        match duration.subsec_nanos() {
+            0 => serializer.serialize_u64(duration.as_secs()),
+            _ => serializer.serialize_f64(duration.as_secs_f64()),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DurationVisitor;
+
+        impl Visitor<'_> for DurationVisitor {
+            type Value = Duration;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a duration like `1m 30s` or seconds")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Duration, E>
+            where
+                E: de::Error,
+            {
+                Ok(Duration::from_secs(v))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Duration, E>
+            where
+                E: de::Error,
+            {
+                let secs = u64::try_from(v).map_err(|_| E::custom("negative duration"))?;
+                self.visit_u64(secs)
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Duration, E>
+            where
+                E: de::Error,
+            {
+                Duration::try_from_secs_f64(v).map_err(E::custom)
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Duration, E>
+            where
+                E: de::Error,
+            {
+                if let Ok(secs) = v.parse::<f64>() {
+                    return self.visit_f64(secs);
+                }
+                parse_duration(v).ok_or_else(|| E::custom(format!("invalid duration `{}`", v)))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+// Whole seconds since the epoch, rounded down, and the nanoseconds after.
+fn unix_time(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(after) => (after.as_secs() as i64, after.subsec_nanos()),
+        Err(err) => {
+            let before = err.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+            }
+        }
+    }
+}
+
+fn from_unix(secs: i64, nanos: u32) -> Option<SystemTime> {
+    let time = match u64::try_from(secs) {
+        Ok(secs) => SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))?,
+        Err(_) => SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))?,
+    };
+    time.checked_add(Duration::from_nanos(nanos.into()))
+}
+
+const DAY: i64 = 86_400;
+
+// The proleptic Gregorian date of a day since the epoch, after Howard
+// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn format_rfc3339(secs: i64, nanos: u32) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(DAY));
+    let time = secs.rem_euclid(DAY);
+    let mut out = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    if nanos > 0 {
+        let fraction = format!("{:09}", nanos);
+        out.push('.');
+        out.push_str(fraction.trim_end_matches('0'));
+    }
+    out.push('Z');
+    out
+}
+
+// `YYYY-MM-DDTHH:MM:SS[.fraction](Z|+HH:MM|-HH:MM)`, where `T` and `Z` may
+// also be lowercase and `T` a space.
+fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let number = |range: std::ops::Range<usize>| -> Option<u32> {
+        let digits = s.get(range)?;
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let bytes = s.as_bytes();
+    if s.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    // a leap second is folded into the next second
+    if day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut rest = &s[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        let digits = &fraction[..len.min(9)];
+        nanos = digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32);
+        rest = &fraction[len..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first()? {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            let (hours, minutes): (u32, u32) = (rest[1..3].parse().ok()?, rest[4..6].parse().ok()?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * i64::from(hours * 3600 + minutes * 60)
+        }
+    };
+
+    let days = days_from_civil(year.into(), month, day);
+    let secs = days * DAY + i64::from(hour * 3600 + minute * 60 + second) - offset;
+    from_unix(secs, nanos)
+}
+
+const UNITS: [(&str, u64); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+fn format_duration(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".to_string();
+    }
+    let mut secs = duration.as_secs();
+    let mut nanos = u64::from(duration.subsec_nanos());
+    let mut parts = Vec::new();
+    for (unit, size) in UNITS {
+        // whole days and hours may not fit into nanoseconds
+        let count = match size >= 1_000_000_000 {
+            true => {
+                let size = size / 1_000_000_000;
+                let count = secs / size;
+                secs %= size;
+                count
+            }
+            false => {
+                let count = nanos / size;
+                nanos %= size;
+                count
+            }
+        };
+        if count > 0 {
+            parts.push(format!("{}{}", count, unit));
+        }
+    }
+    parts.join(" ")
+}
+
+// Whole numbers followed by a unit, like `1h 30m` or `2s500ms`. Besides the
+// units written by `format_duration`, longer names like `min` or `hours` are
+// understood.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let count: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let letters = rest
+            .char_indices()
+            .find(|(_, c)| !c.is_alphabetic())
+            .map_or(rest.len(), |(i, _)| i);
+        let unit = match &rest[..letters] {
+            "d" | "day" | "days" => "d",
+            "h" | "hr" | "hrs" | "hour" | "hours" => "h",
+            "m" | "min" | "mins" | "minute" | "minutes" => "m",
+            "s" | "sec" | "secs" | "second" | "seconds" => "s",
+            "ms" | "msec" | "millis" => "ms",
+            "us" | "µs" | "usec" | "micros" => "us",
+            "ns" | "nsec" | "nanos" => "ns",
+            _ => return None,
+        };
+        let size = UNITS.iter().find(|(name, _)| *name == unit)?.1;
+        let part = match size >= 1_000_000_000 {
+            true => Duration::from_secs(count.checked_mul(size / 1_000_000_000)?),
+            false => Duration::from_nanos(count.checked_mul(size)?),
+        };
+        total = total.checked_add(part)?;
+        rest = rest[letters..].trim_start();
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Times {
+        #[serde(with = "super::system_time")]
+        at: SystemTime,
+        #[serde(with = "super::duration")]
+        after: Duration,
+    }
+
+    #[test]
+    fn timestamps() {
+        let at = |secs, nanos| from_unix(secs, nanos).unwrap();
+        for (secs, nanos, text) in [
+            (0, 0, "1970-01-01T00:00:00Z"),
+            (951_782_400, 0, "2000-02-29T00:00:00Z"),
+            (1_700_000_000, 120_000_000, "2023-11-14T22:13:20.12Z"),
+            (-1, 999_999_999, "1969-12-31T23:59:59.999999999Z"),
+        ] {
+            assert_eq!(unix_time(at(secs, nanos)), (secs, nanos));
+            assert_eq!(format_rfc3339(secs, nanos), text);
+            assert_eq!(parse_rfc3339(text), Some(at(secs, nanos)));
+        }
+        assert_eq!(
+            parse_rfc3339("2023-11-15t00:13:20+02:00"),
+            Some(at(1_700_000_000, 0))
+        );
+        assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2023-11-14T22:13:20"), None);
+        assert_eq!(parse_rfc3339("2023-11-14T22:13:20.Z"), None);
+    }
+
+    #[test]
+    fn durations() {
+        let duration = Duration::new(90_061, 5_000_001);
+        assert_eq!(format_duration(duration), "1d 1h 1m 1s 5ms 1ns");
+        assert_eq!(parse_duration("1d 1h 1m 1s 5ms 1ns"), Some(duration));
+        assert_eq!(parse_duration("2min30sec"), Some(Duration::from_secs(150)));
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(parse_duration("1.5h"), None);
+        assert_eq!(parse_duration("5 parsecs"), None);
+    }
+
+    #[test]
+    fn compact() {
+        let times = Times {
+            at: from_unix(-2, 500_000_000).unwrap(),
+            after: Duration::from_millis(1500),
+        };
+        let mut ser = crate::Serializer::new().human_readable(false);
+        ser.serialize_document(&times).unwrap();
+        let params = ser.into_params();
+        assert_eq!(params, ["[", "--at", "-1.5", "--after", "1.5", "]"]);
+        let back: Times = crate::de::from_params(params.into_iter()).unwrap();
+        assert_eq!(back, times);
+
+        let args = [
+            "[",
+            "--at",
+            "--",
+            "1970-01-01T00:00:01Z",
+            "--after",
+            "-1",
+            "]",
+        ];
+        assert!(crate::from_iter::<Times, _>(args.into_iter()).is_err());
+    }
+}
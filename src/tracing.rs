@@ -0,0 +1,141 @@
+// A tracing-subscriber layer writing events as SHON records, enabled by the
+// `tracing` feature.
+
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use tracing_core::field::{Field, Visit};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::value::{Map, Number, Value};
+
+/// Writes every event as a single line SHON object, with the level and the
+/// target of the event followed by its fields.
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// tracing_subscriber::registry().with(ShonLayer::stderr()).init();
+/// tracing::info!(port = 80, "listening");
+/// // [ --level INFO --target server --message listening --port 80 ]
+/// ```
+pub struct ShonLayer<W> {
+    writer: Mutex<W>,
+}
+
+impl ShonLayer<io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W> ShonLayer<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        ShonLayer {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<S, W> Layer<S> for ShonLayer<W>
+where
+    S: Subscriber,
+    W: Write + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut record = Record(Map::new());
+        record.insert("level", Value::String(meta.level().to_string()));
+        record.insert("target", Value::String(meta.target().to_string()));
+        event.record(&mut record);
+
+        let Ok(line) = crate::to_string(&Value::Object(record.0)) else {
+            return;
+        };
+        if let Ok(mut writer) = self.writer.lock() {
+            // logging must not take the application down, so write errors
+            // are dropped like the fmt layer does
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+}
+
+struct Record(Map);
+
+impl Record {
+    fn insert(&mut self, name: &str, value: Value) {
+        self.0.insert(name.to_string(), value);
+    }
+}
+
+impl Visit for Record {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        match Number::from_f64(value) {
+            Some(n) => self.insert(field.name(), Value::Number(n)),
+            None => self.insert(field.name(), Value::String(value.to_string())),
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field.name(), Value::Number(value.into()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field.name(), Value::Number(value.into()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field.name(), Value::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field.name(), Value::String(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field.name(), Value::String(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shon_layer() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(ShonLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "server", port = 80, tls = false, "listening on {}", "all");
+            tracing::warn!(target: "server", path = ?"-x", ratio = 0.5);
+        });
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output,
+            "[ --level INFO --target server --message 'listening on all' --port 80 --tls -f ]\n\
+             [ --level WARN --target server --path '\"-x\"' --ratio 0.5 ]\n"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Lets a type pick its own SHON settings through `ShonConfig`, for
+//! libraries whose types are (de)serialized at call sites they don't
+//! control.
+//!
+//! The derived impls are turned into inherent functions with
+//! `#[serde(remote = "Self")]`, and the trait impls hand them to
+//! `serialize` and `deserialize` here, which pass the config along to the
+//! SHON (de)serializer. Other formats use the derived impls as they are.
+//!
+//! ```
+//! use serde::{Deserialize, Deserializer, Serialize, Serializer};
+//! use serde_shon::{KeyStyle, ShonConfig, TypeConfig};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! #[serde(remote = "Self")]
+//! struct Copy {
+//!     src: String,
+//!     dry_run: bool,
+//! }
+//!
+//! impl ShonConfig for Copy {
+//!     fn config() -> TypeConfig {
+//!         TypeConfig::new()
+//!             .key_style(KeyStyle::Kebab)
+//!             .flags(true)
+//!             .positional_fields(1)
+//!     }
+//! }
+//!
+//! impl Serialize for Copy {
+//!     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+//!         serde_shon::type_config::serialize(self, serializer, Self::serialize)
+//!     }
+//! }
+//!
+//! impl<'de> Deserialize<'de> for Copy {
+//!     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+//!         serde_shon::type_config::deserialize(deserializer, Self::deserialize)
+//!     }
+//! }
+//!
+//! let copy = Copy { src: "a".to_string(), dry_run: true };
+//! let args = ["[", "a", "--dry-run", "]"];
+//! assert_eq!(serde_shon::to_raw_params(&copy).unwrap(), args);
+//! assert_eq!(serde_shon::from_iter::<Copy, _>(args.into_iter()).unwrap(), copy);
+//! ```
+
+use std::cell::Cell;
+
+use serde::{Deserializer, Serializer};
+
+/// Implemented by types that come with their own SHON settings. See the
+/// module docs for how to hook it up.
+pub trait ShonConfig {
+    fn config() -> TypeConfig;
+}
+
+/// How a struct is written as params and read back. The settings apply to
+/// the fields of the struct itself, not to the structs nested in it, which
+/// have their own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TypeConfig {
+    pub(crate) key_style: KeyStyle,
+    pub(crate) flags: bool,
+    pub(crate) positional: usize,
+}
+
+impl TypeConfig {
+    /// The settings of structs without a config.
+    pub fn new() -> Self {
+        TypeConfig::default()
+    }
+
+    /// How field names are turned into keys.
+    pub fn key_style(mut self, style: KeyStyle) -> Self {
+        self.key_style = style;
+        self
+    }
+
+    /// Writes `true` bools as just their key, and reads a bare key into a
+    /// plain bool as `true`, like fields with `#[shon(flag)]`. Missing
+    /// bools still need `#[serde(default)]`.
+    pub fn flags(mut self, enabled: bool) -> Self {
+        self.flags = enabled;
+        self
+    }
+
+    /// Writes the first `count` fields as bare values before the keys, and
+    /// reads them back from there, like fields with `#[shon(positional)]`.
+    pub fn positional_fields(mut self, count: usize) -> Self {
+        self.positional = count;
+        self
+    }
+}
+
+/// How the keys of struct fields are spelled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyStyle {
+    /// Keys are the field names as serde sees them.
+    #[default]
+    Field,
+    /// Underscores in field names become dashes, so `dry_run` is written as
+    /// `--dry-run`. Both spellings are read.
+    Kebab,
+}
+
+impl KeyStyle {
+    pub(crate) fn key(self, field: &str) -> String {
+        match self {
+            KeyStyle::Field => field.to_string(),
+            KeyStyle::Kebab => field.replace('_', "-"),
+        }
+    }
+}
+
+thread_local! {
+    // the config of the struct that is about to be (de)serialized
+    static PENDING: Cell<Option<TypeConfig>> = const { Cell::new(None) };
+}
+
+// Takes the config handed over for the struct being started, if any.
+pub(crate) fn take() -> TypeConfig {
+    PENDING.with(Cell::take).unwrap_or_default()
+}
+
+fn with_config<T, R>(f: impl FnOnce() -> R) -> R
+where
+    T: ShonConfig + ?Sized,
+{
+    PENDING.with(|pending| pending.set(Some(T::config())));
+    let result = f();
+    // left over when the format wasn't SHON
+    PENDING.with(|pending| pending.set(None));
+    result
+}
+
+/// Serializes the value with the given function, usually the one derived
+/// with `#[serde(remote = "Self")]`, under the config of its type.
+pub fn serialize<T, S, F>(value: &T, serializer: S, serialize: F) -> Result<S::Ok, S::Error>
+where
+    T: ShonConfig + ?Sized,
+    S: Serializer,
+    F: FnOnce(&T, S) -> Result<S::Ok, S::Error>,
+{
+    with_config::<T, _>(|| serialize(value, serializer))
+}
+
+/// Deserializes the value with the given function, usually the one derived
+/// with `#[serde(remote = "Self")]`, under the config of its type.
+pub fn deserialize<'de, T, D, F>(deserializer: D, deserialize: F) -> Result<T, D::Error>
+where
+    T: ShonConfig,
+    D: Deserializer<'de>,
+    F: FnOnce(D) -> Result<T, D::Error>,
+{
+    with_config::<T, _>(|| deserialize(deserializer))
+}
@@ -0,0 +1,106 @@
+// Checks that run once the params have been deserialized, for constraints
+// that the types alone don't express.
+
+use serde::Deserialize;
+
+use crate::{DeserializerOptions, Error, Result};
+
+/// Range checks and cross-field constraints, run by the `_validated` entry
+/// points right after deserialization.
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_shon::Validate;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Range {
+///     min: u32,
+///     max: u32,
+/// }
+///
+/// impl Validate for Range {
+///     fn validate(&self) -> Result<(), String> {
+///         if self.min > self.max {
+///             return Err(format!("--min {} is above --max {}", self.min, self.max));
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let args = ["[", "--min", "5", "--max", "1", "]"];
+/// let err = serde_shon::from_iter_validated::<Range, _>(args.into_iter()).unwrap_err();
+/// assert_eq!(err.to_string(), "invalid arguments: --min 5 is above --max 1");
+/// ```
+pub trait Validate {
+    fn validate(&self) -> std::result::Result<(), String>;
+}
+
+/// Like `from_args`, with the result checked by its `Validate` impl.
+pub fn from_args_validated<'a, T, I>(iter: I) -> Result<T>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a> + Validate,
+{
+    validated(crate::from_args(iter)?)
+}
+
+/// Like `from_iter`, with the result checked by its `Validate` impl.
+pub fn from_iter_validated<'a, T, I>(iter: I) -> Result<T>
+where
+    I: Iterator<Item = &'static str>,
+    T: Deserialize<'a> + Validate,
+{
+    validated(crate::from_iter(iter)?)
+}
+
+/// Like `from_args_with`, with the result checked by its `Validate` impl.
+pub fn from_args_with_validated<'a, T, I>(iter: I, options: &DeserializerOptions) -> Result<T>
+where
+    I: Iterator<Item = String>,
+    T: Deserialize<'a> + Validate,
+{
+    validated(crate::from_args_with(iter, options)?)
+}
+
+fn validated<T>(value: T) -> Result<T>
+where
+    T: Validate,
+{
+    match value.validate() {
+        Ok(()) => Ok(value),
+        Err(msg) => Err(Error::Message(format!("invalid arguments: {}", msg))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Port(u16);
+
+    impl Validate for Port {
+        fn validate(&self) -> std::result::Result<(), String> {
+            match self.0 {
+                0 => Err("port 0 can't be listened on".to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(
+            from_iter_validated::<Port, _>(["80"].into_iter()).unwrap(),
+            Port(80)
+        );
+        let err = from_iter_validated::<Port, _>(["0"].into_iter()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid arguments: port 0 can't be listened on"
+        );
+        assert_eq!(err.exit_code(), 2);
+        let args = ["bin", "x"].map(String::from).into_iter();
+        assert!(from_args_validated::<Port, _>(args).is_err());
+    }
+}
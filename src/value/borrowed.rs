@@ -0,0 +1,246 @@
+use super::{Map, Number, Value};
+use crate::error::TRAILING_SHOWN;
+use crate::options::DEFAULT_MAX_DEPTH;
+use crate::parse::{self, classify, Scalar, Token};
+use crate::{Error, Result};
+
+/// A borrowed counterpart of `Value`, whose strings and keys point directly
+/// into the tokens it was parsed from instead of owning copies of them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(&'a str),
+    Array(Vec<ValueRef<'a>>),
+    Object(Vec<(&'a str, ValueRef<'a>)>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Parses a complete document from the given tokens, without the binary
+    /// name in front. Whitespace around tokens is ignored, just like with
+    /// `from_iter`, and so is the default `DeserializerOptions::max_depth`.
+    pub fn from_tokens<T>(tokens: &'a [T]) -> Result<ValueRef<'a>>
+    where
+        T: AsRef<str>,
+    {
+        // empty tokens are skipped like by `from_iter`, except after `--`
+        let mut escaped = false;
+        let mut parser = Parser {
+            tokens: tokens
+                .iter()
+                .map(|t| t.as_ref().trim())
+                .filter(|t| parse::keep(&mut escaped, t))
+                .collect(),
+            pos: 0,
+            depth: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Ok(ValueRef::Null);
+        }
+        let value = parser.value()?;
+        if let Some(rest) = parser
+            .tokens
+            .get(parser.pos..)
+            .filter(|rest| !rest.is_empty())
+        {
+            return Err(Error::TrailingTokens {
+                remaining: Some(rest.len()),
+                first: rest
+                    .iter()
+                    .take(TRAILING_SHOWN)
+                    .map(|t| t.to_string())
+                    .collect(),
+            });
+        }
+        Ok(value)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, ValueRef::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[ValueRef<'a>]> {
+        match self {
+            ValueRef::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Looks up a key of an object, returning `None` for any other value.
+    pub fn get(&self, key: &str) -> Option<&ValueRef<'a>> {
+        match self {
+            ValueRef::Object(o) => o.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => Value::Null,
+            ValueRef::Bool(b) => Value::Bool(b),
+            ValueRef::Number(n) => Value::Number(n),
+            ValueRef::String(s) => Value::String(s.to_string()),
+            ValueRef::Array(a) => Value::Array(a.into_iter().map(Value::from).collect()),
+            ValueRef::Object(o) => Value::Object(
+                o.into_iter()
+                    .map(|(k, v)| (k.to_string(), Value::from(v)))
+                    .collect::<Map>(),
+            ),
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+    // the number of arrays and objects the next value is nested in
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<&'a str> {
+        let token = self.peek()?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn peek(&self) -> Result<&'a str> {
+        self.tokens
+            .get(self.pos)
+            .copied()
+            .ok_or_else(parse::end_of_input)
+    }
+
+    fn value(&mut self) -> Result<ValueRef<'a>> {
+        let token = self.next()?;
+        Ok(match classify(token) {
+            Token::True => ValueRef::Bool(true),
+            Token::False => ValueRef::Bool(false),
+            Token::Null => ValueRef::Null,
+            Token::Escape => ValueRef::String(self.next()?),
+            Token::Open => {
+                if self.depth == DEFAULT_MAX_DEPTH {
+                    return Err(Error::DepthLimitExceeded);
+                }
+                self.depth += 1;
+                let value = if let Token::Key(_) = classify(self.peek()?) {
+                    let mut entries = Vec::new();
+                    while classify(self.peek()?) != Token::Close {
+                        let key = self.next()?;
+                        let Token::Key(key) = classify(key) else {
+                            return Err(Error::Message(format!("expected key, found `{}`", key)));
+                        };
+                        entries.push((key, self.value()?));
+                    }
+                    self.next()?;
+                    ValueRef::Object(entries)
+                } else {
+                    let mut elements = Vec::new();
+                    while classify(self.peek()?) != Token::Close {
+                        elements.push(self.value()?);
+                    }
+                    self.next()?;
+                    ValueRef::Array(elements)
+                };
+                self.depth -= 1;
+                value
+            }
+            Token::Close => return Err(Error::Message("unexpected `]`".to_string())),
+            Token::EmptyArray => ValueRef::Array(Vec::new()),
+            Token::EmptyObject => ValueRef::Object(Vec::new()),
+            Token::Key(key) => ValueRef::String(key),
+            Token::Word(word) => match parse::word(word) {
+                Scalar::U64(uint) => ValueRef::Number(uint.into()),
+                Scalar::I64(int) => ValueRef::Number(int.into()),
+                Scalar::F64(float) => match Number::from_f64(float) {
+                    Some(float) => ValueRef::Number(float),
+                    None => ValueRef::String(word),
+                },
+                Scalar::Str(s) => ValueRef::String(s),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trailing_tokens() {
+        let err = ValueRef::from_tokens(&["1", "2", "3"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2 unexpected params after the end of the document: `2` `3`"
+        );
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth| {
+            let mut tokens = vec!["["; depth];
+            tokens.extend(vec!["]"; depth]);
+            tokens
+        };
+        assert!(ValueRef::from_tokens(&nested(DEFAULT_MAX_DEPTH)).is_ok());
+        let err = ValueRef::from_tokens(&nested(100_000)).unwrap_err();
+        assert!(matches!(err, Error::DepthLimitExceeded));
+    }
+
+    #[test]
+    fn borrowed_tokens() {
+        let tokens = vec![
+            "[", "--name", "hi", "--ports", "[", "1", "-2", "0.5", "]", "--raw", "--", "10",
+            "--nested", "[--]", "--flag", "-t", "--none", "-n", "]",
+        ];
+        let value = ValueRef::from_tokens(&tokens).unwrap();
+        let name = value.get("name").unwrap().as_str().unwrap();
+        assert!(std::ptr::eq(name, tokens[2]));
+        assert_eq!(value.get("raw"), Some(&ValueRef::String("10")));
+        assert_eq!(value.get("nested"), Some(&ValueRef::Object(Vec::new())));
+        assert_eq!(value.get("flag"), Some(&ValueRef::Bool(true)));
+        assert!(value.get("none").unwrap().is_null());
+
+        let owned = crate::from_iter::<Value, _>(tokens.clone().into_iter()).unwrap();
+        assert_eq!(Value::from(value), owned);
+
+        let strings: Vec<String> = vec!["[".into(), "a".into(), "]".into()];
+        assert_eq!(
+            ValueRef::from_tokens(&strings).unwrap(),
+            ValueRef::Array(vec![ValueRef::String("a")])
+        );
+        assert!(ValueRef::from_tokens(&["[", "--a"]).is_err());
+        assert!(ValueRef::from_tokens(&["a", "b"]).is_err());
+    }
+
+    #[test]
+    fn empty_tokens() {
+        for tokens in [
+            &["[", "--", "", "a", "]"][..],
+            &["[", "", "a", "]"],
+            &["[", "--", " ", "]"],
+            &["[", "--key", "--", "", "]"],
+            &["--", ""],
+            &[""],
+            &[],
+        ] {
+            let value = ValueRef::from_tokens(tokens).map(Value::from);
+            let owned = crate::from_iter::<Value, _>(tokens.iter().copied());
+            assert_eq!(value.unwrap(), owned.unwrap(), "{:?}", tokens);
+        }
+        assert_eq!(
+            ValueRef::from_tokens(&["[", "--", "", "a", "]"]).unwrap(),
+            ValueRef::Array(vec![ValueRef::String(""), ValueRef::String("a")])
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Conversions for building values by hand.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{Map, Number, Value};
+
+macro_rules! from_integer {
+    ($($ty:ty => $via:ty,)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::Number(Number::from(n as $via))
+                }
+            }
+        )*
+    };
+}
+
+from_integer! {
+    i8 => i64,
+    i16 => i64,
+    i32 => i64,
+    i64 => i64,
+    isize => i64,
+    u8 => u64,
+    u16 => u64,
+    u32 => u64,
+    u64 => u64,
+    usize => u64,
+}
+
+impl From<f32> for Value {
+    fn from(f: f32) -> Self {
+        Value::from(f as f64)
+    }
+}
+
+/// Infinite and NaN floats have no SHON representation and become `Null`.
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Number::from_f64(f).map_or(Value::Null, Value::Number)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Null
+    }
+}
+
+impl<T> From<Option<T>> for Value
+where
+    T: Into<Value>,
+{
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T> From<Vec<T>> for Value
+where
+    T: Into<Value>,
+{
+    fn from(vec: Vec<T>) -> Self {
+        Value::Array(vec.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T> From<&[T]> for Value
+where
+    T: Clone + Into<Value>,
+{
+    fn from(slice: &[T]) -> Self {
+        Value::Array(slice.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl From<Map> for Value {
+    fn from(map: Map) -> Self {
+        Value::Object(map)
+    }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from(map: HashMap<K, V, S>) -> Self {
+        map.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        map.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
+    }
+}
+
+/// Collects an array.
+impl FromIterator<Value> for Value {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        Value::Array(iter.into_iter().collect())
+    }
+}
+
+/// Collects an object, where later keys replace earlier ones.
+impl FromIterator<(String, Value)> for Value {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        Value::Object(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Value::from(-3i8).as_i64(), Some(-3));
+        assert_eq!(Value::from(3usize).as_u64(), Some(3));
+        assert_eq!(Value::from(0.5f32).as_f64(), Some(0.5));
+        assert_eq!(Value::from(f64::NAN), Value::Null);
+        assert_eq!(Value::from(None::<bool>), Value::Null);
+        assert_eq!(Value::from(Some("a")), Value::String("a".to_string()));
+        assert_eq!(
+            Value::from(vec![1, 2]),
+            Value::Array(vec![Value::from(1), Value::from(2)])
+        );
+        assert_eq!(Value::from(&["a", "b"][..]), Value::from(vec!["a", "b"]));
+
+        let map = HashMap::from([("port", 80)]);
+        assert_eq!(Value::from(map).get("port"), Some(&Value::from(80)));
+        let map = BTreeMap::from([("b".to_string(), true), ("a".to_string(), false)]);
+        let value = Value::from(map);
+        let keys: Vec<_> = value.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["a", "b"]);
+    }
+
+    #[test]
+    fn collect() {
+        let array: Value = (1..=3).map(Value::from).collect();
+        assert_eq!(array.to_raw_params().unwrap(), ["[", "1", "2", "3", "]"]);
+        let object: Value = [("name", "shon"), ("name", "json")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), Value::from(v)))
+            .collect();
+        assert_eq!(
+            object.to_raw_params().unwrap(),
+            ["[", "--name", "json", "]"]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use super::Value;
+
+// With the `intern` feature, keys are shared between all objects of a thread
+// that use the same key, so many parsed documents with the same shape only
+// hold every key once.
+#[cfg(feature = "intern")]
+type Key = std::sync::Arc<str>;
+#[cfg(not(feature = "intern"))]
+type Key = String;
+
+#[cfg(feature = "intern")]
+fn intern(key: String) -> Key {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    thread_local! {
+        // never shrinks, which is fine as long as keys come from a schema
+        // rather than from the data itself
+        static KEYS: RefCell<HashSet<Key>> = RefCell::new(HashSet::new());
+    }
+    KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        if let Some(key) = keys.get(key.as_str()) {
+            return key.clone();
+        }
+        let key: Key = key.into();
+        keys.insert(key.clone());
+        key
+    })
+}
+
+#[cfg(not(feature = "intern"))]
+fn intern(key: String) -> Key {
+    key
+}
+
+#[cfg(feature = "intern")]
+fn into_string(key: Key) -> String {
+    key.to_string()
+}
+
+#[cfg(not(feature = "intern"))]
+fn into_string(key: Key) -> String {
+    key
+}
+
+// Interned keys are usually the very same string, which saves comparing them
+// byte by byte.
+fn same_key(k: &str, key: &str) -> bool {
+    std::ptr::eq(k, key) || k == key
+}
+
+/// The entries of a SHON object. Keys keep the order in which they were
+/// inserted, which is also the order they are serialized in.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(Key, Value)>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(k, _)| same_key(k, key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| same_key(k, key))
+            .map(|(_, v)| v)
+    }
+
+    /// Inserts the value under the key. An existing value for the key is
+    /// replaced in place and returned.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        match self.get_mut(&key) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.entries.push((intern(key), value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let pos = self.entries.iter().position(|(k, _)| same_key(k, key))?;
+        Some(self.entries.remove(pos).1)
+    }
+
+    /// Sorts the entries by key.
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().map(|(k, v)| (&**k, v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Value)> {
+        self.entries.iter_mut().map(|(k, v)| (&**k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| &**k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+// Objects are equal when they hold the same entries, regardless of order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl Eq for Map {}
+
+impl Map {
+    fn sorted(&self) -> Vec<(&str, &Value)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by_key(|(k, _)| *k);
+        entries
+    }
+}
+
+// Hashing and ordering look at the entries sorted by key, to stay consistent
+// with equality ignoring the insertion order.
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sorted().hash(state);
+    }
+}
+
+impl PartialOrd for Map {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Map {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sorted().cmp(&other.sorted())
+    }
+}
+
+impl FromIterator<(String, Value)> for Map {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let mut map = Map::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+type IntoEntry = fn((Key, Value)) -> (String, Value);
+
+impl IntoIterator for Map {
+    type Item = (String, Value);
+    type IntoIter = std::iter::Map<std::vec::IntoIter<(Key, Value)>, IntoEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries
+            .into_iter()
+            .map((|(k, v)| (into_string(k), v)) as IntoEntry)
+    }
+}
+
+#[cfg(all(test, feature = "intern"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interned_keys() {
+        let a = Map::from_iter([("name".to_string(), Value::Null)]);
+        let b = Map::from_iter([("name".to_string(), Value::Bool(true))]);
+        assert!(std::sync::Arc::ptr_eq(&a.entries[0].0, &b.entries[0].0));
+        assert_eq!(a.keys().collect::<Vec<_>>(), ["name"]);
+        assert_eq!(b.into_iter().next().unwrap().0, "name");
+    }
+}
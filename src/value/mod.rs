@@ -0,0 +1,486 @@
+// A dynamically typed representation of a SHON document.
+
+mod borrowed;
+mod de;
+mod from;
+mod map;
+mod number;
+mod ser;
+
+use serde::{Deserialize, Serialize};
+
+pub use borrowed::ValueRef;
+pub use map::Map;
+pub use number::Number;
+
+/// Any value that can be expressed in SHON.
+///
+/// Values are ordered by their kind first, in the order the variants are
+/// declared in, and then by their contents. See `Number` for how numbers
+/// compare.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<Value>),
+    Object(Map),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Like `as_i64`, but also parses strings holding an integer, like `"5"`.
+    pub fn coerce_i64(&self) -> Option<i64> {
+        match self {
+            Value::String(s) => s.trim().parse().ok(),
+            _ => self.as_i64(),
+        }
+    }
+
+    /// Like `as_f64`, but also parses strings holding a finite number, like
+    /// `"0.5"`.
+    pub fn coerce_f64(&self) -> Option<f64> {
+        match self {
+            Value::String(s) => s.trim().parse().ok().filter(|f: &f64| f.is_finite()),
+            _ => self.as_f64(),
+        }
+    }
+
+    /// Like `as_bool`, but also accepts the strings `"true"` and `"false"`.
+    pub fn coerce_bool(&self) -> Option<bool> {
+        match self {
+            Value::String(s) => s.trim().parse().ok(),
+            _ => self.as_bool(),
+        }
+    }
+
+    /// Looks up a key of an object, returning `None` for any other value.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object()?.get(key)
+    }
+
+    /// Looks up a nested value by a JSON pointer like `/server/ports/0`.
+    /// Within a segment, `~1` stands for `/` and `~0` for `~`.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        pointer_segments(pointer)?
+            .into_iter()
+            .try_fold(self, |value, segment| match value {
+                Value::Object(o) => o.get(&segment),
+                Value::Array(a) => a.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Like `pointer`, but returns a mutable reference.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        pointer_segments(pointer)?
+            .into_iter()
+            .try_fold(self, |value, segment| match value {
+                Value::Object(o) => o.get_mut(&segment),
+                Value::Array(a) => a.get_mut(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Inserts the value at the given JSON pointer, creating any missing
+    /// objects along the way (`null`s are replaced by objects too). Returns
+    /// the value that was previously stored there, if any.
+    pub fn insert_at_path(&mut self, pointer: &str, value: Value) -> crate::Result<Option<Value>> {
+        let invalid = || crate::Error::Message(format!("invalid path `{}`", pointer));
+        let mut segments = pointer_segments(pointer).ok_or_else(invalid)?;
+        let last = match segments.pop() {
+            Some(last) => last,
+            None => return Ok(Some(std::mem::replace(self, value))),
+        };
+        let mut current = self;
+        for segment in segments {
+            if current.is_null() {
+                *current = Value::Object(Map::new());
+            }
+            current = match current {
+                Value::Object(o) => {
+                    if !o.contains_key(&segment) {
+                        o.insert(segment.clone(), Value::Object(Map::new()));
+                    }
+                    o.get_mut(&segment).unwrap()
+                }
+                Value::Array(a) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| a.get_mut(i))
+                    .ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            };
+        }
+        if current.is_null() {
+            *current = Value::Object(Map::new());
+        }
+        match current {
+            Value::Object(o) => Ok(o.insert(last, value)),
+            Value::Array(a) => {
+                let slot = last
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| a.get_mut(i))
+                    .ok_or_else(invalid)?;
+                Ok(Some(std::mem::replace(slot, value)))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Sorts the keys of this and all nested objects.
+    pub fn sort_keys_recursively(&mut self) {
+        match self {
+            Value::Array(a) => a.iter_mut().for_each(Value::sort_keys_recursively),
+            Value::Object(o) => {
+                o.sort_keys();
+                o.iter_mut().for_each(|(_, v)| v.sort_keys_recursively());
+            }
+            _ => {}
+        }
+    }
+
+    /// Brings the value into a canonical form: keys are sorted recursively
+    /// and integral floats are turned into integers, so that equal documents
+    /// also serialize identically.
+    pub fn canonicalize(&mut self) {
+        match self {
+            Value::Number(n) => *n = n.canonical(),
+            Value::Array(a) => a.iter_mut().for_each(Value::canonicalize),
+            Value::Object(o) => {
+                o.sort_keys();
+                o.iter_mut().for_each(|(_, v)| v.canonicalize());
+            }
+            _ => {}
+        }
+    }
+
+    /// The raw params for this value, as `to_raw_params` would produce them.
+    pub fn to_raw_params(&self) -> crate::Result<Vec<String>> {
+        crate::to_raw_params(self)
+    }
+
+    /// The raw params for this value, written by the given serializer with
+    /// the options it was built with.
+    ///
+    /// ```
+    /// use serde_shon::{Serializer, Value};
+    ///
+    /// let value: Value = serde_shon::from_iter(["[", "--a", "[", "1", "]", "]"].into_iter()).unwrap();
+    /// let params = value.to_params_with(Serializer::new().nest_at_depth(1)).unwrap();
+    /// assert_eq!(params, ["[", "--a", "[ 1 ]", "]"]);
+    /// ```
+    pub fn to_params_with(&self, mut serializer: crate::Serializer) -> crate::Result<Vec<String>> {
+        serializer.serialize_document(self)?;
+        Ok(serializer.into_params())
+    }
+
+    /// The shell-escaped form of this value, as `to_string` would produce it.
+    #[cfg(feature = "shell")]
+    pub fn to_shell_string(&self) -> crate::Result<String> {
+        crate::to_string(self)
+    }
+}
+
+fn pointer_segments(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let segments = pointer.strip_prefix('/')?.split('/');
+    Some(
+        segments
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Converts any serializable value into a `Value`, with the same result as
+/// parsing its serialized params.
+pub fn to_value<T>(value: &T) -> crate::Result<Value>
+where
+    T: Serialize,
+{
+    crate::de::from_params(crate::to_raw_params(value)?.into_iter())
+}
+
+/// Deserializes a `T` from the given `Value`.
+pub fn from_value<'a, T>(value: Value) -> crate::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    crate::de::from_params(value.to_raw_params()?.into_iter())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Test {
+        name: String,
+        port: u16,
+        ratio: f64,
+        offset: i32,
+        tags: Vec<String>,
+        extra: HashMap<String, bool>,
+        nothing: Option<u8>,
+    }
+
+    fn test() -> Test {
+        Test {
+            name: "-t".to_string(),
+            port: 8080,
+            ratio: 0.5,
+            offset: -3,
+            tags: vec!["a b".to_string(), "--c".to_string()],
+            extra: HashMap::from([("x".to_string(), true)]),
+            nothing: None,
+        }
+    }
+
+    #[test]
+    fn value_roundtrip() {
+        let value = to_value(&test()).unwrap();
+        assert_eq!(value.get("name").and_then(Value::as_str), Some("-t"));
+        assert_eq!(value.get("port").and_then(Value::as_u64), Some(8080));
+        assert_eq!(value.get("ratio").and_then(Value::as_f64), Some(0.5));
+        assert_eq!(value.get("offset").and_then(Value::as_i64), Some(-3));
+        assert!(value.get("nothing").unwrap().is_null());
+        assert_eq!(from_value::<Test>(value).unwrap(), test());
+    }
+
+    #[test]
+    fn canonical_order() {
+        let float = |f| Value::Number(Number::from_f64(f).unwrap());
+        let object = |entries: Vec<(&str, Value)>| {
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect(),
+            )
+        };
+        let mut value = object(vec![
+            (
+                "b",
+                Value::Array(vec![object(vec![("z", float(1.0)), ("y", float(-2.0))])]),
+            ),
+            ("a", object(vec![("x", float(1.5))])),
+        ]);
+
+        let mut sorted = value.clone();
+        sorted.sort_keys_recursively();
+        assert_eq!(sorted, value);
+        let keys: Vec<_> = sorted.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["a", "b"]);
+        let nested = &sorted.get("b").unwrap().as_array().unwrap()[0];
+        let keys: Vec<_> = nested.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["y", "z"]);
+        assert_eq!(nested.get("z").unwrap().as_u64(), None);
+
+        value.canonicalize();
+        assert_eq!(
+            value.to_raw_params().unwrap(),
+            sorted.to_raw_params().unwrap()
+        );
+        let nested = &value.get("b").unwrap().as_array().unwrap()[0];
+        assert_eq!(nested.get("z").unwrap().as_u64(), Some(1));
+        assert_eq!(nested.get("y").unwrap().as_i64(), Some(-2));
+        assert_eq!(
+            value.get("a").unwrap().get("x").unwrap().as_f64(),
+            Some(1.5)
+        );
+    }
+
+    #[test]
+    fn coercions() {
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(string("5").coerce_i64(), Some(5));
+        assert_eq!(string(" -5 ").coerce_i64(), Some(-5));
+        assert_eq!(string("5.5").coerce_i64(), None);
+        assert_eq!(Value::Number(7u64.into()).coerce_i64(), Some(7));
+        assert_eq!(string("5.5").coerce_f64(), Some(5.5));
+        assert_eq!(string("inf").coerce_f64(), None);
+        assert_eq!(Value::Number(2u64.into()).coerce_f64(), Some(2.0));
+        assert_eq!(string("true").coerce_bool(), Some(true));
+        assert_eq!(string("false").coerce_bool(), Some(false));
+        assert_eq!(string("yes").coerce_bool(), None);
+        assert_eq!(Value::Bool(true).coerce_bool(), Some(true));
+        assert_eq!(Value::Null.coerce_bool(), None);
+    }
+
+    #[test]
+    fn pointers() {
+        let mut value = to_value(&test()).unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/tags/1").and_then(Value::as_str),
+            Some("--c")
+        );
+        assert_eq!(
+            value.pointer("/extra/x").and_then(Value::as_bool),
+            Some(true)
+        );
+        assert_eq!(value.pointer("/tags/2"), None);
+        assert_eq!(value.pointer("tags"), None);
+
+        *value.pointer_mut("/tags/0").unwrap() = Value::String("d".to_string());
+        assert_eq!(value.pointer("/tags/0").and_then(Value::as_str), Some("d"));
+
+        let previous = value
+            .insert_at_path("/server/tls/cert", Value::String("a/b".to_string()))
+            .unwrap();
+        assert_eq!(previous, None);
+        assert_eq!(
+            value.pointer("/server/tls/cert").and_then(Value::as_str),
+            Some("a/b")
+        );
+        value
+            .insert_at_path("/nothing/a~1b", Value::Bool(true))
+            .unwrap();
+        assert_eq!(
+            value.pointer("/nothing/a~1b").and_then(Value::as_bool),
+            Some(true)
+        );
+        let previous = value.insert_at_path("/tags/1", Value::Bool(false)).unwrap();
+        assert_eq!(previous, Some(Value::String("--c".to_string())));
+        assert!(value.insert_at_path("/port/x", Value::Null).is_err());
+        assert!(value.insert_at_path("/tags/5", Value::Null).is_err());
+    }
+
+    #[test]
+    fn hash_and_order() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let int = |i: i64| Value::Number(i.into());
+        let float = |f| Value::Number(Number::from_f64(f).unwrap());
+        let a: Value = crate::from_iter(["[", "--a", "1", "--b", "2", "]"].into_iter()).unwrap();
+        let b: Value = crate::from_iter(["[", "--b", "2", "--a", "1", "]"].into_iter()).unwrap();
+        assert_eq!(a, b);
+        let set: HashSet<Value> = [a.clone(), b.clone(), int(1)].into_iter().collect();
+        assert_eq!(set.len(), 2);
+
+        assert_ne!(int(1), float(1.0));
+        assert_ne!(float(0.0), float(-0.0));
+        let sorted: Vec<Value> = [
+            a,
+            Value::String("x".to_string()),
+            float(1.0),
+            float(-0.0),
+            int(1),
+            int(-1),
+            float(0.5),
+            Value::Bool(true),
+            Value::Null,
+            Value::Array(vec![]),
+        ]
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+        assert_eq!(
+            sorted,
+            vec![
+                Value::Null,
+                Value::Bool(true),
+                int(-1),
+                float(-0.0),
+                float(0.5),
+                int(1),
+                float(1.0),
+                Value::String("x".to_string()),
+                Value::Array(vec![]),
+                b,
+            ]
+        );
+    }
+
+    #[test]
+    fn value_emitters() {
+        let value = Value::Object(Map::from_iter([
+            ("a".to_string(), Value::String("x y".to_string())),
+            (
+                "b".to_string(),
+                Value::Array(vec![Value::Number(1u64.into()), Value::Bool(false)]),
+            ),
+            ("c".to_string(), Value::Object(Map::new())),
+        ]));
+        assert_eq!(
+            value.to_raw_params().unwrap(),
+            vec!["[", "--a", "x y", "--b", "[", "1", "-f", "]", "--c", "[--]", "]"]
+        );
+        #[cfg(feature = "shell")]
+        assert_eq!(
+            value.to_shell_string().unwrap(),
+            "[ --a 'x y' --b [ 1 -f ] --c [--] ]"
+        );
+        let serializer = crate::Serializer::new();
+        assert_eq!(
+            value.to_params_with(serializer).unwrap(),
+            value.to_raw_params().unwrap()
+        );
+        let serializer =
+            crate::Serializer::new().transform(|_, value| value.make_ascii_uppercase());
+        assert_eq!(
+            value.to_params_with(serializer).unwrap(),
+            vec!["[", "--a", "X Y", "--b", "[", "1", "-f", "]", "--c", "[--]", "]"]
+        );
+    }
+}
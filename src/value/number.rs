@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+
+/// A SHON number, which keeps integers exact instead of converting them into
+/// floats.
+///
+/// Numbers are totally ordered by their numeric value. Since a float can
+/// never be NaN, floats compare like `f64::total_cmp`, which means `-0.0` and
+/// `0.0` are distinct values. Integers and floats never equal each other;
+/// for the same numeric value the integer orders first.
+#[derive(Debug, Clone, Copy)]
+pub struct Number(N);
+
+#[derive(Debug, Clone, Copy)]
+enum N {
+    PosInt(u64),
+    // always negative
+    NegInt(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Returns `None` for infinite or NaN floats, which have no SHON
+    /// representation.
+    pub fn from_f64(f: f64) -> Option<Number> {
+        f.is_finite().then_some(Number(N::Float(f)))
+    }
+
+    pub fn is_u64(&self) -> bool {
+        matches!(self.0, N::PosInt(_))
+    }
+
+    pub fn is_i64(&self) -> bool {
+        match self.0 {
+            N::PosInt(u) => i64::try_from(u).is_ok(),
+            N::NegInt(_) => true,
+            N::Float(_) => false,
+        }
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self.0, N::Float(_))
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.0 {
+            N::PosInt(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.0 {
+            N::PosInt(u) => i64::try_from(u).ok(),
+            N::NegInt(i) => Some(i),
+            N::Float(_) => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self.0 {
+            N::PosInt(u) => u as f64,
+            N::NegInt(i) => i as f64,
+            N::Float(f) => f,
+        }
+    }
+}
+
+impl Number {
+    // Integral floats become integers, so that every number has exactly one
+    // representation.
+    pub(super) fn canonical(self) -> Number {
+        match self.0 {
+            N::Float(f) if f.fract() == 0.0 && f >= 0.0 && f < u64::MAX as f64 => {
+                Number(N::PosInt(f as u64))
+            }
+            N::Float(f) if f.fract() == 0.0 && f < 0.0 && f >= i64::MIN as f64 => {
+                Number::from(f as i64)
+            }
+            _ => self,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0, other.0) {
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
+            (N::NegInt(a), N::NegInt(b)) => a.cmp(&b),
+            (N::NegInt(_), N::PosInt(_)) => Ordering::Less,
+            (N::PosInt(_), N::NegInt(_)) => Ordering::Greater,
+            _ => self
+                .as_f64()
+                .total_cmp(&other.as_f64())
+                .then(self.is_f64().cmp(&other.is_f64())),
+        }
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.0 {
+            N::PosInt(u) => (0u8, u).hash(state),
+            N::NegInt(i) => (1u8, i).hash(state),
+            N::Float(f) => (2u8, f.to_bits()).hash(state),
+        }
+    }
+}
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Self {
+        Number(N::PosInt(u))
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Self {
+        match u64::try_from(i) {
+            Ok(u) => Number(N::PosInt(u)),
+            Err(_) => Number(N::NegInt(i)),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            N::PosInt(u) => Display::fmt(&u, f),
+            N::NegInt(i) => Display::fmt(&i, f),
+            N::Float(x) => Display::fmt(&x, f),
+        }
+    }
+}
+
+impl serde::Serialize for Number {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.0 {
+            N::PosInt(u) => serializer.serialize_u64(u),
+            N::NegInt(i) => serializer.serialize_i64(i),
+            N::Float(f) => serializer.serialize_f64(f),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// POSIX shell style word splitting, the inverse of the escaping done by
+// `to_string`.
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::{Error, Result};
+
+/// Splits the input into words the way a POSIX shell would, honoring single
+/// quotes, double quotes and backslash escapes. No expansions are performed.
+pub(crate) fn split(input: &str) -> Result<Vec<String>> {
+    split_words(input, false).map_err(Unterminated::into_error)
+}
+
+/// Like `split`, for documents stored in files. Lines starting with `#` are
+/// comments and skipped.
+pub(crate) fn split_document(input: &str) -> Result<Vec<String>> {
+    split_words(input, true).map_err(Unterminated::into_error)
+}
+
+/// Like `split_document`, for input read line by line. Words are given as
+/// soon as the lines holding them are read, with quotes spanning lines
+/// held back until they end.
+pub(crate) fn split_lines<R: BufRead>(reader: R) -> SplitLines<R> {
+    SplitLines {
+        reader,
+        lines: String::new(),
+        words: VecDeque::new(),
+        done: false,
+    }
+}
+
+pub(crate) struct SplitLines<R> {
+    reader: R,
+    // lines read that don't end a word yet
+    lines: String,
+    words: VecDeque<String>,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for SplitLines<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Result<String>> {
+        loop {
+            if let Some(word) = self.words.pop_front() {
+                return Some(Ok(word));
+            }
+            if self.done {
+                return None;
+            }
+            match self.reader.read_line(&mut self.lines) {
+                Ok(0) => self.done = true,
+                Ok(_) if self.lines.ends_with("\\\n") => continue,
+                Ok(_) => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+            }
+            match split_words(&self.lines, true) {
+                Ok(words) => {
+                    self.words.extend(words);
+                    self.lines.clear();
+                }
+                Err(err) if self.done => return Some(Err(err.into_error())),
+                // the rest of the word is still to come
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// Like `split`, but input that ends within quotes or right after a backslash
+/// gives `None`, as the rest of it may still follow on the next line.
+#[cfg(feature = "shell")]
+pub(crate) fn split_partial(input: &str) -> Option<Vec<String>> {
+    split_words(input, false).ok()
+}
+
+/// Joins params into a single string that `split` turns back into them,
+/// quoting only where needed.
+pub(crate) fn join(params: &[String]) -> String {
+    let quoted: Vec<String> = params
+        .iter()
+        .map(|param| {
+            let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%[]".contains(c);
+            if !param.is_empty() && param.chars().all(safe) {
+                param.clone()
+            } else {
+                format!("'{}'", param.replace('\'', "'\\''"))
+            }
+        })
+        .collect();
+    quoted.join(" ")
+}
+
+/// Splits a command line the way `CommandLineToArgvW` does on Windows. The
+/// first word is the program name, which ends at the first whitespace or is
+/// quoted as a whole. After it, quotes group words, backslashes are only
+/// special right before a quote, and `""` within quotes is a literal quote.
+pub(crate) fn split_windows(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    let mut program = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+        program.extend(chars.by_ref().take_while(|&c| c != '"'));
+    }
+    while let Some(c) = chars.next_if(|&c| c != ' ' && c != '\t') {
+        program.push(c);
+    }
+    words.push(program);
+
+    loop {
+        while chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+        if chars.peek().is_none() {
+            return words;
+        }
+        let mut word = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match c {
+                ' ' | '\t' if !quoted => break,
+                '\\' => {
+                    let mut backslashes = 1;
+                    while chars.next_if_eq(&'\\').is_some() {
+                        backslashes += 1;
+                    }
+                    if chars.peek() == Some(&'"') {
+                        // pairs of backslashes before a quote are escaped
+                        // backslashes, an odd one out escapes the quote
+                        word.extend(std::iter::repeat_n('\\', backslashes / 2));
+                        if backslashes % 2 == 1 {
+                            chars.next();
+                            word.push('"');
+                        }
+                    } else {
+                        word.extend(std::iter::repeat_n('\\', backslashes));
+                    }
+                }
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    word.push('"');
+                }
+                '"' => quoted = !quoted,
+                c => word.push(c),
+            }
+        }
+        words.push(word);
+    }
+}
+
+// why the input ended before the last word was complete
+enum Unterminated {
+    Single,
+    Double,
+    Backslash,
+}
+
+impl Unterminated {
+    fn into_error(self) -> Error {
+        Error::Message(match self {
+            Unterminated::Single => "unterminated single quote".to_string(),
+            Unterminated::Double => "unterminated double quote".to_string(),
+            Unterminated::Backslash => "unexpected end of input after backslash".to_string(),
+        })
+    }
+}
+
+fn split_words(input: &str, comments: bool) -> std::result::Result<Vec<String>, Unterminated> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // distinguishes an empty quoted word (`''`) from no word at all
+    let mut in_word = false;
+    // only whitespace came before on the current line
+    let mut line_start = true;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '#' && comments && line_start {
+            chars.by_ref().find(|&c| c == '\n');
+            continue;
+        }
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(Unterminated::Single),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('\\' | '"' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(Unterminated::Double),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(Unterminated::Double),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // line continuation
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => return Err(Unterminated::Backslash),
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_words() {
+        assert_eq!(
+            split("[ --a 'hello world' b\\ c \"d \\\"e\\\"\" ]\n").unwrap(),
+            vec!["[", "--a", "hello world", "b c", "d \"e\"", "]"]
+        );
+        assert_eq!(split("'it'\\''s' ''").unwrap(), vec!["it's", ""]);
+        assert_eq!(split("a \\\n b").unwrap(), vec!["a", "b"]);
+        assert!(split("'open").is_err());
+        assert!(split("\"open").is_err());
+    }
+
+    #[test]
+    fn join_words() {
+        let params = ["[", "--a", "b c", "", "it's", "\\$x", "]"].map(String::from);
+        let joined = join(&params);
+        assert_eq!(joined, "[ --a 'b c' '' 'it'\\''s' '\\$x' ]");
+        assert_eq!(split(&joined).unwrap(), params);
+    }
+
+    #[test]
+    fn split_documents() {
+        let doc = "# settings\n[\n  --a 1 # not a comment\n\n  # --b 2\n  --c '\n# kept'\n]\n";
+        assert_eq!(
+            split_document(doc).unwrap(),
+            vec!["[", "--a", "1", "#", "not", "a", "comment", "--c", "\n# kept", "]"]
+        );
+        assert_eq!(split("# a").unwrap(), vec!["#", "a"]);
+    }
+
+    #[test]
+    fn split_line_by_line() {
+        let doc = "# settings\n[ --a 'b\n# c' \\\n d\n  # e\n]";
+        let words: Vec<String> = split_lines(doc.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(words, split_document(doc).unwrap());
+        assert_eq!(words, vec!["[", "--a", "b\n# c", "d", "]"]);
+        let mut words = split_lines("a\n'b\n".as_bytes());
+        assert_eq!(words.next().unwrap().unwrap(), "a");
+        assert!(words.next().unwrap().is_err());
+        assert!(words.next().is_none());
+    }
+
+    #[test]
+    fn split_windows_command_line() {
+        assert_eq!(
+            split_windows(r#""C:\Program Files\app.exe" [ --path C:\dir\ "a b" ]"#),
+            vec![
+                r"C:\Program Files\app.exe",
+                "[",
+                "--path",
+                r"C:\dir\",
+                "a b",
+                "]"
+            ]
+        );
+        assert_eq!(
+            split_windows(r#"app a\\"b c" d\"e \\\"f "g""h" """#),
+            vec!["app", r#"a\b c"#, r#"d"e"#, r#"\"f"#, r#"g"h"#, ""]
+        );
+        assert_eq!(split_windows("app\targ  "), vec!["app", "arg"]);
+        assert_eq!(split_windows(""), vec![""]);
+    }
+
+    #[test]
+    #[cfg(feature = "shell")]
+    fn split_partial_input() {
+        assert!(split_partial("a 'b").is_none());
+        assert!(split_partial("a \\").is_none());
+        assert_eq!(
+            split_partial("a 'b\nc'"),
+            Some(vec!["a".to_string(), "b\nc".to_string()])
+        );
+    }
+}
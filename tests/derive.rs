@@ -0,0 +1,364 @@
+#![cfg(feature = "derive")]
+
+use serde::{Deserialize, Serialize};
+use serde_shon::{from_iter, shon, to_raw_params, to_string};
+
+#[shon]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Copy {
+    #[shon(positional)]
+    src: String,
+    #[shon(positional)]
+    dst: String,
+    #[shon(flag)]
+    force: bool,
+    #[shon(flag)]
+    verbose: bool,
+    #[shon(alias = "-o")]
+    owner: Option<String>,
+    #[shon(secret)]
+    token: String,
+}
+
+fn parse(args: &[&'static str]) -> serde_shon::Result<Copy> {
+    from_iter(args.iter().copied())
+}
+
+#[test]
+fn positional_and_flags() {
+    let copy = parse(&[
+        "[", "a", "b", "--force", "-o", "me", "--token", "t0k3n", "]",
+    ])
+    .unwrap();
+    assert_eq!(
+        copy,
+        Copy {
+            src: "a".to_string(),
+            dst: "b".to_string(),
+            force: true,
+            verbose: false,
+            owner: Some("me".to_string()),
+            token: "t0k3n".to_string(),
+        }
+    );
+
+    let copy = parse(&[
+        "[",
+        "a",
+        "b",
+        "--token",
+        "x",
+        "--force",
+        "-f",
+        "--verbose",
+        "]",
+    ])
+    .unwrap();
+    assert!(!copy.force);
+    assert!(copy.verbose);
+    assert_eq!(copy.owner, None);
+
+    // aliases and positional fields are known keys
+    let options = serde_shon::DeserializerOptions::new().deny_unknown_keys(true);
+    let args = ["[", "a", "b", "-o", "me", "--token", "x", "]"].into_iter();
+    let copy: Copy = serde_shon::from_iter_with(args, &options).unwrap();
+    assert_eq!(copy.owner.as_deref(), Some("me"));
+}
+
+#[test]
+fn secrets_and_roundtrip() {
+    let copy = parse(&["[", "a", "b", "--owner", "me", "--token", "t0k3n", "]"]).unwrap();
+    assert_eq!(
+        to_string(&copy).unwrap(),
+        "[ a b --force -f --verbose -f --owner me --token '***' ]"
+    );
+    let params = to_raw_params(&copy).unwrap();
+    assert!(params.contains(&"t0k3n".to_string()));
+    let back: Copy =
+        serde_shon::from_args(std::iter::once("./binary".to_string()).chain(params)).unwrap();
+    assert_eq!(back, copy);
+}
+
+#[shon]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Forward {
+    name: String,
+    #[shon(nested)]
+    payload: Vec<Vec<String>>,
+}
+
+#[test]
+fn nested_fields() {
+    let forward = Forward {
+        name: "a".to_string(),
+        payload: vec![vec!["b c".to_string()], vec![]],
+    };
+    let params = to_raw_params(&forward).unwrap();
+    assert_eq!(
+        params,
+        ["[", "--name", "a", "--payload", "[ [ 'b c' ] [ ] ]", "]"]
+    );
+    let options = serde_shon::DeserializerOptions::new().nested_documents(true);
+    let args = std::iter::once("./binary".to_string()).chain(params);
+    let back: Forward = serde_shon::from_args_with(args, &options).unwrap();
+    assert_eq!(back, forward);
+}
+
+#[shon]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Exec {
+    #[shon(positional)]
+    program: String,
+    #[shon(flag)]
+    quiet: bool,
+    #[shon(rest)]
+    args: Vec<String>,
+}
+
+#[test]
+fn rest_after_end_of_options() {
+    use serde_shon::{DeserializerOptions, Dialect, DoubleDash, Serializer};
+
+    let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+    let options = DeserializerOptions::new().dialect(dialect.clone());
+    let parse = |args: &[&str]| -> serde_shon::Result<Exec> {
+        let args = std::iter::once("./binary").chain(args.iter().copied());
+        serde_shon::from_args_with(args.map(String::from), &options)
+    };
+    let exec = parse(&["[", "ls", "--quiet", "]", "--", "-la", "[", "5"]).unwrap();
+    assert_eq!(exec.args, ["-la", "[", "5"]);
+    assert!(exec.quiet);
+    assert_eq!(parse(&["[", "ls", "]"]).unwrap().args, Vec::<String>::new());
+    let args = ["./binary", "[", "ls", "]", "extra", "--", "-la"].map(String::from);
+    let (partial, rest) =
+        serde_shon::from_args_partial_with::<Exec, _>(args.into_iter(), &options).unwrap();
+    assert_eq!(partial.args, ["-la"]);
+    assert_eq!(rest, ["extra"]);
+    let args = ["./binary", "[", "ls", "]", "--", "-la", "5"].map(String::from);
+    let (_, map) = serde_shon::from_args_mapped::<Exec, _>(args.into_iter(), &options).unwrap();
+    assert_eq!(map.get("/program"), Some(2..3));
+    assert_eq!(map.get("/args"), Some(5..7));
+
+    let mut ser = Serializer::new().dialect(dialect.clone());
+    ser.serialize_document(&exec).unwrap();
+    assert_eq!(
+        ser.into_params(),
+        ["[", "ls", "--quiet", "-t", "]", "--", "-la", "[", "5"]
+    );
+
+    // the configured meaning of `--` is checked both ways
+    let err = parse(&["[", "ls", "--quiet", "--", "-t", "]"]).unwrap_err();
+    assert!(err.to_string().contains("within brackets"), "{}", err);
+    let err = serde_shon::from_args_with::<Vec<String>, _>(
+        ["./binary", "[", "a", "]", "--", "b"]
+            .map(String::from)
+            .into_iter(),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("nothing takes"), "{}", err);
+    let exec = Exec {
+        program: "-1".to_string(),
+        quiet: false,
+        args: vec![],
+    };
+    let mut ser = Serializer::new().dialect(dialect);
+    let err = ser.serialize_document(&exec).unwrap_err();
+    assert!(err.to_string().contains("\"-1\""), "{}", err);
+
+    // as a string escape, the rest field is keyed
+    let exec: Exec =
+        from_iter(["[", "ls", "--args", "[", "--", "-x", "]", "]"].into_iter()).unwrap();
+    assert_eq!(exec.args, ["-x"]);
+    assert_eq!(
+        to_raw_params(&exec).unwrap(),
+        ["[", "ls", "--quiet", "-f", "--args", "[", "--", "-x", "]", "]"]
+    );
+    let err = from_iter::<Exec, _>(["[", "ls", "--args", "[", "--"].into_iter()).unwrap_err();
+    assert!(err.to_string().contains("there is none"), "{}", err);
+}
+
+#[test]
+fn rest_after_document() {
+    use serde_shon::{DeserializerOptions, Dialect, DoubleDash, Value};
+
+    let options = DeserializerOptions::new().trailing_rest(true);
+    let args = |args: &[&str]| {
+        let args = std::iter::once("./binary").chain(args.iter().copied());
+        args.map(String::from).collect::<Vec<_>>().into_iter()
+    };
+    let exec: Exec = serde_shon::from_args_with(
+        args(&["[", "ls", "--quiet", "]", "-la", "]", "5"]),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(exec.args, ["-la", "]", "5"]);
+    assert!(exec.quiet);
+    let (_, map) =
+        serde_shon::from_args_mapped::<Exec, _>(args(&["[", "ls", "]", "a", "b"]), &options)
+            .unwrap();
+    assert_eq!(map.get("/args"), Some(4..6));
+    let exec: Exec = serde_shon::from_args_with(args(&["[", "ls", "]"]), &options).unwrap();
+    assert!(exec.args.is_empty());
+
+    // without a rest field, they are left over like they would be otherwise
+    let err = serde_shon::from_args_with::<Vec<String>, _>(args(&["[", "a", "]", "x"]), &options)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "arg 4 `x`: 1 unexpected param after the end of the document: `x`"
+    );
+
+    // nothing after the document is nothing left over, with `--` ending the
+    // options or not
+    let dialect = Dialect::new().double_dash(DoubleDash::EndOfOptions);
+    for options in [options.clone(), options.dialect(dialect)] {
+        let value: Value =
+            serde_shon::from_iter_with(["[", "[--]", "]"].into_iter(), &options).unwrap();
+        assert!(value.as_array().is_some());
+        for args in [
+            &["[", "}", "]"][..],
+            &["[", "B", "--b", "]"],
+            &["[", "x", "}", "-1", "-t", "]"],
+        ] {
+            let _ = serde_shon::from_iter_with::<Value, _>(args.iter().copied(), &options);
+        }
+    }
+}
+
+#[shon]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Listen {
+    #[shon(alias = "-p")]
+    port: u16,
+    #[shon(flag)]
+    tls: bool,
+    max_connections: Option<u32>,
+}
+
+#[shon]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Serve {
+    root: String,
+    #[serde(flatten)]
+    listen: Listen,
+    #[serde(flatten)]
+    extra: std::collections::HashMap<String, serde_shon::Value>,
+}
+
+#[test]
+fn flattened_structs() {
+    use serde_shon::{DeserializerOptions, KeyStyle};
+
+    let args = ["[", "--root", "www", "-p", "80", "--tls", "--x", "1", "]"];
+    let serve: Serve = from_iter(args.into_iter()).unwrap();
+    assert_eq!(serve.listen.port, 80);
+    assert!(serve.listen.tls);
+    assert_eq!(serve.extra.len(), 1);
+    assert_eq!(serve.extra["x"].as_u64(), Some(1));
+    let params = to_raw_params(&serve).unwrap();
+    let args = std::iter::once("./binary".to_string()).chain(params);
+    assert_eq!(serde_shon::from_args::<Serve, _>(args).unwrap(), serve);
+
+    // the options reach the flattened fields too
+    let options = DeserializerOptions::new()
+        .key_style(KeyStyle::Kebab)
+        .negation(true);
+    let args = [
+        "[",
+        "--root",
+        "www",
+        "--port",
+        "80",
+        "--max-connections",
+        "5",
+        "--no-tls",
+        "]",
+    ];
+    let serve: Serve = serde_shon::from_iter_with(args.into_iter(), &options).unwrap();
+    assert_eq!(serve.listen.max_connections, Some(5));
+    assert!(!serve.listen.tls);
+
+    // bare keys need a flag to take them
+    let args = ["[", "--root", "www", "--port", "80", "--x", "]"];
+    let err = from_iter::<Serve, _>(args.into_iter()).unwrap_err();
+    assert_eq!(err.to_string(), "arg 5 `--x`: missing value");
+    let args = ["[", "--root", "www", "--port", "x", "--tls", "]"];
+    let err = from_iter::<Serve, _>(args.into_iter()).unwrap_err();
+    assert_eq!(err.to_string(), "arg 4 `x`: invalid number `x`");
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct PlainListen {
+    port: u16,
+    tls: bool,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct PlainServe {
+    root: String,
+    #[serde(flatten)]
+    listen: PlainListen,
+}
+
+#[test]
+fn flattened_without_shon() {
+    use serde_shon::{DeserializerOptions, Error};
+
+    // the keys reach the flattened struct through what serde buffered
+    let args = ["[", "--root", "www", "--port", "80", "--tls", "-t", "]"];
+    let serve: PlainServe = from_iter(args.into_iter()).unwrap();
+    assert_eq!((serve.listen.port, serve.listen.tls), (80, true));
+    // which doesn't know about flags that aren't given
+    let options = DeserializerOptions::new().flags(true);
+    let args = ["[", "--root", "www", "--port", "80", "]"];
+    let err = serde_shon::from_iter_with::<PlainServe, _>(args.into_iter(), &options).unwrap_err();
+    assert!(
+        matches!(err.inner(), Error::MissingField("tls")),
+        "{:?}",
+        err
+    );
+    #[shon]
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct ShonServe {
+        root: String,
+        #[serde(flatten)]
+        listen: PlainListen,
+    }
+    let serve: ShonServe = serde_shon::from_iter_with(args.into_iter(), &options).unwrap();
+    assert!(!serve.listen.tls);
+}
+
+fn serve_from_words<'de, D>(deserializer: D) -> Result<Serve, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let words = String::deserialize(deserializer)?;
+    let params = words.split(',').map(String::from);
+    serde_shon::from_stream(params).map_err(serde::de::Error::custom)
+}
+
+#[shon]
+#[derive(Debug, Deserialize)]
+struct Site {
+    #[serde(deserialize_with = "serve_from_words")]
+    serve: Serve,
+    #[serde(flatten)]
+    listen: Listen,
+}
+
+#[test]
+fn flattened_in_nested_parse() {
+    // another parse while the object is read doesn't see its params
+    let args = [
+        "[",
+        "--serve",
+        "[,--root,www,--port,80,--tls,-t,]",
+        "--port",
+        "81",
+        "]",
+    ];
+    let site: Site = from_iter(args.into_iter()).unwrap();
+    assert_eq!((site.serve.listen.port, site.listen.port), (80, 81));
+    assert_eq!(site.serve.root, "www");
+}